@@ -1,5 +1,9 @@
-use crate::error::OperationError;
-use crate::git_operations::GitOperations;
+use crate::config::RemoteCleanup;
+use crate::error::{OperationError, RepositoryError};
+use crate::forge::{self, Forge};
+use crate::git_operations::{GitCredentials, GitOperations};
+use crate::index::{IndexManager, ProjectRegistration};
+use crate::manifest::ProjectManifest;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::Mutex as AsyncMutex;
@@ -11,12 +15,12 @@ pub trait Operation: Send + Sync {
     fn description(&self) -> String;
 }
 
-pub struct AtomicOperations {
-    operations: Vec<Box<dyn Operation>>,
+pub struct AtomicOperations<'a> {
+    operations: Vec<Box<dyn Operation + 'a>>,
     atomic: bool,
 }
 
-impl AtomicOperations {
+impl<'a> AtomicOperations<'a> {
     pub fn new(no_atomic: bool) -> Self {
         Self {
             operations: Vec::new(),
@@ -24,7 +28,7 @@ impl AtomicOperations {
         }
     }
     
-    pub fn add_operation(&mut self, operation: Box<dyn Operation>) {
+    pub fn add_operation(&mut self, operation: Box<dyn Operation + 'a>) {
         self.operations.push(operation);
     }
     
@@ -192,6 +196,7 @@ impl Operation for CommitOperation {
 
 pub struct PushOperation {
     repository_path: PathBuf,
+    credentials: Option<GitCredentials>,
     pushed: Arc<AsyncMutex<bool>>,
 }
 
@@ -199,15 +204,22 @@ impl PushOperation {
     pub fn new(repository_path: PathBuf) -> Self {
         Self {
             repository_path,
+            credentials: None,
             pushed: Arc::new(AsyncMutex::new(false)),
         }
     }
+    
+    /// 使用 HTTPS 凭据推送
+    pub fn with_credentials(mut self, credentials: Option<GitCredentials>) -> Self {
+        self.credentials = credentials;
+        self
+    }
 }
 
 #[async_trait::async_trait]
 impl Operation for PushOperation {
     async fn execute(&self) -> Result<(), OperationError> {
-        GitOperations::push(&self.repository_path)?;
+        GitOperations::push_with_credentials(&self.repository_path, self.credentials.as_ref())?;
         let mut pushed = self.pushed.lock().await;
         *pushed = true;
         Ok(())
@@ -232,6 +244,416 @@ impl Operation for PushOperation {
     }
 }
 
+/// 移动文件或目录，回滚时移回
+///
+/// 也用于删除：先移到同一文件系统上的暂存位置，全部操作成功后由调用方删除暂存内容。
+pub struct MovePathOperation {
+    from: PathBuf,
+    to: PathBuf,
+    moved: Arc<AsyncMutex<bool>>,
+}
+
+impl MovePathOperation {
+    pub fn new(from: PathBuf, to: PathBuf) -> Self {
+        Self {
+            from,
+            to,
+            moved: Arc::new(AsyncMutex::new(false)),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Operation for MovePathOperation {
+    async fn execute(&self) -> Result<(), OperationError> {
+        if self.to.exists() {
+            return Err(OperationError::ExecutionFailed {
+                message: format!("{} already exists", self.to.display()),
+            });
+        }
+        if let Some(parent) = self.to.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(&self.from, &self.to)?;
+        *self.moved.lock().await = true;
+        Ok(())
+    }
+    
+    async fn rollback(&self) -> Result<(), OperationError> {
+        let mut moved = self.moved.lock().await;
+        if *moved {
+            std::fs::rename(&self.to, &self.from)?;
+            *moved = false;
+        }
+        Ok(())
+    }
+    
+    fn description(&self) -> String {
+        format!("Move {} to {}", self.from.display(), self.to.display())
+    }
+}
+
+/// 修改忽略规则文件（`.gitignore`、`.git/info/exclude`）中某个目录的规则，回滚时恢复原文件
+///
+/// 只处理恰好匹配该目录的规则（允许前导和结尾的 `/`），文件不存在时什么也不做。
+pub struct IgnoreRuleOperation {
+    file: PathBuf,
+    directory: String,
+    /// 新的目录名，None 表示删除规则
+    replacement: Option<String>,
+    original: Arc<AsyncMutex<Option<String>>>,
+}
+
+impl IgnoreRuleOperation {
+    /// 删除目录的规则
+    pub fn remove(file: PathBuf, directory: String) -> Self {
+        Self {
+            file,
+            directory,
+            replacement: None,
+            original: Arc::new(AsyncMutex::new(None)),
+        }
+    }
+    
+    /// 将目录的规则改为新目录名，保留原规则的前导和结尾 `/`
+    pub fn rename(file: PathBuf, directory: String, new_directory: String) -> Self {
+        Self {
+            replacement: Some(new_directory),
+            ..Self::remove(file, directory)
+        }
+    }
+    
+    fn matches(&self, line: &str) -> bool {
+        line.trim().trim_start_matches('/').trim_end_matches('/') == self.directory.trim_matches('/')
+    }
+    
+    fn rewrite(&self, line: &str, replacement: &str) -> String {
+        let rule = line.trim();
+        let leading = if rule.starts_with('/') { "/" } else { "" };
+        let trailing = if rule.ends_with('/') { "/" } else { "" };
+        format!("{}{}{}", leading, replacement.trim_matches('/'), trailing)
+    }
+}
+
+#[async_trait::async_trait]
+impl Operation for IgnoreRuleOperation {
+    async fn execute(&self) -> Result<(), OperationError> {
+        if !self.file.exists() {
+            return Ok(());
+        }
+        
+        let content = std::fs::read_to_string(&self.file)?;
+        if !content.lines().any(|line| self.matches(line)) {
+            return Ok(());
+        }
+        
+        let lines: Vec<String> = content
+            .lines()
+            .filter_map(|line| match (&self.replacement, self.matches(line)) {
+                (_, false) => Some(line.to_string()),
+                (Some(replacement), true) => Some(self.rewrite(line, replacement)),
+                (None, true) => None,
+            })
+            .collect();
+        let mut updated = lines.join("\n");
+        if !updated.is_empty() {
+            updated.push('\n');
+        }
+        std::fs::write(&self.file, updated)?;
+        *self.original.lock().await = Some(content);
+        Ok(())
+    }
+    
+    async fn rollback(&self) -> Result<(), OperationError> {
+        if let Some(content) = self.original.lock().await.take() {
+            std::fs::write(&self.file, content)?;
+        }
+        Ok(())
+    }
+    
+    fn description(&self) -> String {
+        format!("Update ignore rule for {} in {}", self.directory, self.file.display())
+    }
+}
+
+/// 隐藏仓库注册信息所在的位置
+pub enum RegistrationStore<'a> {
+    /// 全局索引
+    Index(&'a mut IndexManager),
+    /// 父仓库路径，注册信息记录在其本地清单中
+    Manifest(PathBuf),
+}
+
+impl RegistrationStore<'_> {
+    async fn register(&mut self, registration: ProjectRegistration) -> Result<(), OperationError> {
+        match self {
+            RegistrationStore::Index(index) => index
+                .register_project(registration)
+                .await
+                .map_err(RepositoryError::from)?,
+            RegistrationStore::Manifest(project_path) => {
+                let mut manifest = ProjectManifest::load(project_path.as_path())?.unwrap_or_default();
+                manifest.register(registration)?;
+                manifest.save(project_path.as_path())?;
+            }
+        }
+        Ok(())
+    }
+    
+    async fn unregister(&mut self, repository_key: &str) -> Result<ProjectRegistration, OperationError> {
+        let removed = match self {
+            RegistrationStore::Index(index) => index
+                .unregister_project(repository_key)
+                .await
+                .map_err(RepositoryError::from)?,
+            RegistrationStore::Manifest(project_path) => {
+                let mut manifest = ProjectManifest::load(project_path.as_path())?.unwrap_or_default();
+                let removed = manifest.unregister(repository_key);
+                manifest.save(project_path.as_path())?;
+                removed
+            }
+        };
+        removed.ok_or_else(|| OperationError::ExecutionFailed {
+            message: format!("{} is not registered", repository_key),
+        })
+    }
+}
+
+/// 移除隐藏仓库的注册信息，回滚时重新注册
+pub struct UnregisterOperation<'a> {
+    store: AsyncMutex<RegistrationStore<'a>>,
+    repository_key: String,
+    removed: AsyncMutex<Option<ProjectRegistration>>,
+}
+
+impl<'a> UnregisterOperation<'a> {
+    pub fn new(store: RegistrationStore<'a>, repository_key: String) -> Self {
+        Self {
+            store: AsyncMutex::new(store),
+            repository_key,
+            removed: AsyncMutex::new(None),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Operation for UnregisterOperation<'_> {
+    async fn execute(&self) -> Result<(), OperationError> {
+        let removed = self.store.lock().await.unregister(&self.repository_key).await?;
+        *self.removed.lock().await = Some(removed);
+        Ok(())
+    }
+    
+    async fn rollback(&self) -> Result<(), OperationError> {
+        if let Some(registration) = self.removed.lock().await.take() {
+            self.store.lock().await.register(registration).await?;
+        }
+        Ok(())
+    }
+    
+    fn description(&self) -> String {
+        format!("Unregister {}", self.repository_key)
+    }
+}
+
+/// 用新的注册信息替换旧的（Repository Key 可以不同），回滚时恢复旧的注册信息
+pub struct ReplaceRegistrationOperation<'a> {
+    store: AsyncMutex<RegistrationStore<'a>>,
+    /// (旧的 Repository Key, 新的注册信息)
+    replacements: Vec<(String, ProjectRegistration)>,
+    replaced: AsyncMutex<Vec<ProjectRegistration>>,
+}
+
+impl<'a> ReplaceRegistrationOperation<'a> {
+    pub fn new(store: RegistrationStore<'a>, replacements: Vec<(String, ProjectRegistration)>) -> Self {
+        Self {
+            store: AsyncMutex::new(store),
+            replacements,
+            replaced: AsyncMutex::new(Vec::new()),
+        }
+    }
+    
+    /// 按相反顺序恢复已替换的注册信息
+    async fn restore(&self, store: &mut RegistrationStore<'a>, replaced: &mut Vec<ProjectRegistration>) -> Result<(), OperationError> {
+        while let Some(previous) = replaced.pop() {
+            let (_, registration) = &self.replacements[replaced.len()];
+            store.unregister(&registration.repository_key).await?;
+            store.register(previous).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Operation for ReplaceRegistrationOperation<'_> {
+    async fn execute(&self) -> Result<(), OperationError> {
+        let mut store = self.store.lock().await;
+        let mut replaced = self.replaced.lock().await;
+        
+        for (repository_key, registration) in &self.replacements {
+            let result = match store.unregister(repository_key).await {
+                Ok(previous) => match store.register(registration.clone()).await {
+                    Ok(()) => {
+                        replaced.push(previous);
+                        Ok(())
+                    }
+                    Err(e) => store.register(previous).await.and(Err(e)),
+                },
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                self.restore(&mut store, &mut replaced).await?;
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+    
+    async fn rollback(&self) -> Result<(), OperationError> {
+        let mut store = self.store.lock().await;
+        let mut replaced = self.replaced.lock().await;
+        self.restore(&mut store, &mut replaced).await
+    }
+    
+    fn description(&self) -> String {
+        let keys: Vec<&str> = self.replacements.iter().map(|(key, _)| key.as_str()).collect();
+        format!("Re-register {}", keys.join(", "))
+    }
+}
+
+/// 修改仓库的 origin URL，回滚时恢复原 URL
+pub struct SetOriginOperation {
+    repository_path: PathBuf,
+    url: String,
+    previous: Arc<AsyncMutex<Option<String>>>,
+}
+
+impl SetOriginOperation {
+    pub fn new(repository_path: PathBuf, url: String) -> Self {
+        Self {
+            repository_path,
+            url,
+            previous: Arc::new(AsyncMutex::new(None)),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Operation for SetOriginOperation {
+    async fn execute(&self) -> Result<(), OperationError> {
+        let previous = GitOperations::get_remote_origin(&self.repository_path)?;
+        let repo = git2::Repository::open(&self.repository_path)?;
+        repo.remote_set_url("origin", &self.url)?;
+        *self.previous.lock().await = Some(previous);
+        Ok(())
+    }
+    
+    async fn rollback(&self) -> Result<(), OperationError> {
+        if let Some(previous) = self.previous.lock().await.take() {
+            let repo = git2::Repository::open(&self.repository_path)?;
+            repo.remote_set_url("origin", &previous)?;
+        }
+        Ok(())
+    }
+    
+    fn description(&self) -> String {
+        format!("Set origin of {} to {}", self.repository_path.display(), self.url)
+    }
+}
+
+/// 在平台上为远程仓库改名（保留历史），回滚时改回原名
+pub struct RenameRemoteOperation {
+    forge: Box<dyn Forge>,
+    owner: String,
+    repo_name: String,
+    new_name: String,
+    renamed: AsyncMutex<bool>,
+}
+
+impl RenameRemoteOperation {
+    pub fn new(forge: Box<dyn Forge>, owner: String, repo_name: String, new_name: String) -> Self {
+        Self {
+            forge,
+            owner,
+            repo_name,
+            new_name,
+            renamed: AsyncMutex::new(false),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Operation for RenameRemoteOperation {
+    async fn execute(&self) -> Result<(), OperationError> {
+        self.forge.rename_repository(&self.owner, &self.repo_name, &self.new_name).await?;
+        *self.renamed.lock().await = true;
+        Ok(())
+    }
+    
+    async fn rollback(&self) -> Result<(), OperationError> {
+        let mut renamed = self.renamed.lock().await;
+        if *renamed {
+            self.forge.rename_repository(&self.owner, &self.new_name, &self.repo_name).await?;
+            *renamed = false;
+        }
+        Ok(())
+    }
+    
+    fn description(&self) -> String {
+        format!("Rename remote repository {}/{} to {}", self.owner, self.repo_name, self.new_name)
+    }
+}
+
+/// 按 `remote_cleanup` 移除远程隐藏仓库：删除，或改名为 `dot-trash-*`（并归档）
+///
+/// 改名可以在回滚时恢复，删除不能，因此应作为最后一个操作加入。
+pub struct DisposeRemoteOperation {
+    forge: Box<dyn Forge>,
+    owner: String,
+    repo_name: String,
+    mode: RemoteCleanup,
+    /// 已执行的处理：Some(None) 表示已删除，Some(Some(name)) 表示已改名为 name
+    disposed: AsyncMutex<Option<Option<String>>>,
+}
+
+impl DisposeRemoteOperation {
+    pub fn new(forge: Box<dyn Forge>, owner: String, repo_name: String, mode: RemoteCleanup) -> Self {
+        Self {
+            forge,
+            owner,
+            repo_name,
+            mode,
+            disposed: AsyncMutex::new(None),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Operation for DisposeRemoteOperation {
+    async fn execute(&self) -> Result<(), OperationError> {
+        let trash_name = forge::dispose_repository(self.forge.as_ref(), &self.owner, &self.repo_name, self.mode).await?;
+        *self.disposed.lock().await = Some(trash_name);
+        Ok(())
+    }
+    
+    async fn rollback(&self) -> Result<(), OperationError> {
+        match self.disposed.lock().await.take() {
+            Some(Some(trash_name)) => {
+                self.forge.rename_repository(&self.owner, &trash_name, &self.repo_name).await?;
+                Ok(())
+            }
+            Some(None) => Err(OperationError::RollbackFailed {
+                message: format!("Cannot restore deleted repository {}/{}", self.owner, self.repo_name),
+            }),
+            None => Ok(()),
+        }
+    }
+    
+    fn description(&self) -> String {
+        format!("Remove remote repository {}/{}", self.owner, self.repo_name)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -276,14 +698,16 @@ mod tests {
             vec!["nonexistent.txt".to_string()],
         )));
         
-        // 添加一个会失败的操作（提交空的更改）
+        // 添加一个会失败的操作（在不是 git 仓库的目录中提交）
+        let not_a_repo = temp_dir.path().join("not-a-repo");
+        std::fs::create_dir_all(&not_a_repo).unwrap();
         operations.add_operation(Box::new(CommitOperation::new(
-            repo_path.clone(),
+            not_a_repo,
             "test commit".to_string(),
         )));
         
         let result = operations.execute().await;
-        // 应该失败，因为没有文件可以提交
+        // 应该失败，因为提交目标不是 git 仓库
         assert!(result.is_err());
     }
     
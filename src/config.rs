@@ -1,32 +1,299 @@
 use crate::error::ConfigError;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::env;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct DotConfig {
     pub authorized_organizations: Vec<String>,
     pub default_organization: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub github_token: Option<String>,
+    /// 索引存储后端，默认使用组织下的 `.index` 仓库
+    #[serde(default)]
+    pub index: IndexBackend,
+    /// 默认将新的隐藏目录记录到项目本地清单（.git/dot/manifest.json）而不是全局索引
+    #[serde(default)]
+    pub use_manifest: bool,
+    /// 索引加密配置，未配置时索引以明文保存
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_encryption: Option<IndexEncryption>,
+    /// 默认克隆协议，组织配置中的 `protocol` 优先
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clone_protocol: Option<CloneProtocol>,
+    /// 新建隐藏仓库的命名策略，已有项目沿用注册时记录的名称
+    #[serde(default, skip_serializing_if = "RepositoryNaming::is_default")]
+    pub repository_naming: RepositoryNaming,
+    /// 回滚或移除隐藏仓库时如何处理远程仓库
+    #[serde(default, skip_serializing_if = "RemoteCleanup::is_default")]
+    pub remote_cleanup: RemoteCleanup,
+    /// 隐藏文件在父仓库工作区中的呈现方式
+    #[serde(default, skip_serializing_if = "HiddenFileMode::is_default")]
+    pub hidden_file_mode: HiddenFileMode,
+    /// 新建隐藏目录的内容模板（名称 → 本地目录或 git URL），`dot init --template <name>` 使用
+    ///
+    /// ```json
+    /// "templates": {
+    ///   "kiro-default": "~/dot-templates/kiro",
+    ///   "kiro-team": "git@github.com:acme/kiro-template.git"
+    /// }
+    /// ```
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub templates: HashMap<String, String>,
+    /// 各组织的托管平台配置，未配置的组织使用 GitHub
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub organizations: HashMap<String, OrganizationConfig>,
 }
 
-impl Default for DotConfig {
-    fn default() -> Self {
-        Self {
-            authorized_organizations: vec![],
-            default_organization: None,
-            github_token: None,
+/// 远程隐藏仓库的命名策略
+///
+/// ```json
+/// "repository_naming": "slug"
+/// "repository_naming": { "template": "dot-{owner}-{project}-{dir}-{hash}" }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RepositoryNaming {
+    /// `md5(repository_key)`（旧版本的命名）
+    #[default]
+    Md5,
+    /// 截断的 `sha256(repository_key)`
+    Sha256,
+    /// `<project>--<dir>-<hash>`，例如 `app--kiro-1a2b3c4d`
+    Slug,
+    /// 自定义模板，支持 `{host}` `{owner}` `{project}` `{dir}` `{hash}` `{md5}`
+    Template(String),
+}
+
+impl RepositoryNaming {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// 回滚或移除隐藏仓库时对远程仓库的处理方式
+///
+/// 默认直接删除。选择 `archive` 或 `rename` 时，只有本次运行中新建且没有任何推送的
+/// 远程仓库会被直接删除，其余仓库改名为 `dot-trash-<时间戳>-<原名>` 保留。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemoteCleanup {
+    /// 直接删除
+    #[default]
+    Delete,
+    /// 改名并归档（平台不支持归档时只改名）
+    Archive,
+    /// 只改名
+    Rename,
+}
+
+impl RemoteCleanup {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// 隐藏文件（如 `.env`）在父仓库工作区中的呈现方式
+///
+/// 文件本身保存在项目的 `.dot-files` 隐藏仓库中。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HiddenFileMode {
+    /// 指向 `.dot-files` 中文件的符号链接
+    #[default]
+    Symlink,
+    /// 文件副本，`dot add` 时同步回 `.dot-files`（适用于不支持符号链接的工具或系统）
+    Copy,
+}
+
+impl HiddenFileMode {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// 托管隐藏仓库的平台
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeKind {
+    #[default]
+    #[serde(rename = "github")]
+    GitHub,
+    #[serde(rename = "gitlab")]
+    GitLab,
+    #[serde(alias = "forgejo")]
+    Gitea,
+    Bitbucket,
+    /// 本地目录中的裸仓库，不需要任何托管服务
+    Local,
+}
+
+/// 组织配置
+///
+/// ```json
+/// "organizations": {
+///   "my-team": { "forge": "gitlab", "api_base_url": "https://gitlab.example.com/api/v4" },
+///   "corp": { "api_base_url": "https://ghe.example.com/api/v3", "ssh_host": "ssh.ghe.example.com", "protocol": "https" },
+///   "my-ws": { "forge": "bitbucket", "token": "user:app-password" },
+///   "offline": { "forge": "local", "root": "/mnt/nas/dot-remotes" }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrganizationConfig {
+    #[serde(default)]
+    pub forge: ForgeKind,
+    /// API 地址，默认为平台的公共服务地址
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_base_url: Option<String>,
+    /// 访问令牌，未配置时从平台对应的环境变量读取
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// SSH 克隆地址使用的主机，默认从 API 地址推导
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_host: Option<String>,
+    /// HTTPS 克隆地址和网页使用的主机，默认从 API 地址推导
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub https_host: Option<String>,
+    /// 首选克隆协议，默认为 SSH
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<CloneProtocol>,
+    /// 本地平台存放裸仓库的根目录，默认为 ~/dot-remotes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<PathBuf>,
+    /// 组织 API 拒绝创建（403/404）时改为在当前用户账户下创建，默认关闭
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_user_fallback: bool,
+    /// 新建隐藏仓库后应用的设置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository_template: Option<RepositoryTemplate>,
+}
+
+/// 新建隐藏仓库的组织策略，创建后通过平台 API 逐项应用
+///
+/// ```json
+/// "repository_template": {
+///   "visibility": "internal",
+///   "topics": ["dot-hidden"],
+///   "wiki": false, "issues": false, "projects": false,
+///   "default_branch": "main",
+///   "teams": [{ "team": "platform", "permission": "push" }]
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RepositoryTemplate {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<Visibility>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub topics: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wiki: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issues: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub projects: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_branch: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub teams: Vec<TeamAccess>,
+}
+
+/// 隐藏仓库的可见性（始终不公开）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    #[default]
+    Private,
+    /// 企业内部可见（GitHub Enterprise / GitLab）
+    Internal,
+}
+
+/// 授予团队的仓库权限
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TeamAccess {
+    pub team: String,
+    /// pull / triage / push / maintain / admin，默认为 push
+    #[serde(default = "default_team_permission")]
+    pub permission: String,
+}
+
+fn default_team_permission() -> String {
+    "push".to_string()
+}
+
+/// 克隆隐藏仓库和索引仓库使用的协议
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CloneProtocol {
+    #[default]
+    Ssh,
+    Https,
+}
+
+/// 索引加密配置（age / X25519）
+///
+/// ```json
+/// "index_encryption": {
+///   "recipients": ["age1...", "age1..."],
+///   "identity_file": "/home/me/.dot/identity.txt"
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IndexEncryption {
+    /// 可以解密索引的接收者公钥
+    #[serde(default)]
+    pub recipients: Vec<String>,
+    /// 本机私钥文件，默认为 ~/.dot/identity.txt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_file: Option<PathBuf>,
+}
+
+impl IndexEncryption {
+    pub fn identity_path(&self) -> Result<PathBuf, ConfigError> {
+        match &self.identity_file {
+            Some(path) => Ok(path.clone()),
+            None => default_identity_path(),
         }
     }
 }
 
+/// 默认的索引解密私钥文件: ~/.dot/identity.txt
+pub fn default_identity_path() -> Result<PathBuf, ConfigError> {
+    let home = dirs::home_dir().ok_or(ConfigError::HomeDirectoryNotFound)?;
+    Ok(home.join(".dot").join("identity.txt"))
+}
+
+/// 索引存储后端配置
+///
+/// ```json
+/// "index": { "type": "forge" }
+/// "index": { "type": "git", "url": "ssh://git.example.com/team/.index.git" }
+/// "index": { "type": "local", "path": "/home/me/.dot/local-index" }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IndexBackend {
+    /// 组织所在平台上的 `{org}/.index` 仓库（旧配置中的 `github` 同义）
+    #[default]
+    #[serde(alias = "github")]
+    Forge,
+    /// 任意 Git URL（自建服务、本地裸仓库等）
+    Git { url: String },
+    /// 本地目录，不做同步
+    Local { path: PathBuf },
+}
+
 pub struct ConfigManager {
     config_path: PathBuf,
     config: DotConfig,
 }
 
 impl ConfigManager {
+    /// 使用给定的配置创建（不读取磁盘，保存时写入 config_path）
+    pub fn new(config_path: PathBuf, config: DotConfig) -> Self {
+        Self { config_path, config }
+    }
+    
     pub async fn load() -> Result<Self, ConfigError> {
         let config_path = Self::config_file_path()?;
         
@@ -49,10 +316,97 @@ impl ConfigManager {
         self.config.authorized_organizations.contains(&org.to_string())
     }
     
+    pub fn get_authorized_organizations(&self) -> &[String] {
+        &self.config.authorized_organizations
+    }
+    
     pub fn get_default_organization(&self) -> Option<&String> {
         self.config.default_organization.as_ref()
     }
     
+    pub fn get_index_backend(&self) -> &IndexBackend {
+        &self.config.index
+    }
+    
+    pub fn use_manifest(&self) -> bool {
+        self.config.use_manifest
+    }
+    
+    pub fn remote_cleanup(&self) -> RemoteCleanup {
+        self.config.remote_cleanup
+    }
+    
+    pub fn hidden_file_mode(&self) -> HiddenFileMode {
+        self.config.hidden_file_mode
+    }
+    
+    /// 按名称查找隐藏目录模板的来源
+    pub fn template(&self, name: &str) -> Result<&str, ConfigError> {
+        self.config.templates
+            .get(name)
+            .map(String::as_str)
+            .ok_or_else(|| ConfigError::TemplateNotFound(name.to_string()))
+    }
+    
+    pub fn repository_naming(&self) -> &RepositoryNaming {
+        &self.config.repository_naming
+    }
+    
+    pub fn get_index_encryption(&self) -> Option<&IndexEncryption> {
+        self.config.index_encryption.as_ref()
+    }
+    
+    /// 添加索引加密接收者，返回是否有变化
+    pub async fn add_index_recipient(&mut self, recipient: String) -> Result<bool, ConfigError> {
+        let encryption = self.config.index_encryption.get_or_insert_with(IndexEncryption::default);
+        if encryption.recipients.contains(&recipient) {
+            return Ok(false);
+        }
+        encryption.recipients.push(recipient);
+        self.save().await?;
+        Ok(true)
+    }
+    
+    /// 移除索引加密接收者，返回是否有变化
+    pub async fn remove_index_recipient(&mut self, recipient: &str) -> Result<bool, ConfigError> {
+        let Some(encryption) = self.config.index_encryption.as_mut() else {
+            return Ok(false);
+        };
+        let before = encryption.recipients.len();
+        encryption.recipients.retain(|r| r != recipient);
+        if encryption.recipients.len() == before {
+            return Ok(false);
+        }
+        self.save().await?;
+        Ok(true)
+    }
+    
+    /// 获取组织的平台配置（未配置时为 GitHub 默认值），未指定协议时使用全局的 `clone_protocol`
+    pub fn get_organization_config(&self, org: &str) -> OrganizationConfig {
+        let mut org_config = self.config.organizations.get(org).cloned().unwrap_or_default();
+        if org_config.protocol.is_none() {
+            org_config.protocol = self.config.clone_protocol;
+        }
+        org_config
+    }
+    
+    /// 获取组织所在平台的访问令牌（优先使用组织配置，其次使用平台对应的环境变量）
+    pub fn get_forge_token(&self, org: &str) -> Option<String> {
+        let org_config = self.get_organization_config(org);
+        if let Some(token) = org_config.token.filter(|t| !t.is_empty()) {
+            return Some(token);
+        }
+        
+        let env_name = match org_config.forge {
+            ForgeKind::GitHub => return self.get_github_token(),
+            ForgeKind::GitLab => "GITLAB_TOKEN",
+            ForgeKind::Gitea => "GITEA_TOKEN",
+            ForgeKind::Bitbucket => "BITBUCKET_TOKEN",
+            ForgeKind::Local => return None,
+        };
+        env::var(env_name).ok().filter(|t| !t.is_empty())
+    }
+    
     /// 获取 GitHub Token（优先从配置文件，其次从环境变量）
     pub fn get_github_token(&self) -> Option<String> {
         // 优先使用配置文件中的 token
@@ -104,7 +458,7 @@ impl ConfigManager {
         Ok(home.join(".dot").join("dot.conf"))
     }
     
-    async fn ensure_config_dir(config_path: &PathBuf) -> Result<(), ConfigError> {
+    async fn ensure_config_dir(config_path: &Path) -> Result<(), ConfigError> {
         if let Some(parent) = config_path.parent() {
             tokio::fs::create_dir_all(parent).await?;
         }
@@ -131,6 +485,8 @@ mod tests {
         assert!(config.config.authorized_organizations.is_empty());
         assert!(config.config.default_organization.is_none());
         
+        assert_eq!(config.get_index_backend(), &IndexBackend::Forge);
+        
         // 恢复原始 HOME 环境变量
         if let Some(home) = original_home {
             env::set_var("HOME", home);
@@ -167,4 +523,110 @@ mod tests {
             env::remove_var("HOME");
         }
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_index_backend_parsing() {
+        let legacy: DotConfig = serde_json::from_str(
+            r#"{"authorized_organizations": ["org"], "default_organization": "org"}"#
+        ).unwrap();
+        assert_eq!(legacy.index, IndexBackend::Forge);
+        
+        let git: DotConfig = serde_json::from_str(
+            r#"{"authorized_organizations": [], "default_organization": null,
+                "index": {"type": "git", "url": "file:///srv/index.git"}}"#
+        ).unwrap();
+        assert_eq!(git.index, IndexBackend::Git { url: "file:///srv/index.git".to_string() });
+        
+        let local: DotConfig = serde_json::from_str(
+            r#"{"authorized_organizations": [], "default_organization": null,
+                "index": {"type": "local", "path": "/tmp/index"}}"#
+        ).unwrap();
+        assert_eq!(local.index, IndexBackend::Local { path: PathBuf::from("/tmp/index") });
+        
+        let explicit: DotConfig = serde_json::from_str(
+            r#"{"authorized_organizations": [], "default_organization": null,
+                "index": {"type": "github"}}"#
+        ).unwrap();
+        assert_eq!(explicit.index, IndexBackend::Forge);
+    }
+    
+    #[test]
+    fn test_organization_forge_config() {
+        let config: DotConfig = serde_json::from_str(
+            r#"{"authorized_organizations": ["gh", "team", "ws"], "default_organization": "gh",
+                "organizations": {
+                    "team": {"forge": "forgejo", "api_base_url": "https://code.example.com/api/v1", "token": "t0ken"},
+                    "ws": {"forge": "bitbucket"},
+                    "nas": {"forge": "local", "root": "/mnt/nas/dot-remotes"},
+                    "corp": {"repository_template": {
+                        "visibility": "internal", "topics": ["dot-hidden"], "wiki": false,
+                        "default_branch": "trunk", "teams": [{"team": "platform"}]
+                    }}
+                }}"#
+        ).unwrap();
+        let manager = ConfigManager::new(PathBuf::from("/nonexistent/dot.conf"), config);
+        
+        assert_eq!(manager.get_organization_config("gh").forge, ForgeKind::GitHub);
+        let team = manager.get_organization_config("team");
+        assert_eq!(team.forge, ForgeKind::Gitea);
+        assert_eq!(team.api_base_url.as_deref(), Some("https://code.example.com/api/v1"));
+        assert_eq!(manager.get_forge_token("team").as_deref(), Some("t0ken"));
+        assert_eq!(manager.get_organization_config("ws").forge, ForgeKind::Bitbucket);
+        let nas = manager.get_organization_config("nas");
+        assert_eq!(nas.forge, ForgeKind::Local);
+        assert_eq!(nas.root, Some(PathBuf::from("/mnt/nas/dot-remotes")));
+        assert_eq!(manager.get_forge_token("nas"), None);
+        assert_eq!(manager.get_organization_config("gh").protocol, None);
+        
+        let template = manager.get_organization_config("corp").repository_template.unwrap();
+        assert_eq!(template.visibility, Some(Visibility::Internal));
+        assert_eq!(template.wiki, Some(false));
+        assert_eq!(template.issues, None);
+        assert_eq!(template.default_branch.as_deref(), Some("trunk"));
+        assert_eq!(template.teams, vec![TeamAccess { team: "platform".to_string(), permission: "push".to_string() }]);
+    }
+    
+    #[test]
+    fn test_repository_naming_parsing() {
+        let parse = |json: &str| serde_json::from_str::<DotConfig>(json).unwrap().repository_naming;
+        assert_eq!(parse(r#"{"authorized_organizations": []}"#), RepositoryNaming::Md5);
+        assert_eq!(parse(r#"{"authorized_organizations": [], "repository_naming": "slug"}"#), RepositoryNaming::Slug);
+        assert_eq!(
+            parse(r#"{"authorized_organizations": [], "repository_naming": {"template": "dot-{project}-{dir}"}}"#),
+            RepositoryNaming::Template("dot-{project}-{dir}".to_string())
+        );
+    }
+    
+    #[test]
+    fn test_remote_cleanup_defaults_to_delete() {
+        let parse = |json: &str| serde_json::from_str::<DotConfig>(json).unwrap().remote_cleanup;
+        assert_eq!(parse(r#"{"authorized_organizations": []}"#), RemoteCleanup::Delete);
+        assert_eq!(parse(r#"{"authorized_organizations": [], "remote_cleanup": "archive"}"#), RemoteCleanup::Archive);
+        assert_eq!(parse(r#"{"authorized_organizations": [], "remote_cleanup": "rename"}"#), RemoteCleanup::Rename);
+    }
+    
+    #[test]
+    fn test_clone_protocol_resolution() {
+        let config: DotConfig = serde_json::from_str(
+            r#"{"authorized_organizations": ["a", "b"], "default_organization": "a",
+                "clone_protocol": "https",
+                "organizations": {"b": {"protocol": "ssh"}}}"#
+        ).unwrap();
+        let manager = ConfigManager::new(PathBuf::from("/nonexistent/dot.conf"), config);
+        
+        assert_eq!(manager.get_organization_config("a").protocol, Some(CloneProtocol::Https));
+        assert_eq!(manager.get_organization_config("b").protocol, Some(CloneProtocol::Ssh));
+    }
+    
+    #[test]
+    fn test_templates_lookup() {
+        let config: DotConfig = serde_json::from_str(
+            r#"{"authorized_organizations": [],
+                "templates": {"kiro-default": "~/dot-templates/kiro"}}"#
+        ).unwrap();
+        let manager = ConfigManager::new(PathBuf::from("/nonexistent/dot.conf"), config);
+        
+        assert_eq!(manager.template("kiro-default").unwrap(), "~/dot-templates/kiro");
+        assert!(matches!(manager.template("missing"), Err(ConfigError::TemplateNotFound(name)) if name == "missing"));
+    }
+}
@@ -0,0 +1,170 @@
+//! 隐藏仓库的部署密钥
+//!
+//! CI 和服务器使用部署密钥读取隐藏仓库，而不需要个人令牌。GitHub 不允许同一个部署密钥
+//! 添加到多个仓库，因此每个隐藏仓库生成一对 ed25519 密钥，并通过 SSH 主机别名选择密钥。
+
+use crate::error::RepositoryError;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// dot 管理的部署密钥标题前缀，`list` 和 `revoke` 只处理带有该前缀的密钥
+pub const TITLE_PREFIX: &str = "dot:";
+
+/// 部署密钥在平台上的标题，例如 `dot:ci-runner`
+pub fn key_title(name: &str) -> String {
+    format!("{}{}", TITLE_PREFIX, name)
+}
+
+/// 检查密钥名称（用作目录名和标题）
+pub fn validate_name(name: &str) -> Result<(), RepositoryError> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err(RepositoryError::IoError(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Invalid deploy key name '{}': use letters, digits, '-', '_' or '.'", name),
+        )));
+    }
+    Ok(())
+}
+
+/// 密钥保存目录: ~/.dot/deploy-keys/<name>
+pub fn key_dir(name: &str) -> Result<PathBuf, RepositoryError> {
+    let home = dirs::home_dir().ok_or(RepositoryError::IoError(
+        std::io::Error::new(std::io::ErrorKind::NotFound, "Home directory not found")
+    ))?;
+    Ok(home.join(".dot").join("deploy-keys").join(name))
+}
+
+/// 生成 ed25519 密钥对（私钥已存在时复用），返回公钥
+pub fn ensure_key_pair(path: &Path, comment: &str) -> Result<String, RepositoryError> {
+    if !path.exists() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let output = Command::new("ssh-keygen")
+            .args(["-q", "-t", "ed25519", "-N", "", "-C", comment, "-f"])
+            .arg(path)
+            .output()
+            .map_err(|e| RepositoryError::IoError(std::io::Error::new(
+                e.kind(),
+                format!("ssh-keygen is not available: {}", e),
+            )))?;
+        if !output.status.success() {
+            return Err(RepositoryError::IoError(std::io::Error::other(format!(
+                "ssh-keygen failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))));
+        }
+    }
+
+    let public_key = std::fs::read_to_string(public_key_path(path))?;
+    Ok(public_key.trim().to_string())
+}
+
+pub fn public_key_path(path: &Path) -> PathBuf {
+    let mut public = path.as_os_str().to_owned();
+    public.push(".pub");
+    PathBuf::from(public)
+}
+
+/// 删除本地密钥对（不存在时忽略）
+pub fn remove_key_pair(path: &Path) -> Result<(), RepositoryError> {
+    for file in [path.to_path_buf(), public_key_path(path)] {
+        if file.exists() {
+            std::fs::remove_file(file)?;
+        }
+    }
+    Ok(())
+}
+
+/// 公钥是否相同（平台返回的公钥通常去掉了注释）
+pub fn same_key(a: &str, b: &str) -> bool {
+    let material = |key: &str| key.split_whitespace().take(2).collect::<Vec<_>>().join(" ");
+    material(a) == material(b)
+}
+
+/// SSH 配置中的一个隐藏仓库
+#[derive(Debug, Clone, PartialEq)]
+pub struct SshConfigEntry {
+    pub hidden_directory: String,
+    pub host: String,
+    pub owner: String,
+    pub repo_name: String,
+    pub identity_file: PathBuf,
+}
+
+impl SshConfigEntry {
+    /// 主机别名，用于为每个仓库选择对应的密钥
+    pub fn alias(&self) -> String {
+        format!("dot-{}", self.repo_name)
+    }
+
+    /// 通过别名克隆的 URL
+    pub fn clone_url(&self) -> String {
+        format!("git@{}:{}/{}.git", self.alias(), self.owner, self.repo_name)
+    }
+}
+
+/// 生成 `~/.ssh/config` 片段
+pub fn ssh_config_snippet(project: &str, entries: &[SshConfigEntry]) -> String {
+    let mut lines = vec![format!("# dot deploy keys for {}", project)];
+    for entry in entries {
+        lines.push(String::new());
+        lines.push(format!("# {} -> {}", entry.hidden_directory, entry.clone_url()));
+        lines.push(format!("Host {}", entry.alias()));
+        lines.push(format!("    HostName {}", entry.host));
+        lines.push("    User git".to_string());
+        lines.push(format!("    IdentityFile {}", entry.identity_file.display()));
+        lines.push("    IdentitiesOnly yes".to_string());
+    }
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_config_snippet() {
+        let entry = SshConfigEntry {
+            hidden_directory: ".kiro".to_string(),
+            host: "github.com".to_string(),
+            owner: "acme".to_string(),
+            repo_name: "app--kiro-1a2b3c4d".to_string(),
+            identity_file: PathBuf::from("/home/ci/.dot/deploy-keys/ci/app--kiro-1a2b3c4d"),
+        };
+        assert_eq!(entry.clone_url(), "git@dot-app--kiro-1a2b3c4d:acme/app--kiro-1a2b3c4d.git");
+
+        let snippet = ssh_config_snippet("github.com:acme/app", &[entry]);
+        assert!(snippet.contains("Host dot-app--kiro-1a2b3c4d\n    HostName github.com\n    User git\n"));
+        assert!(snippet.contains("IdentityFile /home/ci/.dot/deploy-keys/ci/app--kiro-1a2b3c4d"));
+    }
+
+    #[test]
+    fn test_key_names_and_comparison() {
+        assert_eq!(key_title("ci-runner"), "dot:ci-runner");
+        assert!(validate_name("ci-runner.01").is_ok());
+        assert!(validate_name("../etc").is_err());
+        assert!(validate_name("").is_err());
+        assert!(same_key("ssh-ed25519 AAAAC3Nz dot:ci", "ssh-ed25519 AAAAC3Nz"));
+        assert!(!same_key("ssh-ed25519 AAAAC3Nz", "ssh-ed25519 BBBB"));
+    }
+
+    #[test]
+    fn test_generate_key_pair() {
+        if Command::new("ssh-keygen").arg("-?").output().is_err() {
+            return;
+        }
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("keys").join("abc");
+        let public_key = ensure_key_pair(&path, "dot:test").unwrap();
+        assert!(public_key.starts_with("ssh-ed25519 "));
+        // 已存在时复用
+        assert_eq!(ensure_key_pair(&path, "dot:test").unwrap(), public_key);
+
+        remove_key_pair(&path).unwrap();
+        assert!(!path.exists() && !public_key_path(&path).exists());
+    }
+}
@@ -13,6 +13,12 @@ pub enum ConfigError {
     
     #[error("Organization not authorized in ~/.dot/dot.conf")]
     OrganizationNotAuthorized,
+    
+    #[error("Invalid repository naming template: {0}")]
+    InvalidNamingTemplate(String),
+    
+    #[error("Template '{0}' is not defined in ~/.dot/dot.conf")]
+    TemplateNotFound(String),
 }
 
 #[derive(Error, Debug)]
@@ -23,11 +29,48 @@ pub enum IndexError {
     #[error("Project already exists: {0}")]
     ProjectAlreadyExists(String),
     
+    #[error("Index for organization {0} could not be loaded; fix the index and try again")]
+    OrganizationIndexUnavailable(String),
+    
     #[error("Failed to access index repository: {0}")]
     IoError(#[from] std::io::Error),
     
     #[error("Failed to parse index data: {0}")]
     JsonError(#[from] serde_json::Error),
+    
+    #[error("Index git operation failed: {0}")]
+    GitError(#[from] git2::Error),
+    
+    #[error("Index encryption error: {0}")]
+    Encryption(String),
+    
+    #[error("Configuration error: {0}")]
+    ConfigError(#[from] ConfigError),
+}
+
+/// 托管平台 API 返回的错误
+#[derive(Error, Debug)]
+pub enum ForgeError {
+    #[error("{forge} authentication failed: {message}")]
+    Authentication { forge: String, message: String },
+    
+    #[error("{forge} refused the request (organization policy or missing permission): {message}")]
+    ForbiddenByPolicy { forge: String, message: String },
+    
+    #[error("Repository name is already taken on {forge}: {message}")]
+    NameTaken { forge: String, message: String },
+    
+    #[error("{forge} rejected the request: {message}")]
+    Validation { forge: String, message: String },
+    
+    #[error("Not found on {forge}: {message}")]
+    NotFound { forge: String, message: String },
+    
+    #[error("{forge} rate limit exceeded{}", retry_after.map(|s| format!(", retry after {}s", s)).unwrap_or_default())]
+    RateLimited { forge: String, retry_after: Option<u64> },
+    
+    #[error("{forge} API error ({status}): {message}")]
+    Api { forge: String, status: u16, message: String },
 }
 
 #[derive(Error, Debug)]
@@ -38,6 +81,9 @@ pub enum RepositoryError {
     #[error("Invalid git remote origin URL")]
     InvalidRemoteUrl,
     
+    #[error("Invalid hidden directory '{path}': {reason}")]
+    InvalidHiddenDirectory { path: String, reason: String },
+    
     #[error("Project already exists: {0}")]
     ProjectAlreadyExists(String),
     
@@ -47,6 +93,9 @@ pub enum RepositoryError {
     #[error("IO operation failed: {0}")]
     IoError(#[from] std::io::Error),
     
+    #[error("Failed to parse JSON: {0}")]
+    JsonError(#[from] serde_json::Error),
+    
     #[error("Atomic operation failed")]
     AtomicOperationFailed,
     
@@ -55,6 +104,9 @@ pub enum RepositoryError {
     
     #[error("Index error: {0}")]
     IndexError(#[from] IndexError),
+    
+    #[error("{0}")]
+    Forge(#[from] ForgeError),
 }
 
 #[derive(Error, Debug)]
@@ -0,0 +1,327 @@
+//! 在项目的每个仓库中运行命令（`dot foreach`、`dot git`）
+//!
+//! 与 `git submodule foreach` 类似：`dot foreach` 的命令交给 shell 执行，`dot git` 直接运行 git；
+//! 工作目录为各个仓库，并通过 `DOT_DIR`、`DOT_REPO_KEY`、`DOT_REMOTE` 环境变量提供仓库信息。
+
+use crate::error::RepositoryError;
+use crate::git_operations::GitOperations;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{ExitStatus, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+
+/// 运行命令的仓库
+#[derive(Debug, Clone)]
+pub struct Target {
+    /// 隐藏目录，父仓库为 `.`
+    pub directory: String,
+    pub path: PathBuf,
+    /// Repository Key，父仓库为 base key
+    pub repository_key: String,
+    /// 仓库的远程 URL
+    pub remote: String,
+}
+
+/// 在每个仓库中运行的命令
+#[derive(Debug, Clone)]
+pub enum Invocation {
+    /// 交给 shell 执行的命令行
+    Shell(String),
+    /// git 及其参数
+    Git(Vec<String>),
+}
+
+impl Invocation {
+    fn command(&self, target: &Target) -> Command {
+        let mut command = match self {
+            Invocation::Shell(line) => shell(line),
+            Invocation::Git(args) => {
+                let mut git = Command::new("git");
+                git.args(args);
+                git
+            }
+        };
+        command
+            .current_dir(&target.path)
+            .env("DOT_DIR", &target.directory)
+            .env("DOT_REPO_KEY", &target.repository_key)
+            .env("DOT_REMOTE", &target.remote);
+        command
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ForeachOptions {
+    /// 同时运行的命令数，1 为依次运行并直接输出
+    pub parallel: usize,
+    /// 命令失败后继续在其余仓库中运行
+    pub continue_on_error: bool,
+    /// 每行输出加上 `[目录]` 前缀（总是在命令结束后按仓库整体打印）
+    pub prefix_output: bool,
+}
+
+/// 单个仓库中命令的结果（无法启动时为错误信息）
+#[derive(Debug)]
+pub struct Outcome {
+    pub directory: String,
+    pub result: Result<ExitStatus, String>,
+}
+
+impl Outcome {
+    pub fn success(&self) -> bool {
+        matches!(&self.result, Ok(status) if status.success())
+    }
+}
+
+/// 在各个仓库中运行命令，返回已运行的命令的结果（按仓库顺序）
+///
+/// 没有 `continue_on_error` 时，第一个失败之后不再启动新的命令。
+pub async fn run(targets: Vec<Target>, invocation: &Invocation, options: ForeachOptions) -> Vec<Outcome> {
+    if options.parallel <= 1 && !options.prefix_output {
+        run_sequential(targets, invocation, options.continue_on_error).await
+    } else {
+        run_parallel(targets, invocation, options).await
+    }
+}
+
+async fn run_sequential(targets: Vec<Target>, invocation: &Invocation, continue_on_error: bool) -> Vec<Outcome> {
+    let mut outcomes = Vec::new();
+    for target in targets {
+        println!("Entering '{}'", target.directory);
+        let result = invocation.command(&target)
+            .status()
+            .await
+            .map_err(|e| e.to_string());
+        let outcome = Outcome { directory: target.directory, result };
+        let failed = !outcome.success();
+        outcomes.push(outcome);
+        if failed && !continue_on_error {
+            break;
+        }
+    }
+    outcomes
+}
+
+/// 捕获输出运行（可以并行），每个仓库的输出在命令结束后整体打印，不会互相穿插
+async fn run_parallel(targets: Vec<Target>, invocation: &Invocation, options: ForeachOptions) -> Vec<Outcome> {
+    let semaphore = Arc::new(Semaphore::new(options.parallel.max(1)));
+    let stop = Arc::new(AtomicBool::new(false));
+    let print_lock = Arc::new(Mutex::new(()));
+    let mut tasks = tokio::task::JoinSet::new();
+
+    for (position, target) in targets.into_iter().enumerate() {
+        let Ok(permit) = semaphore.clone().acquire_owned().await else {
+            break;
+        };
+        if stop.load(Ordering::SeqCst) {
+            break;
+        }
+
+        let mut child = invocation.command(&target);
+        child.stdin(Stdio::null());
+        let stop = stop.clone();
+        let print_lock = print_lock.clone();
+        tasks.spawn(async move {
+            let output = child.output().await;
+            drop(permit);
+
+            let result = match output {
+                Ok(output) => {
+                    let _guard = print_lock.lock().unwrap_or_else(|e| e.into_inner());
+                    print_output(&target.directory, &output, options.prefix_output);
+                    Ok(output.status)
+                }
+                Err(e) => Err(e.to_string()),
+            };
+            let outcome = Outcome { directory: target.directory, result };
+            if !outcome.success() && !options.continue_on_error {
+                stop.store(true, Ordering::SeqCst);
+            }
+            (position, outcome)
+        });
+    }
+
+    let mut outcomes = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok(outcome) = joined {
+            outcomes.push(outcome);
+        }
+    }
+    outcomes.sort_by_key(|(position, _)| *position);
+    outcomes.into_iter().map(|(_, outcome)| outcome).collect()
+}
+
+fn print_output(directory: &str, output: &Output, prefix: bool) {
+    if !prefix {
+        println!("Entering '{}'", directory);
+        let _ = std::io::stdout().write_all(&output.stdout);
+        let _ = std::io::stderr().write_all(&output.stderr);
+        return;
+    }
+
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        println!("[{}] {}", directory, line);
+    }
+    for line in String::from_utf8_lossy(&output.stderr).lines() {
+        eprintln!("[{}] {}", directory, line);
+    }
+}
+
+/// 通过 shell 执行命令行
+fn shell(command: &str) -> Command {
+    #[cfg(windows)]
+    let (program, flag) = ("cmd", "/C");
+    #[cfg(not(windows))]
+    let (program, flag) = ("sh", "-c");
+
+    let mut shell = Command::new(program);
+    shell.args([flag, command]);
+    shell
+}
+
+/// 只保留 `only` 中列出的仓库（`.` 为父仓库），为空时保留全部
+///
+/// 列出的目录不在 `targets` 中时返回错误。
+pub fn select(targets: Vec<Target>, only: &[String]) -> Result<Vec<Target>, RepositoryError> {
+    if only.is_empty() {
+        return Ok(targets);
+    }
+
+    let mut selected = Vec::new();
+    for directory in only {
+        let directory = match directory.as_str() {
+            "." => ".".to_string(),
+            directory => GitOperations::normalize_hidden_path(directory)?,
+        };
+        if !selected.iter().any(|t: &Target| t.directory == directory) {
+            let target = targets
+                .iter()
+                .find(|t| t.directory == directory)
+                .ok_or_else(|| RepositoryError::IoError(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("{} is not a checked-out hidden directory", directory),
+                )))?;
+            selected.push(target.clone());
+        }
+    }
+    // 保持父仓库在前、隐藏仓库按目录排序的顺序
+    selected.sort_by_key(|t| targets.iter().position(|other| other.directory == t.directory));
+    Ok(selected)
+}
+
+/// 汇总退出状态：全部成功时返回 Ok，否则返回列出失败仓库的错误
+///
+/// `total` 为计划运行的仓库数，因前面的失败而没有运行的仓库计为跳过。
+pub fn summarize(outcomes: &[Outcome], total: usize) -> Result<(), RepositoryError> {
+    let failures: Vec<String> = outcomes
+        .iter()
+        .filter(|outcome| !outcome.success())
+        .map(|outcome| match &outcome.result {
+            Ok(status) => match status.code() {
+                Some(code) => format!("{} (exit code {})", outcome.directory, code),
+                None => format!("{} ({})", outcome.directory, status),
+            },
+            Err(e) => format!("{} ({})", outcome.directory, e),
+        })
+        .collect();
+    if failures.is_empty() {
+        return Ok(());
+    }
+
+    let mut message = format!("{} of {} commands failed: {}", failures.len(), total, failures.join(", "));
+    let skipped = total - outcomes.len();
+    if skipped > 0 {
+        message.push_str(&format!("; {} skipped (use --continue-on-error to run them)", skipped));
+    }
+    Err(RepositoryError::IoError(std::io::Error::other(message)))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn targets(temp_dir: &TempDir, directories: &[&str]) -> Vec<Target> {
+        directories
+            .iter()
+            .map(|directory| {
+                let path = temp_dir.path().join(directory);
+                std::fs::create_dir_all(&path).unwrap();
+                Target {
+                    directory: directory.to_string(),
+                    path,
+                    repository_key: format!("github.com:user/repo/{}", directory),
+                    remote: format!("git@github.com:acme/{}.git", directory),
+                }
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_sequential_stops_at_first_failure() {
+        let temp_dir = TempDir::new().unwrap();
+        let targets = targets(&temp_dir, &["a", "b", "c"]);
+        let command = r#"echo "$DOT_DIR $DOT_REPO_KEY $DOT_REMOTE" > out.txt; test "$DOT_DIR" != b"#;
+        let options = ForeachOptions { parallel: 1, continue_on_error: false, prefix_output: false };
+
+        let outcomes = run(targets, &Invocation::Shell(command.to_string()), options).await;
+        assert_eq!(outcomes.len(), 2);
+        assert!(outcomes[0].success());
+        assert!(!outcomes[1].success());
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("a").join("out.txt")).unwrap(),
+            "a github.com:user/repo/a git@github.com:acme/a.git\n"
+        );
+        assert!(!temp_dir.path().join("c").join("out.txt").exists());
+
+        let error = summarize(&outcomes, 3).unwrap_err().to_string();
+        assert!(error.contains("1 of 3 commands failed: b (exit code 1); 1 skipped"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_parallel_continue_on_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let targets = targets(&temp_dir, &["a", "b", "c", "d"]);
+        let command = r#"touch ran; case "$DOT_DIR" in b) exit 3;; d) exit 4;; esac"#;
+        let options = ForeachOptions { parallel: 2, continue_on_error: true, prefix_output: false };
+
+        let outcomes = run(targets, &Invocation::Shell(command.to_string()), options).await;
+        let directories: Vec<&str> = outcomes.iter().map(|o| o.directory.as_str()).collect();
+        assert_eq!(directories, vec!["a", "b", "c", "d"]);
+        for directory in ["a", "b", "c", "d"] {
+            assert!(temp_dir.path().join(directory).join("ran").exists());
+        }
+
+        let error = summarize(&outcomes, 4).unwrap_err().to_string();
+        assert!(error.ends_with("2 of 4 commands failed: b (exit code 3), d (exit code 4)"), "{}", error);
+        assert!(summarize(&outcomes[..1], 1).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_git_with_prefixed_output() {
+        let temp_dir = TempDir::new().unwrap();
+        let targets = targets(&temp_dir, &[".", ".kiro", "tools/.cache"]);
+        GitOperations::init_repository(temp_dir.path().join(".kiro")).unwrap();
+        GitOperations::init_repository(temp_dir.path().join("tools/.cache")).unwrap();
+
+        // 只选择的仓库按原来的顺序运行
+        let selected = select(targets.clone(), &["./tools/.cache/".to_string(), ".kiro".to_string()]).unwrap();
+        let directories: Vec<&str> = selected.iter().map(|t| t.directory.as_str()).collect();
+        assert_eq!(directories, vec![".kiro", "tools/.cache"]);
+        assert!(select(targets.clone(), &[".cursor".to_string()]).is_err());
+        assert_eq!(select(targets.clone(), &[]).unwrap().len(), 3);
+
+        let invocation = Invocation::Git(vec!["rev-parse".to_string(), "--git-dir".to_string()]);
+        let options = ForeachOptions { parallel: 1, continue_on_error: true, prefix_output: true };
+        let outcomes = run(selected, &invocation, options).await;
+        assert!(outcomes.iter().all(Outcome::success));
+
+        // 目录本身不是仓库时 git 在父目录中查找，这里 temp_dir 不是 git 仓库
+        let outcomes = run(select(targets, &[".".to_string()]).unwrap(), &invocation, options).await;
+        let error = summarize(&outcomes, 1).unwrap_err().to_string();
+        assert!(error.ends_with("1 of 1 commands failed: . (exit code 128)"), "{}", error);
+    }
+}
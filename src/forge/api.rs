@@ -0,0 +1,624 @@
+use super::request_error;
+use crate::error::{ForgeError, RepositoryError};
+use reqwest::header::{HeaderMap, AUTHORIZATION, ETAG, IF_NONE_MATCH, LINK, RETRY_AFTER};
+use reqwest::{RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 单次等待的上限，超过时直接返回限流响应而不是长时间阻塞
+const MAX_WAIT: Duration = Duration::from_secs(60);
+const MAX_RETRIES: u32 = 3;
+/// ETag 缓存的条目上限，超过时丢弃最早写入的条目
+const MAX_CACHED_RESPONSES: usize = 256;
+
+/// 托管平台 HTTP API 的共享客户端
+///
+/// 所有平台共用一个连接池，并统一处理：
+/// - 限流：遇到 429 或 `X-RateLimit-Remaining: 0` 时按 `Retry-After` / `X-RateLimit-Reset` 等待后重试
+/// - 条件请求：GET 请求缓存 ETag，命中 304 时返回缓存的内容（GitHub 不计入限流额度）；
+///   缓存保存在 `~/.dot/cache/api-etags.json` 中，后续命令也能使用
+/// - 分页：跟随 `Link: <...>; rel="next"` 读取所有页
+pub struct ApiClient {
+    client: reqwest::Client,
+    etags: Mutex<EtagCache>,
+}
+
+/// ETag 缓存；指定文件时每次变化都写回文件，供之后的进程使用
+#[derive(Default)]
+struct EtagCache {
+    path: Option<PathBuf>,
+    entries: HashMap<String, CachedResponse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedResponse {
+    etag: String,
+    body: String,
+    /// 写入时间（Unix 秒），用于淘汰旧条目
+    updated: u64,
+}
+
+impl EtagCache {
+    /// 读取缓存文件，文件不存在或无法解析时从空缓存开始
+    fn open(path: PathBuf) -> Self {
+        let entries = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { path: Some(path), entries }
+    }
+
+    fn get(&self, key: &str) -> Option<CachedResponse> {
+        self.entries.get(key).cloned()
+    }
+
+    /// 缓存响应体（只缓存 UTF-8 文本，API 返回的都是 JSON）
+    fn insert(&mut self, key: String, etag: &str, body: &[u8]) {
+        let Ok(body) = String::from_utf8(body.to_vec()) else {
+            return self.remove(&key);
+        };
+        let updated = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        self.entries.insert(key, CachedResponse { etag: etag.to_string(), body, updated });
+        while self.entries.len() > MAX_CACHED_RESPONSES {
+            let Some(oldest) = self.entries.iter().min_by_key(|(_, e)| e.updated).map(|(k, _)| k.clone()) else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+        self.persist();
+    }
+
+    fn remove(&mut self, key: &str) {
+        if self.entries.remove(key).is_some() {
+            self.persist();
+        }
+    }
+
+    /// 写回缓存文件；缓存只是优化，写入失败时忽略
+    fn persist(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Ok(content) = serde_json::to_vec(&self.entries) {
+            let _ = write_private(path, &content);
+        }
+    }
+}
+
+/// 先写临时文件再改名，避免并发的命令读到写了一半的文件；响应体包含私有仓库信息，只允许本人读取
+fn write_private(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let temp = path.with_extension(format!("tmp.{}", std::process::id()));
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(&temp)?.write_all(content)?;
+    std::fs::rename(&temp, path)
+}
+
+/// 已读取完整响应体的 API 响应
+#[derive(Debug)]
+pub struct ApiResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl ApiResponse {
+    pub fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T, RepositoryError> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+
+    /// 将错误响应解析为具体的错误类型
+    ///
+    /// 解析平台返回的结构化错误（GitHub/Gitea 的 `message` + `errors`、GitLab 的字段错误、
+    /// Bitbucket 的 `error.message`），而不是在响应文本中匹配子串。
+    pub fn error(&self, forge: &str) -> ForgeError {
+        let body: ErrorBody = serde_json::from_slice(&self.body).unwrap_or_default();
+        let message = body.describe().unwrap_or_else(|| self.text());
+        let forge = forge.to_string();
+
+        if let Some(delay) = rate_limit_delay(self.status, &self.headers, SystemTime::now()) {
+            return ForgeError::RateLimited { forge, retry_after: Some(delay.as_secs()) };
+        }
+
+        match self.status {
+            StatusCode::UNAUTHORIZED => ForgeError::Authentication { forge, message },
+            StatusCode::FORBIDDEN => ForgeError::ForbiddenByPolicy { forge, message },
+            StatusCode::NOT_FOUND => ForgeError::NotFound { forge, message },
+            StatusCode::CONFLICT => ForgeError::NameTaken { forge, message },
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => {
+                if body.name_taken() {
+                    ForgeError::NameTaken { forge, message }
+                } else {
+                    ForgeError::Validation { forge, message }
+                }
+            }
+            status => ForgeError::Api { forge, status: status.as_u16(), message },
+        }
+    }
+
+    /// `Link` 头中 rel="next" 的地址
+    pub fn next_page(&self) -> Option<String> {
+        let link = self.headers.get(LINK)?.to_str().ok()?;
+        parse_next_link(link)
+    }
+}
+
+impl ApiClient {
+    /// ETag 只缓存在内存中的客户端
+    pub fn new() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            etags: Mutex::new(EtagCache::default()),
+        }
+    }
+
+    /// ETag 缓存保存在 `path` 中的客户端
+    pub fn with_cache_file(path: PathBuf) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            etags: Mutex::new(EtagCache::open(path)),
+        }
+    }
+
+    /// 进程内共享的客户端，ETag 缓存保存在 `~/.dot/cache/api-etags.json`（测试中只在内存中）
+    pub fn shared() -> &'static ApiClient {
+        static CLIENT: OnceLock<ApiClient> = OnceLock::new();
+        CLIENT.get_or_init(|| match dirs::home_dir() {
+            Some(home) if !cfg!(test) => {
+                ApiClient::with_cache_file(home.join(".dot").join("cache").join("api-etags.json"))
+            }
+            _ => ApiClient::new(),
+        })
+    }
+
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// 发送请求，遇到限流时等待后重试
+    pub async fn send(&self, request: RequestBuilder) -> Result<ApiResponse, RepositoryError> {
+        let mut attempt = 0;
+        loop {
+            let current = request.try_clone().ok_or_else(|| {
+                RepositoryError::IoError(std::io::Error::other("request body cannot be retried"))
+            })?;
+            let response = current.send().await.map_err(request_error)?;
+            let status = response.status();
+            let headers = response.headers().clone();
+
+            if attempt < MAX_RETRIES {
+                if let Some(delay) = rate_limit_delay(status, &headers, SystemTime::now()) {
+                    if delay <= MAX_WAIT {
+                        attempt += 1;
+                        eprintln!("  Rate limited ({}), retrying in {}s...", status, delay.as_secs());
+                        tokio::time::sleep(delay).await;
+                        continue;
+                    }
+                }
+            }
+
+            let body = response.bytes().await.map_err(request_error)?.to_vec();
+            return Ok(ApiResponse { status, headers, body });
+        }
+    }
+
+    /// 条件 GET：带上缓存的 ETag，304 时返回缓存的响应体
+    ///
+    /// 缓存按认证身份和 URL 区分，不同令牌不会拿到彼此的响应。收到 304 但没有对应的
+    /// 缓存内容时（例如缓存文件被其他命令改写），去掉 `If-None-Match` 重新请求。
+    pub async fn get_cached(&self, request: RequestBuilder) -> Result<ApiResponse, RepositoryError> {
+        let unconditional = request.try_clone();
+        let key = unconditional
+            .as_ref()
+            .and_then(|r| r.try_clone())
+            .and_then(|r| r.build().ok())
+            .map(|r| cache_key(&r));
+
+        let cached = key.as_ref().and_then(|key| self.etags.lock().unwrap().get(key));
+        let request = match &cached {
+            Some(cached) => request.header(IF_NONE_MATCH, &cached.etag),
+            None => request,
+        };
+
+        let mut response = self.send(request).await?;
+        if response.status == StatusCode::NOT_MODIFIED {
+            match (cached, unconditional) {
+                (Some(cached), _) => {
+                    response.status = StatusCode::OK;
+                    response.body = cached.body.into_bytes();
+                    return Ok(response);
+                }
+                (None, Some(unconditional)) => response = self.send(unconditional).await?,
+                (None, None) => return Ok(response),
+            }
+        }
+
+        if let Some(key) = key.filter(|_| response.status.is_success()) {
+            let mut etags = self.etags.lock().unwrap();
+            match response.headers.get(ETAG).and_then(|v| v.to_str().ok()) {
+                Some(etag) => etags.insert(key, etag, &response.body),
+                None => etags.remove(&key),
+            }
+        }
+        Ok(response)
+    }
+
+    /// 读取所有分页，`make_request` 根据 URL 构造请求（添加认证头等），错误按 `forge` 报告
+    pub async fn get_all_pages<T, F>(&self, forge: &str, first_url: String, make_request: F) -> Result<Vec<T>, RepositoryError>
+    where
+        T: serde::de::DeserializeOwned,
+        F: Fn(&str) -> Result<RequestBuilder, RepositoryError>,
+    {
+        let mut items = Vec::new();
+        let mut next = Some(first_url);
+        while let Some(url) = next {
+            let response = self.send(make_request(&url)?).await?;
+            if !response.status.is_success() {
+                return Err(response.error(forge).into());
+            }
+            items.extend(response.json::<Vec<T>>()?);
+            next = response.next_page();
+        }
+        Ok(items)
+    }
+}
+
+/// ETag 缓存的键：认证头（GitHub/Gitea/Bitbucket 的 `Authorization`、GitLab 的
+/// `PRIVATE-TOKEN`）的 SHA-256 加 URL，缓存文件中只保存令牌的哈希
+fn cache_key(request: &reqwest::Request) -> String {
+    let mut hasher = Sha256::new();
+    for name in [AUTHORIZATION.as_str(), "private-token"] {
+        for value in request.headers().get_all(name) {
+            hasher.update(value.as_bytes());
+            hasher.update(b"\n");
+        }
+    }
+    let digest = hasher.finalize();
+    let hash: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{} {}", hash, request.url())
+}
+
+/// 各平台错误响应体的并集
+#[derive(Debug, Default, serde::Deserialize)]
+struct ErrorBody {
+    /// GitHub/Gitea 为字符串；GitLab 的校验错误为 `{字段: [错误...]}`
+    #[serde(default)]
+    message: Option<serde_json::Value>,
+    /// GitHub 的详细错误列表
+    #[serde(default)]
+    errors: Vec<ErrorDetail>,
+    /// GitLab 为字符串，Bitbucket 为 `{ "message": ... }`
+    #[serde(default)]
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+struct ErrorDetail {
+    #[serde(default)]
+    field: Option<String>,
+    #[serde(default)]
+    code: Option<String>,
+    #[serde(default)]
+    message: Option<String>,
+}
+
+impl ErrorBody {
+    fn describe(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        match &self.message {
+            Some(serde_json::Value::String(message)) => parts.push(message.clone()),
+            Some(serde_json::Value::Object(fields)) => {
+                for (field, errors) in fields {
+                    let errors = match errors {
+                        serde_json::Value::Array(items) => items
+                            .iter()
+                            .filter_map(|e| e.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        other => other.to_string(),
+                    };
+                    parts.push(format!("{} {}", field, errors));
+                }
+            }
+            _ => {}
+        }
+        match &self.error {
+            Some(serde_json::Value::String(error)) => parts.push(error.clone()),
+            Some(serde_json::Value::Object(error)) => {
+                if let Some(message) = error.get("message").and_then(|m| m.as_str()) {
+                    parts.push(message.to_string());
+                }
+            }
+            _ => {}
+        }
+        parts.extend(self.errors.iter().filter_map(|e| e.message.clone()));
+        (!parts.is_empty()).then(|| parts.join(": "))
+    }
+
+    /// 是否为“名称已被占用”的校验错误
+    fn name_taken(&self) -> bool {
+        // GitHub: {"errors": [{"resource": "Repository", "code": "custom", "field": "name",
+        //                      "message": "name already exists on this account"}]}
+        let github = self.errors.iter().any(|e| {
+            e.code.as_deref() == Some("already_exists")
+                || (e.field.as_deref() == Some("name")
+                    && e.message.as_deref().is_some_and(|m| m.contains("already exists")))
+        });
+        // GitLab: {"message": {"name": ["has already been taken"], "path": ["has already been taken"]}}
+        let gitlab = match &self.message {
+            Some(serde_json::Value::Object(fields)) => ["name", "path"].iter().any(|field| {
+                fields.get(*field)
+                    .and_then(|errors| errors.as_array())
+                    .is_some_and(|errors| errors.iter().any(|e| e.as_str() == Some("has already been taken")))
+            }),
+            _ => false,
+        };
+        // Bitbucket: {"error": {"message": "Repository with this Slug and Owner already exists."}}
+        let bitbucket = match &self.error {
+            Some(serde_json::Value::Object(error)) => error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .is_some_and(|m| m.ends_with("already exists.")),
+            _ => false,
+        };
+        github || gitlab || bitbucket
+    }
+}
+
+impl Default for ApiClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 根据限流响应头计算需要等待的时间，不是限流响应时返回 None
+fn rate_limit_delay(status: StatusCode, headers: &HeaderMap, now: SystemTime) -> Option<Duration> {
+    if status != StatusCode::TOO_MANY_REQUESTS && status != StatusCode::FORBIDDEN {
+        return None;
+    }
+
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    if let Some(seconds) = header(RETRY_AFTER.as_str()).and_then(|v| v.trim().parse::<u64>().ok()) {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    if header("x-ratelimit-remaining") == Some("0") {
+        let reset = header("x-ratelimit-reset")?.trim().parse::<u64>().ok()?;
+        let now = now.duration_since(UNIX_EPOCH).ok()?.as_secs();
+        return Some(Duration::from_secs(reset.saturating_sub(now) + 1));
+    }
+
+    // 没有限流头的 429 使用固定退避；403 可能是真正的权限错误
+    (status == StatusCode::TOO_MANY_REQUESTS).then(|| Duration::from_secs(1))
+}
+
+/// 解析 `Link` 头，例如 `<https://api.github.com/orgs/x/repos?page=2>; rel="next", <...>; rel="last"`
+fn parse_next_link(link: &str) -> Option<String> {
+    link.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        let is_next = params
+            .split(';')
+            .any(|p| matches!(p.trim(), "rel=\"next\"" | "rel=next"));
+        is_next.then(|| url.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_parse_next_link() {
+        let link = r#"<https://api.github.com/orgs/acme/repos?page=2>; rel="next", <https://api.github.com/orgs/acme/repos?page=5>; rel="last""#;
+        assert_eq!(parse_next_link(link).as_deref(), Some("https://api.github.com/orgs/acme/repos?page=2"));
+        assert_eq!(parse_next_link(r#"<https://x/?page=1>; rel="prev""#), None);
+    }
+
+    #[test]
+    fn test_rate_limit_delay() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_000);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("0"));
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("1010"));
+        assert_eq!(rate_limit_delay(StatusCode::FORBIDDEN, &headers, now), Some(Duration::from_secs(11)));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        assert_eq!(rate_limit_delay(StatusCode::TOO_MANY_REQUESTS, &headers, now), Some(Duration::from_secs(7)));
+
+        // 普通的 403（权限不足）不重试
+        assert_eq!(rate_limit_delay(StatusCode::FORBIDDEN, &HeaderMap::new(), now), None);
+        assert_eq!(rate_limit_delay(StatusCode::OK, &headers, now), None);
+    }
+
+    fn response(status: u16, body: serde_json::Value) -> ApiResponse {
+        ApiResponse {
+            status: StatusCode::from_u16(status).unwrap(),
+            headers: HeaderMap::new(),
+            body: serde_json::to_vec(&body).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_error_classification() {
+        let taken = response(422, serde_json::json!({
+            "message": "Repository creation failed.",
+            "errors": [{"resource": "Repository", "code": "custom", "field": "name",
+                        "message": "name already exists on this account"}]
+        }));
+        assert!(matches!(taken.error("GitHub"), ForgeError::NameTaken { .. }));
+
+        let invalid = response(422, serde_json::json!({
+            "message": "Validation Failed",
+            "errors": [{"resource": "Repository", "code": "invalid", "field": "name"}]
+        }));
+        assert!(matches!(invalid.error("GitHub"), ForgeError::Validation { .. }));
+
+        let gitlab = response(400, serde_json::json!({"message": {"path": ["has already been taken"]}}));
+        assert!(matches!(gitlab.error("GitLab"), ForgeError::NameTaken { .. }));
+
+        let policy = response(403, serde_json::json!({"message": "Members cannot create private repositories"}));
+        match policy.error("GitHub") {
+            ForgeError::ForbiddenByPolicy { message, .. } => assert!(message.contains("private repositories")),
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        assert!(matches!(response(401, serde_json::json!({"message": "Bad credentials"})).error("GitHub"),
+            ForgeError::Authentication { .. }));
+        assert!(matches!(response(404, serde_json::json!({})).error("GitHub"), ForgeError::NotFound { .. }));
+
+        let mut limited = response(403, serde_json::json!({"message": "API rate limit exceeded"}));
+        limited.headers.insert("x-ratelimit-remaining", HeaderValue::from_static("0"));
+        limited.headers.insert("x-ratelimit-reset", HeaderValue::from_static("0"));
+        assert!(matches!(limited.error("GitHub"), ForgeError::RateLimited { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_retries_after_rate_limit() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/user"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+            .up_to_n_times(1)
+            .mount(&server).await;
+        Mock::given(method("GET")).and(path("/user"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .mount(&server).await;
+
+        let api = ApiClient::new();
+        let response = api.send(api.client().get(format!("{}/user", server.uri()))).await.unwrap();
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.text(), "ok");
+    }
+
+    #[tokio::test]
+    async fn test_conditional_request_uses_cached_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/repos/acme/abc")).and(header("If-None-Match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .expect(1)
+            .mount(&server).await;
+        Mock::given(method("GET")).and(path("/repos/acme/abc"))
+            .respond_with(ResponseTemplate::new(200).insert_header("ETag", "\"v1\"").set_body_string("{}"))
+            .up_to_n_times(1)
+            .mount(&server).await;
+
+        let api = ApiClient::new();
+        let url = format!("{}/repos/acme/abc", server.uri());
+        let first = api.get_cached(api.client().get(&url)).await.unwrap();
+        let second = api.get_cached(api.client().get(&url)).await.unwrap();
+        assert_eq!(second.status, StatusCode::OK);
+        assert_eq!(second.body, first.body);
+    }
+
+    #[tokio::test]
+    async fn test_conditional_cache_survives_across_clients() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/user")).and(header("If-None-Match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .expect(1)
+            .mount(&server).await;
+        Mock::given(method("GET")).and(path("/user"))
+            .respond_with(ResponseTemplate::new(200).insert_header("ETag", "\"v1\"").set_body_string("{\"login\":\"alice\"}"))
+            .expect(1)
+            .mount(&server).await;
+
+        // 每个 dot 命令是一个新进程，缓存通过文件传递
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let cache_file = temp_dir.path().join("cache").join("api-etags.json");
+        let url = format!("{}/user", server.uri());
+        let first = ApiClient::with_cache_file(cache_file.clone());
+        first.get_cached(first.client().get(&url).bearer_auth("token")).await.unwrap();
+        assert!(!std::fs::read_to_string(&cache_file).unwrap().contains("token"));
+
+        let second = ApiClient::with_cache_file(cache_file);
+        let response = second.get_cached(second.client().get(&url).bearer_auth("token")).await.unwrap();
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.text(), "{\"login\":\"alice\"}");
+    }
+
+    #[tokio::test]
+    async fn test_not_modified_without_cached_body_is_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/user"))
+            .respond_with(ResponseTemplate::new(304))
+            .up_to_n_times(1)
+            .mount(&server).await;
+        Mock::given(method("GET")).and(path("/user"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .mount(&server).await;
+
+        let api = ApiClient::new();
+        let response = api.get_cached(api.client().get(format!("{}/user", server.uri()))).await.unwrap();
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.text(), "ok");
+    }
+
+    #[tokio::test]
+    async fn test_get_all_pages() {
+        let server = MockServer::start().await;
+        let next = format!("<{}/items?page=2>; rel=\"next\"", server.uri());
+        Mock::given(method("GET")).and(path("/items")).and(wiremock::matchers::query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([3])))
+            .mount(&server).await;
+        Mock::given(method("GET")).and(path("/items"))
+            .respond_with(ResponseTemplate::new(200).insert_header("Link", next.as_str()).set_body_json(serde_json::json!([1, 2])))
+            .mount(&server).await;
+
+        let api = ApiClient::new();
+        let items: Vec<u32> = api
+            .get_all_pages("Gitea", format!("{}/items", server.uri()), |url| Ok(api.client().get(url)))
+            .await
+            .unwrap();
+        assert_eq!(items, vec![1, 2, 3]);
+
+        // 错误使用调用方的平台名
+        let error = api
+            .get_all_pages::<u32, _>("Gitea", format!("{}/missing", server.uri()), |url| Ok(api.client().get(url)))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Gitea"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_conditional_cache_is_per_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/repos/acme/abc")).and(header("If-None-Match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .expect(1)
+            .mount(&server).await;
+        Mock::given(method("GET")).and(path("/repos/acme/abc"))
+            .respond_with(ResponseTemplate::new(200).insert_header("ETag", "\"v1\"").set_body_string("{}"))
+            .expect(2)
+            .mount(&server).await;
+
+        let api = ApiClient::new();
+        let url = format!("{}/repos/acme/abc", server.uri());
+        let request = |token: &str| api.client().get(&url).bearer_auth(token);
+        api.get_cached(request("alice")).await.unwrap();
+        // 另一个令牌不使用 alice 的缓存
+        api.get_cached(request("bob")).await.unwrap();
+        api.get_cached(request("alice")).await.unwrap();
+    }
+}
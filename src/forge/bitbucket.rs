@@ -0,0 +1,323 @@
+use super::{api_error, missing_token, ApiClient, CreatedRepository, DeployKey, Forge, RemoteHosts, RemoteRepository};
+use crate::error::{ForgeError, RepositoryError};
+use crate::git_operations::GitCredentials;
+use serde::{Deserialize, Serialize};
+
+pub(crate) const DEFAULT_API_BASE: &str = "https://api.bitbucket.org/2.0";
+
+/// Bitbucket Cloud API 客户端
+///
+/// 组织对应 Bitbucket 的 workspace。令牌为 `username:app_password` 时使用 Basic 认证，
+/// 否则作为 Bearer 访问令牌使用。
+pub struct BitbucketForge {
+    token: Option<String>,
+    api_base: String,
+    hosts: RemoteHosts,
+    api: &'static ApiClient,
+}
+
+#[derive(Debug, Serialize)]
+struct CreateRepoRequest<'a> {
+    scm: &'a str,
+    is_private: bool,
+    description: &'a str,
+}
+
+#[derive(Deserialize)]
+struct RepoResponse {
+    slug: String,
+    workspace: Workspace,
+    description: Option<String>,
+    is_private: bool,
+}
+
+#[derive(Deserialize)]
+struct Workspace {
+    slug: String,
+}
+
+#[derive(Deserialize)]
+struct DeployKeyResponse {
+    id: u64,
+    #[serde(default)]
+    label: String,
+    key: String,
+}
+
+#[derive(Deserialize)]
+struct DeployKeyPage {
+    values: Vec<DeployKeyResponse>,
+}
+
+impl From<DeployKeyResponse> for DeployKey {
+    fn from(key: DeployKeyResponse) -> Self {
+        Self {
+            id: key.id.to_string(),
+            title: key.label,
+            key: key.key,
+            // Bitbucket 的部署密钥只能读取
+            read_only: true,
+        }
+    }
+}
+
+impl BitbucketForge {
+    pub fn new(token: Option<String>, api_base: Option<String>) -> Self {
+        let api_base = api_base
+            .unwrap_or_else(|| DEFAULT_API_BASE.to_string())
+            .trim_end_matches('/')
+            .to_string();
+        Self {
+            token,
+            hosts: RemoteHosts::from_api_base(&api_base),
+            api_base,
+            api: ApiClient::shared(),
+        }
+    }
+
+    /// 使用指定的克隆主机和协议
+    pub fn with_hosts(mut self, hosts: RemoteHosts) -> Self {
+        self.hosts = hosts;
+        self
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> Result<reqwest::RequestBuilder, RepositoryError> {
+        let token = self.token.as_ref().ok_or_else(|| missing_token("Bitbucket"))?;
+        let builder = self.api.client()
+            .request(method, format!("{}{}", self.api_base, path))
+            .header("Accept", "application/json")
+            .header("User-Agent", "dot-cli");
+
+        Ok(match token.split_once(':') {
+            Some((username, app_password)) => builder.basic_auth(username, Some(app_password)),
+            None => builder.bearer_auth(token),
+        })
+    }
+
+    fn repository_path(workspace: &str, repo_name: &str) -> String {
+        format!("/repositories/{}/{}", workspace, repo_name.to_lowercase())
+    }
+
+    /// 发送请求并返回 JSON 响应（空响应为 Null）
+    async fn call(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value, RepositoryError> {
+        let mut request = self.request(method, path)?;
+        if let Some(body) = &body {
+            request = request.json(body);
+        }
+        let response = self.api.send(request).await?;
+        if !response.status.is_success() {
+            return Err(api_error("Bitbucket", &response));
+        }
+        if response.body.is_empty() {
+            return Ok(serde_json::Value::Null);
+        }
+        response.json()
+    }
+}
+
+#[async_trait::async_trait]
+impl Forge for BitbucketForge {
+    fn name(&self) -> &'static str {
+        "Bitbucket"
+    }
+
+    async fn create_repository(
+        &self,
+        owner: &str,
+        repo_name: &str,
+        description: &str,
+    ) -> Result<CreatedRepository, RepositoryError> {
+        let request_body = CreateRepoRequest {
+            scm: "git",
+            is_private: true,
+            description,
+        };
+
+        let request = self
+            .request(reqwest::Method::POST, &Self::repository_path(owner, repo_name))?
+            .json(&request_body);
+        let response = self.api.send(request).await?;
+
+        let status = response.status;
+        if status.is_success() {
+            return Ok(CreatedRepository { owner: owner.to_string(), url: self.clone_url(owner, repo_name) });
+        }
+
+        Err(api_error("Bitbucket", &response))
+    }
+
+    async fn delete_repository(&self, owner: &str, repo_name: &str) -> Result<(), RepositoryError> {
+        let request = self.request(reqwest::Method::DELETE, &Self::repository_path(owner, repo_name))?;
+        let response = self.api.send(request).await?;
+
+        let status = response.status;
+        if status.is_success() || status.as_u16() == 404 {
+            return Ok(());
+        }
+
+        Err(api_error("Bitbucket", &response))
+    }
+
+    /// Bitbucket 根据新名称生成新的 slug
+    async fn rename_repository(&self, owner: &str, repo_name: &str, new_name: &str) -> Result<(), RepositoryError> {
+        let request = self
+            .request(reqwest::Method::PUT, &Self::repository_path(owner, repo_name))?
+            .json(&serde_json::json!({ "name": new_name }));
+        let response = self.api.send(request).await?;
+        if response.status.is_success() {
+            return Ok(());
+        }
+        Err(api_error("Bitbucket", &response))
+    }
+
+    async fn get_repository(&self, owner: &str, repo_name: &str) -> Result<Option<RemoteRepository>, RepositoryError> {
+        let request = self.request(reqwest::Method::GET, &Self::repository_path(owner, repo_name))?;
+        let response = self.api.send(request).await?;
+
+        let status = response.status;
+        if status.as_u16() == 404 {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(api_error("Bitbucket", &response));
+        }
+
+        let repo: RepoResponse = response.json()?;
+        Ok(Some(RemoteRepository {
+            owner: repo.workspace.slug,
+            name: repo.slug,
+            description: repo.description.filter(|d| !d.is_empty()),
+            private: repo.is_private,
+            topics: Vec::new(),
+        }))
+    }
+
+    async fn authenticated_user(&self) -> Result<String, RepositoryError> {
+        let request = self.request(reqwest::Method::GET, "/user")?;
+        let response = self.api.send(request).await?;
+
+        let status = response.status;
+        if !status.is_success() {
+            return Err(api_error("Bitbucket", &response));
+        }
+
+        #[derive(Deserialize)]
+        struct User {
+            username: String,
+        }
+
+        let user: User = response.json()?;
+        Ok(user.username)
+    }
+
+    fn clone_url(&self, owner: &str, repo_name: &str) -> String {
+        self.hosts.url(owner, &repo_name.to_lowercase())
+    }
+
+    async fn add_deploy_key(
+        &self,
+        owner: &str,
+        repo_name: &str,
+        title: &str,
+        public_key: &str,
+        read_only: bool,
+    ) -> Result<DeployKey, RepositoryError> {
+        if !read_only {
+            return Err(ForgeError::Validation {
+                forge: self.name().to_string(),
+                message: "deploy keys are read-only on Bitbucket".to_string(),
+            }
+            .into());
+        }
+        let body = serde_json::json!({ "label": title, "key": public_key });
+        let path = format!("{}/deploy-keys", Self::repository_path(owner, repo_name));
+        let key = self.call(reqwest::Method::POST, &path, Some(body)).await?;
+        Ok(serde_json::from_value::<DeployKeyResponse>(key)?.into())
+    }
+
+    async fn list_deploy_keys(&self, owner: &str, repo_name: &str) -> Result<Vec<DeployKey>, RepositoryError> {
+        let path = format!("{}/deploy-keys", Self::repository_path(owner, repo_name));
+        let page: DeployKeyPage = serde_json::from_value(self.call(reqwest::Method::GET, &path, None).await?)?;
+        Ok(page.values.into_iter().map(DeployKey::from).collect())
+    }
+
+    async fn delete_deploy_key(&self, owner: &str, repo_name: &str, id: &str) -> Result<(), RepositoryError> {
+        let path = format!("{}/deploy-keys/{}", Self::repository_path(owner, repo_name), id);
+        self.call(reqwest::Method::DELETE, &path, None).await?;
+        Ok(())
+    }
+
+    fn ssh_host(&self) -> Option<String> {
+        Some(self.hosts.ssh_host.clone())
+    }
+
+    fn credentials(&self) -> Option<GitCredentials> {
+        let token = self.token.as_ref()?;
+        Some(match token.split_once(':') {
+            Some((username, app_password)) => GitCredentials::new(&self.hosts.https_host, username, app_password),
+            None => GitCredentials::new(&self.hosts.https_host, "x-token-auth", token),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_create_repository_with_app_password() {
+        let server = MockServer::start().await;
+        // alice:secret
+        Mock::given(method("POST")).and(path("/repositories/acme/abc"))
+            .and(header("Authorization", "Basic YWxpY2U6c2VjcmV0"))
+            .and(body_partial_json(serde_json::json!({"scm": "git", "is_private": true})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"slug": "abc"})))
+            .expect(1)
+            .mount(&server).await;
+
+        let forge = BitbucketForge::new(Some("alice:secret".to_string()), Some(server.uri()));
+        let url = forge.create_repository("acme", "abc", "Hidden repository").await.unwrap().url;
+        assert_eq!(url, "git@127.0.0.1:acme/abc.git");
+    }
+
+    #[tokio::test]
+    async fn test_get_repository_with_bearer_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/repositories/acme/abc"))
+            .and(header("Authorization", "Bearer test-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "slug": "abc",
+                "workspace": {"slug": "acme"},
+                "description": "Hidden repository",
+                "is_private": true
+            })))
+            .mount(&server).await;
+
+        let forge = BitbucketForge::new(Some("test-token".to_string()), Some(server.uri()));
+        let repo = forge.get_repository("acme", "abc").await.unwrap().unwrap();
+        assert_eq!(repo.owner, "acme");
+        assert_eq!(repo.description.as_deref(), Some("Hidden repository"));
+        assert!(!forge.repository_exists("acme", "missing").await.unwrap());
+    }
+
+    #[test]
+    fn test_app_password_credentials() {
+        let forge = BitbucketForge::new(Some("alice:secret".to_string()), None);
+        let credentials = forge.credentials().unwrap();
+        assert_eq!(credentials.username, "alice");
+        assert_eq!(credentials.token, "secret");
+    }
+
+    #[test]
+    fn test_default_clone_url() {
+        let forge = BitbucketForge::new(None, None);
+        assert_eq!(forge.clone_url("acme", ".index"), "git@bitbucket.org:acme/.index.git");
+    }
+}
@@ -0,0 +1,355 @@
+use super::{api_error, missing_token, ApiClient, CreatedRepository, DeployKey, Forge, RemoteHosts, RemoteRepository, RepositoryStep};
+use crate::config::Visibility;
+use crate::error::{ForgeError, RepositoryError};
+use crate::git_operations::GitCredentials;
+use serde::{Deserialize, Serialize};
+
+pub(crate) const DEFAULT_API_BASE: &str = "https://gitea.com/api/v1";
+
+/// Gitea / Forgejo API 客户端
+pub struct GiteaForge {
+    token: Option<String>,
+    api_base: String,
+    hosts: RemoteHosts,
+    api: &'static ApiClient,
+}
+
+#[derive(Debug, Serialize)]
+struct CreateRepoRequest<'a> {
+    name: &'a str,
+    description: &'a str,
+    private: bool,
+    auto_init: bool,
+}
+
+#[derive(Deserialize)]
+struct RepoResponse {
+    name: String,
+    owner: Owner,
+    description: Option<String>,
+    private: bool,
+    #[serde(default)]
+    topics: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Owner {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct KeyResponse {
+    id: u64,
+    title: String,
+    key: String,
+    #[serde(default)]
+    read_only: bool,
+}
+
+impl From<KeyResponse> for DeployKey {
+    fn from(key: KeyResponse) -> Self {
+        Self {
+            id: key.id.to_string(),
+            title: key.title,
+            key: key.key,
+            read_only: key.read_only,
+        }
+    }
+}
+
+impl GiteaForge {
+    pub fn new(token: Option<String>, api_base: Option<String>) -> Self {
+        let api_base = api_base
+            .unwrap_or_else(|| DEFAULT_API_BASE.to_string())
+            .trim_end_matches('/')
+            .to_string();
+        Self {
+            token,
+            hosts: RemoteHosts::from_api_base(&api_base),
+            api_base,
+            api: ApiClient::shared(),
+        }
+    }
+
+    /// 使用指定的克隆主机和协议
+    pub fn with_hosts(mut self, hosts: RemoteHosts) -> Self {
+        self.hosts = hosts;
+        self
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> Result<reqwest::RequestBuilder, RepositoryError> {
+        let token = self.token.as_ref().ok_or_else(|| missing_token("Gitea"))?;
+        Ok(self.api.client()
+            .request(method, format!("{}{}", self.api_base, path))
+            .header("Authorization", format!("token {}", token))
+            .header("Accept", "application/json")
+            .header("User-Agent", "dot-cli"))
+    }
+}
+
+impl GiteaForge {
+    /// 发送请求并返回 JSON 响应（空响应为 Null）
+    async fn call(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value, RepositoryError> {
+        let mut request = self.request(method, path)?;
+        if let Some(body) = &body {
+            request = request.json(body);
+        }
+        let response = self.api.send(request).await?;
+        if !response.status.is_success() {
+            return Err(api_error("Gitea", &response));
+        }
+        if response.body.is_empty() {
+            return Ok(serde_json::Value::Null);
+        }
+        response.json()
+    }
+}
+
+#[async_trait::async_trait]
+impl Forge for GiteaForge {
+    fn name(&self) -> &'static str {
+        "Gitea"
+    }
+
+    async fn create_repository(
+        &self,
+        owner: &str,
+        repo_name: &str,
+        description: &str,
+    ) -> Result<CreatedRepository, RepositoryError> {
+        // 个人账户使用用户 API，否则使用组织 API
+        let current_user = self.authenticated_user().await?;
+        let path = if owner.eq_ignore_ascii_case(&current_user) {
+            "/user/repos".to_string()
+        } else {
+            format!("/orgs/{}/repos", owner)
+        };
+
+        let request_body = CreateRepoRequest {
+            name: repo_name,
+            description,
+            private: true,
+            auto_init: true,
+        };
+
+        let request = self
+            .request(reqwest::Method::POST, &path)?
+            .json(&request_body);
+        let response = self.api.send(request).await?;
+
+        if response.status.is_success() {
+            return Ok(CreatedRepository { owner: owner.to_string(), url: self.clone_url(owner, repo_name) });
+        }
+
+        // 409 表示仓库已存在
+        Err(api_error("Gitea", &response))
+    }
+
+    async fn delete_repository(&self, owner: &str, repo_name: &str) -> Result<(), RepositoryError> {
+        let request = self.request(reqwest::Method::DELETE, &format!("/repos/{}/{}", owner, repo_name))?;
+        let response = self.api.send(request).await?;
+
+        let status = response.status;
+        if status.is_success() || status.as_u16() == 404 {
+            return Ok(());
+        }
+
+        Err(api_error("Gitea", &response))
+    }
+
+    async fn rename_repository(&self, owner: &str, repo_name: &str, new_name: &str) -> Result<(), RepositoryError> {
+        let body = serde_json::json!({ "name": new_name });
+        self.call(reqwest::Method::PATCH, &format!("/repos/{}/{}", owner, repo_name), Some(body)).await?;
+        Ok(())
+    }
+
+    async fn archive_repository(&self, owner: &str, repo_name: &str) -> Result<(), RepositoryError> {
+        let body = serde_json::json!({ "archived": true });
+        self.call(reqwest::Method::PATCH, &format!("/repos/{}/{}", owner, repo_name), Some(body)).await?;
+        Ok(())
+    }
+
+    async fn get_repository(&self, owner: &str, repo_name: &str) -> Result<Option<RemoteRepository>, RepositoryError> {
+        let request = self.request(reqwest::Method::GET, &format!("/repos/{}/{}", owner, repo_name))?;
+        let response = self.api.send(request).await?;
+
+        let status = response.status;
+        if status.as_u16() == 404 {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(api_error("Gitea", &response));
+        }
+
+        let repo: RepoResponse = response.json()?;
+        Ok(Some(RemoteRepository {
+            owner: repo.owner.login,
+            name: repo.name,
+            description: repo.description.filter(|d| !d.is_empty()),
+            private: repo.private,
+            topics: repo.topics,
+        }))
+    }
+
+    async fn authenticated_user(&self) -> Result<String, RepositoryError> {
+        let request = self.request(reqwest::Method::GET, "/user")?;
+        let response = self.api.send(request).await?;
+
+        let status = response.status;
+        if !status.is_success() {
+            return Err(api_error("Gitea", &response));
+        }
+
+        #[derive(Deserialize)]
+        struct User {
+            login: String,
+        }
+
+        let user: User = response.json()?;
+        Ok(user.login)
+    }
+
+    fn clone_url(&self, owner: &str, repo_name: &str) -> String {
+        self.hosts.url(owner, repo_name)
+    }
+
+    async fn add_deploy_key(
+        &self,
+        owner: &str,
+        repo_name: &str,
+        title: &str,
+        public_key: &str,
+        read_only: bool,
+    ) -> Result<DeployKey, RepositoryError> {
+        let body = serde_json::json!({ "title": title, "key": public_key, "read_only": read_only });
+        let key = self.call(reqwest::Method::POST, &format!("/repos/{}/{}/keys", owner, repo_name), Some(body)).await?;
+        Ok(serde_json::from_value::<KeyResponse>(key)?.into())
+    }
+
+    async fn list_deploy_keys(&self, owner: &str, repo_name: &str) -> Result<Vec<DeployKey>, RepositoryError> {
+        let keys = self.call(reqwest::Method::GET, &format!("/repos/{}/{}/keys", owner, repo_name), None).await?;
+        let keys: Vec<KeyResponse> = serde_json::from_value(keys)?;
+        Ok(keys.into_iter().map(DeployKey::from).collect())
+    }
+
+    async fn delete_deploy_key(&self, owner: &str, repo_name: &str, id: &str) -> Result<(), RepositoryError> {
+        self.call(reqwest::Method::DELETE, &format!("/repos/{}/{}/keys/{}", owner, repo_name, id), None).await?;
+        Ok(())
+    }
+
+    fn ssh_host(&self) -> Option<String> {
+        Some(self.hosts.ssh_host.clone())
+    }
+
+    async fn apply_step(&self, owner: &str, repo_name: &str, step: &RepositoryStep) -> Result<(), RepositoryError> {
+        let repo_path = format!("/repos/{}/{}", owner, repo_name);
+        match step {
+            RepositoryStep::Settings { visibility, wiki, issues, projects } => {
+                if *visibility == Some(Visibility::Internal) {
+                    return Err(ForgeError::Validation {
+                        forge: self.name().to_string(),
+                        message: "internal visibility is not available for repositories".to_string(),
+                    }
+                    .into());
+                }
+                let mut settings = serde_json::Map::new();
+                if visibility.is_some() {
+                    settings.insert("private".to_string(), true.into());
+                }
+                for (field, value) in [("has_wiki", wiki), ("has_issues", issues), ("has_projects", projects)] {
+                    if let Some(value) = value {
+                        settings.insert(field.to_string(), (*value).into());
+                    }
+                }
+                self.call(reqwest::Method::PATCH, &repo_path, Some(settings.into())).await?;
+            }
+            RepositoryStep::Topics(topics) => {
+                let body = serde_json::json!({ "topics": topics });
+                self.call(reqwest::Method::PUT, &format!("{}/topics", repo_path), Some(body)).await?;
+            }
+            RepositoryStep::DefaultBranch(branch) => {
+                let repo = self.call(reqwest::Method::GET, &repo_path, None).await?;
+                let current = repo["default_branch"].as_str().unwrap_or_default();
+                if current != branch {
+                    let body = serde_json::json!({ "new_branch_name": branch, "old_branch_name": current });
+                    self.call(reqwest::Method::POST, &format!("{}/branches", repo_path), Some(body)).await?;
+                    let body = serde_json::json!({ "default_branch": branch });
+                    self.call(reqwest::Method::PATCH, &repo_path, Some(body)).await?;
+                }
+            }
+            // Gitea 的权限由团队本身决定
+            RepositoryStep::TeamAccess { team, .. } => {
+                self.call(reqwest::Method::PUT, &format!("{}/teams/{}", repo_path, team), None).await?;
+            }
+        }
+        Ok(())
+    }
+
+    fn credentials(&self) -> Option<GitCredentials> {
+        self.token.as_ref().map(|token| GitCredentials::new(&self.hosts.https_host, "oauth2", token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn mock_user(server: &MockServer, login: &str) {
+        Mock::given(method("GET")).and(path("/user"))
+            .and(header("Authorization", "token test-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"login": login})))
+            .mount(server).await;
+    }
+
+    #[tokio::test]
+    async fn test_create_repository_in_organization() {
+        let server = MockServer::start().await;
+        mock_user(&server, "alice").await;
+        Mock::given(method("POST")).and(path("/orgs/acme/repos"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({"name": "abc"})))
+            .expect(1)
+            .mount(&server).await;
+
+        let forge = GiteaForge::new(Some("test-token".to_string()), Some(server.uri()));
+        let url = forge.create_repository("acme", "abc", "Hidden repository").await.unwrap().url;
+        assert_eq!(url, "git@127.0.0.1:acme/abc.git");
+    }
+
+    #[tokio::test]
+    async fn test_create_repository_for_user() {
+        let server = MockServer::start().await;
+        mock_user(&server, "alice").await;
+        Mock::given(method("POST")).and(path("/user/repos"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({"name": "abc"})))
+            .expect(1)
+            .mount(&server).await;
+
+        let forge = GiteaForge::new(Some("test-token".to_string()), Some(server.uri()));
+        forge.create_repository("alice", "abc", "Hidden repository").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_repository() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/repos/acme/abc"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "name": "abc",
+                "owner": {"login": "acme"},
+                "description": "",
+                "private": true
+            })))
+            .mount(&server).await;
+
+        let forge = GiteaForge::new(Some("test-token".to_string()), Some(server.uri()));
+        let repo = forge.get_repository("acme", "abc").await.unwrap().unwrap();
+        assert_eq!(repo.description, None);
+        assert!(!forge.repository_exists("acme", "missing").await.unwrap());
+    }
+}
@@ -0,0 +1,972 @@
+use super::{api_error, ApiClient, ApiResponse, CreatedRepository, DeployKey, Forge, RemoteHosts, RemoteRepository, RepositoryStep};
+use crate::config::Visibility;
+use crate::error::{ForgeError, RepositoryError};
+use crate::git_operations::GitCredentials;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+pub(crate) const DEFAULT_API_BASE: &str = "https://api.github.com";
+
+/// GitHub API 客户端
+pub struct GitHubForge {
+    token: Option<String>,
+    api_base: String,
+    hosts: RemoteHosts,
+    api: &'static ApiClient,
+    /// 组织 API 拒绝时是否在当前用户账户下创建
+    user_fallback: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct CreateRepoRequest {
+    name: String,
+    description: String,
+    private: bool,
+    auto_init: bool,
+}
+
+#[derive(Deserialize)]
+struct RepoResponse {
+    name: String,
+    owner: Owner,
+    description: Option<String>,
+    private: bool,
+    #[serde(default)]
+    topics: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Owner {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct KeyResponse {
+    id: u64,
+    title: String,
+    key: String,
+    #[serde(default)]
+    read_only: bool,
+}
+
+impl From<KeyResponse> for DeployKey {
+    fn from(key: KeyResponse) -> Self {
+        Self {
+            id: key.id.to_string(),
+            title: key.title,
+            key: key.key,
+            read_only: key.read_only,
+        }
+    }
+}
+
+impl GitHubForge {
+    pub fn new(token: Option<String>, api_base: Option<String>) -> Self {
+        let api_base = api_base
+            .unwrap_or_else(|| DEFAULT_API_BASE.to_string())
+            .trim_end_matches('/')
+            .to_string();
+        Self {
+            token,
+            hosts: RemoteHosts::from_api_base(&api_base),
+            api_base,
+            api: ApiClient::shared(),
+            user_fallback: false,
+        }
+    }
+
+    /// 使用指定的克隆主机和协议
+    pub fn with_hosts(mut self, hosts: RemoteHosts) -> Self {
+        self.hosts = hosts;
+        self
+    }
+
+    /// 组织 API 返回 403/404 时改为在当前用户账户下创建仓库
+    pub fn with_user_fallback(mut self, enabled: bool) -> Self {
+        self.user_fallback = enabled;
+        self
+    }
+
+    pub fn hosts(&self) -> &RemoteHosts {
+        &self.hosts
+    }
+
+    /// gh CLI 命令（GitHub Enterprise 通过 GH_HOST 指定主机）
+    fn gh(&self) -> Command {
+        let mut command = Command::new("gh");
+        if self.hosts.https_host != "github.com" {
+            command.env("GH_HOST", &self.hosts.https_host);
+        }
+        command
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str, token: &str) -> reqwest::RequestBuilder {
+        self.request_url(method, &format!("{}{}", self.api_base, path), token)
+    }
+
+    /// 使用完整 URL 构造请求（分页时 `Link` 头给出的是完整地址）
+    fn request_url(&self, method: reqwest::Method, url: &str, token: &str) -> reqwest::RequestBuilder {
+        self.api.client()
+            .request(method, url)
+            .header("Authorization", format!("Bearer {}", token))
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            .header("User-Agent", "dot-cli")
+    }
+
+    /// 调用 API 并返回 JSON 响应（有 token 时直接请求，否则通过 `gh api`）
+    async fn call(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value, RepositoryError> {
+        let Some(token) = &self.token else {
+            return self.call_via_gh_cli(method, path, body);
+        };
+
+        let mut request = self.request(method, path, token);
+        if let Some(body) = &body {
+            request = request.json(body);
+        }
+        let response = self.api.send(request).await?;
+        if !response.status.is_success() {
+            return Err(api_error("GitHub", &response));
+        }
+        if response.body.is_empty() {
+            return Ok(serde_json::Value::Null);
+        }
+        response.json()
+    }
+
+    fn call_via_gh_cli(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value, RepositoryError> {
+        let mut command = self.gh();
+        command
+            .args(["api", "-X", method.as_str(), path.trim_start_matches('/')])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if body.is_some() {
+            command.args(["--input", "-"]).stdin(Stdio::piped());
+        }
+
+        let mut child = command.spawn()?;
+        if let (Some(body), Some(mut stdin)) = (body, child.stdin.take()) {
+            stdin.write_all(&serde_json::to_vec(&body)?)?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(RepositoryError::IoError(std::io::Error::other(
+                format!("gh CLI error: {}", String::from_utf8_lossy(&output.stderr).trim())
+            )));
+        }
+        if output.stdout.iter().all(u8::is_ascii_whitespace) {
+            return Ok(serde_json::Value::Null);
+        }
+        Ok(serde_json::from_slice(&output.stdout)?)
+    }
+
+    /// 使用 GitHub API 创建仓库
+    async fn create_repo_via_api(
+        &self,
+        org: &str,
+        repo_name: &str,
+        description: &str,
+        token: &str,
+    ) -> Result<CreatedRepository, RepositoryError> {
+        // 先获取当前用户名，判断是个人账户还是组织
+        let current_user = self.get_authenticated_user(token).await?;
+
+        println!("  Authenticated as: {}", current_user);
+
+        // 如果 org 和当前用户名相同，使用用户 API
+        if org.eq_ignore_ascii_case(&current_user) {
+            println!("  Using user API (personal account)");
+            return self.create_repo_for_user(&current_user, repo_name, description, token).await;
+        }
+
+        // 否则尝试组织 API
+        println!("  Using organization API for: {}", org);
+
+        let request_body = CreateRepoRequest {
+            name: repo_name.to_string(),
+            description: description.to_string(),
+            private: true,
+            auto_init: true,
+        };
+
+        let request = self
+            .request(reqwest::Method::POST, &format!("/orgs/{}/repos", org), token)
+            .json(&request_body);
+        let response = self.api.send(request).await?;
+
+        if response.status.is_success() {
+            return Ok(CreatedRepository { owner: org.to_string(), url: self.clone_url(org, repo_name) });
+        }
+
+        match response.error("GitHub") {
+            // 组织策略禁止创建或组织不存在：仅在显式开启时改为创建个人仓库
+            ForgeError::ForbiddenByPolicy { .. } | ForgeError::NotFound { .. } if self.user_fallback => {
+                println!("  Organization API failed ({}), creating in user account instead...", response.status);
+                self.create_repo_for_user(&current_user, repo_name, description, token).await
+            }
+            ForgeError::ForbiddenByPolicy { forge, message } => Err(ForgeError::ForbiddenByPolicy {
+                forge,
+                message: format!(
+                    "{} (set \"allow_user_fallback\": true for '{}' in ~/.dot/dot.conf to create repositories in your user account)",
+                    message, org
+                ),
+            }
+            .into()),
+            error => Err(error.into()),
+        }
+    }
+
+    /// 在认证用户 `user` 的个人账户下创建仓库
+    async fn create_repo_for_user(
+        &self,
+        user: &str,
+        repo_name: &str,
+        description: &str,
+        token: &str,
+    ) -> Result<CreatedRepository, RepositoryError> {
+        let request_body = CreateRepoRequest {
+            name: repo_name.to_string(),
+            description: description.to_string(),
+            private: true,
+            auto_init: true,
+        };
+
+        let request = self
+            .request(reqwest::Method::POST, "/user/repos", token)
+            .json(&request_body);
+        let response = self.api.send(request).await?;
+
+        if response.status.is_success() {
+            return Ok(CreatedRepository { owner: user.to_string(), url: self.clone_url(user, repo_name) });
+        }
+
+        Err(api_error("GitHub", &response))
+    }
+
+    /// 获取认证用户名
+    async fn get_authenticated_user(&self, token: &str) -> Result<String, RepositoryError> {
+        let request = self.request(reqwest::Method::GET, "/user", token);
+        let response = self.api.get_cached(request).await?;
+
+        if !response.status.is_success() {
+            return Err(api_error("GitHub", &response));
+        }
+
+        #[derive(Deserialize)]
+        struct User {
+            login: String,
+        }
+
+        let user: User = response.json().map_err(|e| {
+            RepositoryError::IoError(std::io::Error::other(
+                format!("Failed to parse user response: {}", e)
+            ))
+        })?;
+
+        Ok(user.login)
+    }
+
+    /// 使用 gh CLI 创建仓库（回退方案）
+    async fn create_repo_via_gh_cli(
+        &self,
+        org: &str,
+        repo_name: &str,
+        description: &str,
+    ) -> Result<CreatedRepository, RepositoryError> {
+        let output = self.gh()
+            .args([
+                "repo", "create",
+                &format!("{}/{}", org, repo_name),
+                "--private",
+                "--description", description,
+            ])
+            .output();
+
+        match output {
+            Ok(result) => {
+                if result.status.success() {
+                    return Ok(CreatedRepository { owner: org.to_string(), url: self.clone_url(org, repo_name) });
+                }
+
+                let stderr = String::from_utf8_lossy(&result.stderr);
+
+                // gh 只输出文本，由调用方校验已存在的仓库
+                if stderr.contains("Name already exists") {
+                    return Err(ForgeError::NameTaken {
+                        forge: self.name().to_string(),
+                        message: stderr.trim().to_string(),
+                    }
+                    .into());
+                }
+
+                Err(RepositoryError::IoError(std::io::Error::other(
+                    format!("gh CLI error: {}. Please run 'gh auth login' or set github_token in ~/.dot/dot.conf", stderr.trim())
+                )))
+            }
+            Err(e) => {
+                Err(RepositoryError::IoError(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("GitHub CLI (gh) not available: {}. Please set github_token in ~/.dot/dot.conf or install gh CLI", e)
+                )))
+            }
+        }
+    }
+
+    async fn delete_repo_via_api(
+        &self,
+        org: &str,
+        repo_name: &str,
+        token: &str,
+    ) -> Result<(), RepositoryError> {
+        let request = self.request(reqwest::Method::DELETE, &format!("/repos/{}/{}", org, repo_name), token);
+        let response = self.api.send(request).await?;
+
+        if response.status.is_success() || response.status.as_u16() == 404 {
+            return Ok(());
+        }
+
+        Err(api_error("GitHub", &response))
+    }
+
+    async fn delete_repo_via_gh_cli(
+        &self,
+        org: &str,
+        repo_name: &str,
+    ) -> Result<(), RepositoryError> {
+        let output = self.gh()
+            .args([
+                "repo", "delete",
+                &format!("{}/{}", org, repo_name),
+                "--yes",
+            ])
+            .output();
+
+        match output {
+            Ok(result) if result.status.success() => Ok(()),
+            Ok(result) => {
+                let stderr = String::from_utf8_lossy(&result.stderr);
+                // 仓库不存在视为已删除
+                if stderr.contains("Could not resolve to a Repository") || stderr.contains("Not Found") {
+                    return Ok(());
+                }
+                Err(RepositoryError::IoError(std::io::Error::other(
+                    format!("gh CLI error: {} (deleting requires 'gh auth refresh -s delete_repo')", stderr.trim())
+                )))
+            }
+            Err(e) => Err(RepositoryError::IoError(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("GitHub CLI (gh) not available: {}", e),
+            ))),
+        }
+    }
+
+    /// 使用 gh CLI 查询仓库（没有 token 时）
+    ///
+    /// `gh api -i` 在输出中包含 HTTP 状态行和响应头，请求失败时以非零状态退出但仍输出响应，
+    /// 因此按状态码判断仓库是否存在，并与 API 请求一样解析错误类型。
+    fn get_repo_via_gh_cli(&self, org: &str, repo_name: &str) -> Result<Option<RemoteRepository>, RepositoryError> {
+        let output = self.gh()
+            .args(["api", "-i", &format!("repos/{}/{}", org, repo_name)])
+            .output()?;
+
+        let Some(response) = gh_response(&output.stdout) else {
+            return Err(RepositoryError::IoError(std::io::Error::other(
+                format!("gh CLI error: {}", String::from_utf8_lossy(&output.stderr).trim())
+            )));
+        };
+        if response.status.as_u16() == 404 {
+            return Ok(None);
+        }
+        if !response.status.is_success() {
+            return Err(api_error("GitHub", &response));
+        }
+
+        let repo: RepoResponse = response.json()?;
+        Ok(Some(repo.into()))
+    }
+
+    /// 使用 gh CLI 列出仓库（没有 token 时）
+    fn list_repos_via_gh_cli(&self, owner: &str) -> Result<Vec<RemoteRepository>, RepositoryError> {
+        let output = self.gh()
+            .args(["api", "--paginate", &format!("orgs/{}/repos?per_page=100", owner)])
+            .output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(RepositoryError::IoError(std::io::Error::other(
+                format!("gh CLI error: {}", stderr.trim())
+            )));
+        }
+
+        // --paginate 输出多个 JSON 数组
+        let mut repos = Vec::new();
+        for page in serde_json::Deserializer::from_slice(&output.stdout).into_iter::<Vec<RepoResponse>>() {
+            repos.extend(page?.into_iter().map(RemoteRepository::from));
+        }
+        Ok(repos)
+    }
+}
+
+/// 解析 `gh api -i` 的输出（状态行、响应头、空行、响应体），不是 HTTP 响应时返回 None
+fn gh_response(stdout: &[u8]) -> Option<ApiResponse> {
+    let text = String::from_utf8_lossy(stdout);
+    let (head, body) = text
+        .split_once("\r\n\r\n")
+        .or_else(|| text.split_once("\n\n"))
+        .unwrap_or((&text, ""));
+    let mut lines = head.lines();
+
+    let status_line = lines.next()?;
+    if !status_line.starts_with("HTTP/") {
+        return None;
+    }
+    let status = status_line.split_whitespace().nth(1)?.parse().ok()?;
+    let status = reqwest::StatusCode::from_u16(status).ok()?;
+
+    let mut headers = reqwest::header::HeaderMap::new();
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else { continue };
+        if let (Ok(name), Ok(value)) = (
+            reqwest::header::HeaderName::from_bytes(name.trim().as_bytes()),
+            reqwest::header::HeaderValue::from_str(value.trim()),
+        ) {
+            headers.append(name, value);
+        }
+    }
+
+    Some(ApiResponse { status, headers, body: body.as_bytes().to_vec() })
+}
+
+impl From<RepoResponse> for RemoteRepository {
+    fn from(repo: RepoResponse) -> Self {
+        Self {
+            owner: repo.owner.login,
+            name: repo.name,
+            description: repo.description,
+            private: repo.private,
+            topics: repo.topics,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Forge for GitHubForge {
+    fn name(&self) -> &'static str {
+        "GitHub"
+    }
+
+    /// 创建远程仓库
+    /// 优先使用 GitHub API（如果有 token），否则使用 gh CLI
+    async fn create_repository(
+        &self,
+        org: &str,
+        repo_name: &str,
+        description: &str,
+    ) -> Result<CreatedRepository, RepositoryError> {
+        // 优先使用 GitHub API
+        if let Some(token) = &self.token {
+            return self.create_repo_via_api(org, repo_name, description, token).await;
+        }
+
+        // 回退到 gh CLI
+        self.create_repo_via_gh_cli(org, repo_name, description).await
+    }
+
+    /// 删除远程仓库
+    async fn delete_repository(
+        &self,
+        org: &str,
+        repo_name: &str,
+    ) -> Result<(), RepositoryError> {
+        // 优先使用 GitHub API
+        if let Some(token) = &self.token {
+            return self.delete_repo_via_api(org, repo_name, token).await;
+        }
+
+        // 回退到 gh CLI
+        self.delete_repo_via_gh_cli(org, repo_name).await
+    }
+
+    async fn rename_repository(&self, org: &str, repo_name: &str, new_name: &str) -> Result<(), RepositoryError> {
+        let body = serde_json::json!({ "name": new_name });
+        self.call(reqwest::Method::PATCH, &format!("/repos/{}/{}", org, repo_name), Some(body)).await?;
+        Ok(())
+    }
+
+    async fn archive_repository(&self, org: &str, repo_name: &str) -> Result<(), RepositoryError> {
+        let body = serde_json::json!({ "archived": true });
+        self.call(reqwest::Method::PATCH, &format!("/repos/{}/{}", org, repo_name), Some(body)).await?;
+        Ok(())
+    }
+
+    async fn get_repository(&self, org: &str, repo_name: &str) -> Result<Option<RemoteRepository>, RepositoryError> {
+        let Some(token) = &self.token else {
+            return self.get_repo_via_gh_cli(org, repo_name);
+        };
+
+        let request = self.request(reqwest::Method::GET, &format!("/repos/{}/{}", org, repo_name), token);
+        let response = self.api.get_cached(request).await?;
+
+        let status = response.status;
+        if status.as_u16() == 404 {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(api_error("GitHub", &response));
+        }
+
+        let repo: RepoResponse = response.json()?;
+        Ok(Some(repo.into()))
+    }
+
+    /// 列出组织（或个人账户）下的所有仓库，自动翻页
+    async fn list_repositories(&self, owner: &str) -> Result<Vec<RemoteRepository>, RepositoryError> {
+        let Some(token) = &self.token else {
+            return self.list_repos_via_gh_cli(owner);
+        };
+
+        let current_user = self.get_authenticated_user(token).await?;
+        let path = if owner.eq_ignore_ascii_case(&current_user) {
+            "/user/repos?affiliation=owner&per_page=100".to_string()
+        } else {
+            format!("/orgs/{}/repos?type=all&per_page=100", owner)
+        };
+
+        let repos: Vec<RepoResponse> = self.api
+            .get_all_pages("GitHub", format!("{}{}", self.api_base, path), |url| {
+                Ok(self.request_url(reqwest::Method::GET, url, token))
+            })
+            .await?;
+        Ok(repos.into_iter().map(RemoteRepository::from).collect())
+    }
+
+    async fn authenticated_user(&self) -> Result<String, RepositoryError> {
+        match &self.token {
+            Some(token) => self.get_authenticated_user(token).await,
+            None => {
+                let output = self.gh().args(["api", "user", "-q", ".login"]).output()?;
+                if !output.status.success() {
+                    return Err(RepositoryError::IoError(std::io::Error::other(
+                        "Failed to get authenticated user. Please run 'gh auth login' or set github_token in ~/.dot/dot.conf"
+                    )));
+                }
+                Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+            }
+        }
+    }
+
+    fn clone_url(&self, owner: &str, repo_name: &str) -> String {
+        self.hosts.url(owner, repo_name)
+    }
+
+    async fn add_deploy_key(
+        &self,
+        owner: &str,
+        repo_name: &str,
+        title: &str,
+        public_key: &str,
+        read_only: bool,
+    ) -> Result<DeployKey, RepositoryError> {
+        let body = serde_json::json!({ "title": title, "key": public_key, "read_only": read_only });
+        let key = self.call(reqwest::Method::POST, &format!("/repos/{}/{}/keys", owner, repo_name), Some(body)).await?;
+        Ok(serde_json::from_value::<KeyResponse>(key)?.into())
+    }
+
+    async fn list_deploy_keys(&self, owner: &str, repo_name: &str) -> Result<Vec<DeployKey>, RepositoryError> {
+        let keys = self.call(reqwest::Method::GET, &format!("/repos/{}/{}/keys", owner, repo_name), None).await?;
+        let keys: Vec<KeyResponse> = serde_json::from_value(keys)?;
+        Ok(keys.into_iter().map(DeployKey::from).collect())
+    }
+
+    async fn delete_deploy_key(&self, owner: &str, repo_name: &str, id: &str) -> Result<(), RepositoryError> {
+        self.call(reqwest::Method::DELETE, &format!("/repos/{}/{}/keys/{}", owner, repo_name, id), None).await?;
+        Ok(())
+    }
+
+    fn ssh_host(&self) -> Option<String> {
+        Some(self.hosts.ssh_host.clone())
+    }
+
+    async fn apply_step(&self, owner: &str, repo_name: &str, step: &RepositoryStep) -> Result<(), RepositoryError> {
+        let repo_path = format!("/repos/{}/{}", owner, repo_name);
+        match step {
+            RepositoryStep::Settings { visibility, wiki, issues, projects } => {
+                let mut settings = serde_json::Map::new();
+                if let Some(visibility) = visibility {
+                    let visibility = match visibility {
+                        Visibility::Private => "private",
+                        Visibility::Internal => "internal",
+                    };
+                    settings.insert("visibility".to_string(), visibility.into());
+                }
+                for (field, value) in [("has_wiki", wiki), ("has_issues", issues), ("has_projects", projects)] {
+                    if let Some(value) = value {
+                        settings.insert(field.to_string(), (*value).into());
+                    }
+                }
+                self.call(reqwest::Method::PATCH, &repo_path, Some(settings.into())).await?;
+            }
+            RepositoryStep::Topics(topics) => {
+                let body = serde_json::json!({ "names": topics });
+                self.call(reqwest::Method::PUT, &format!("{}/topics", repo_path), Some(body)).await?;
+            }
+            RepositoryStep::DefaultBranch(branch) => {
+                // auto_init 创建的初始分支改名，而不是新建分支
+                let repo = self.call(reqwest::Method::GET, &repo_path, None).await?;
+                let current = repo["default_branch"].as_str().unwrap_or_default();
+                if current != branch {
+                    let body = serde_json::json!({ "new_name": branch });
+                    self.call(reqwest::Method::POST, &format!("{}/branches/{}/rename", repo_path, current), Some(body)).await?;
+                }
+            }
+            RepositoryStep::TeamAccess { team, permission } => {
+                let path = format!("/orgs/{}/teams/{}/repos/{}/{}", owner, team, owner, repo_name);
+                let body = serde_json::json!({ "permission": permission });
+                self.call(reqwest::Method::PUT, &path, Some(body)).await?;
+            }
+        }
+        Ok(())
+    }
+
+    fn credentials(&self) -> Option<GitCredentials> {
+        self.token.as_ref().map(|token| GitCredentials::new(&self.hosts.https_host, "x-access-token", token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{CloneProtocol, OrganizationConfig, RepositoryTemplate};
+    use wiremock::matchers::{body_partial_json, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_parse_gh_api_include_output() {
+        let found = gh_response(b"HTTP/2.0 200 OK\nContent-Type: application/json; charset=utf-8\nEtag: W/\"abc\"\n\n{\"name\": \"abc\"}").unwrap();
+        assert_eq!(found.status.as_u16(), 200);
+        assert_eq!(found.headers.get("etag").unwrap(), "W/\"abc\"");
+        assert_eq!(found.json::<serde_json::Value>().unwrap()["name"], "abc");
+
+        let missing = gh_response(b"HTTP/2.0 404 Not Found\r\nContent-Type: application/json\r\n\r\n{\"message\": \"Not Found\"}").unwrap();
+        assert_eq!(missing.status.as_u16(), 404);
+
+        let forbidden = gh_response(b"HTTP/2.0 403 Forbidden\n\n{\"message\": \"Resource not accessible by integration\"}").unwrap();
+        assert!(matches!(forbidden.error("GitHub"), ForgeError::ForbiddenByPolicy { .. }));
+
+        assert!(gh_response(b"").is_none());
+        assert!(gh_response(b"error connecting to api.github.com").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_create_repository_in_organization() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/user"))
+            .and(header("Authorization", "Bearer test-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"login": "alice"})))
+            .mount(&server).await;
+        Mock::given(method("POST")).and(path("/orgs/acme/repos"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({"name": "abc"})))
+            .expect(1)
+            .mount(&server).await;
+
+        let forge = GitHubForge::new(Some("test-token".to_string()), Some(server.uri()));
+        let url = forge.create_repository("acme", "abc", "Hidden repository").await.unwrap().url;
+        assert_eq!(url, "git@127.0.0.1:acme/abc.git");
+    }
+
+    #[tokio::test]
+    async fn test_enterprise_server_configuration() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/api/v3/user"))
+            .and(header("Authorization", "Bearer test-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"login": "alice"})))
+            .mount(&server).await;
+        Mock::given(method("POST")).and(path("/api/v3/orgs/acme/repos"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({"name": "abc"})))
+            .expect(1)
+            .mount(&server).await;
+
+        let org_config = OrganizationConfig {
+            api_base_url: Some(format!("{}/api/v3", server.uri())),
+            ssh_host: Some("ghe.example.com".to_string()),
+            https_host: Some("ghe.example.com".to_string()),
+            ..Default::default()
+        };
+        let forge = crate::forge::from_config(&org_config, Some("test-token".to_string()));
+        let url = forge.create_repository("acme", "abc", "Hidden repository").await.unwrap().url;
+        assert_eq!(url, "git@ghe.example.com:acme/abc.git");
+
+        let https = OrganizationConfig { protocol: Some(CloneProtocol::Https), ..org_config };
+        let forge = crate::forge::from_config(&https, Some("test-token".to_string()));
+        assert_eq!(forge.clone_url("acme", ".index"), "https://ghe.example.com/acme/.index.git");
+    }
+
+    #[tokio::test]
+    async fn test_enterprise_server_on_non_default_port() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/api/v3/user"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"login": "alice"})))
+            .mount(&server).await;
+        Mock::given(method("POST")).and(path("/api/v3/orgs/acme/repos"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({"name": "abc"})))
+            .mount(&server).await;
+
+        // HTTPS 主机保留端口，SSH 主机不含端口
+        let port = server.address().port();
+        let org_config = OrganizationConfig {
+            api_base_url: Some(format!("{}/api/v3", server.uri())),
+            protocol: Some(CloneProtocol::Https),
+            ..Default::default()
+        };
+        let forge = crate::forge::from_config(&org_config, Some("test-token".to_string()));
+        let url = forge.create_repository("acme", "abc", "Hidden repository").await.unwrap().url;
+        assert_eq!(url, format!("https://127.0.0.1:{}/acme/abc.git", port));
+
+        let credentials = forge.credentials().unwrap();
+        assert_eq!(credentials.host, format!("127.0.0.1:{}", port));
+        assert!(credentials.accepts(&url));
+        assert!(!credentials.accepts("https://127.0.0.1/acme/abc.git"));
+
+        let ssh = OrganizationConfig { protocol: Some(CloneProtocol::Ssh), ..org_config };
+        let forge = crate::forge::from_config(&ssh, Some("test-token".to_string()));
+        assert_eq!(forge.clone_url("acme", "abc"), "git@127.0.0.1:acme/abc.git");
+    }
+
+    #[tokio::test]
+    async fn test_list_organization_repositories_paginated() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/user"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"login": "alice"})))
+            .mount(&server).await;
+        let repo = |name: &str| serde_json::json!({
+            "name": name, "owner": {"login": "acme"}, "description": null, "private": true
+        });
+        Mock::given(method("GET")).and(path("/orgs/acme/repos")).and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([repo("c")])))
+            .mount(&server).await;
+        let next = format!("<{}/orgs/acme/repos?type=all&per_page=100&page=2>; rel=\"next\"", server.uri());
+        Mock::given(method("GET")).and(path("/orgs/acme/repos"))
+            .and(header("Authorization", "Bearer test-token"))
+            .respond_with(ResponseTemplate::new(200)
+                .insert_header("Link", next.as_str())
+                .set_body_json(serde_json::json!([repo("a"), repo("b")])))
+            .mount(&server).await;
+
+        let forge = GitHubForge::new(Some("test-token".to_string()), Some(server.uri()));
+        let names: Vec<String> = forge.list_repositories("acme").await.unwrap()
+            .into_iter().map(|r| r.name).collect();
+        assert_eq!(names, vec!["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_repository() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/repos/acme/abc"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "name": "abc",
+                "owner": {"login": "acme"},
+                "description": "Hidden repository",
+                "private": true
+            })))
+            .mount(&server).await;
+        Mock::given(method("GET")).and(path("/repos/acme/missing"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server).await;
+
+        let forge = GitHubForge::new(Some("test-token".to_string()), Some(server.uri()));
+        let repo = forge.get_repository("acme", "abc").await.unwrap().unwrap();
+        assert_eq!(repo.owner, "acme");
+        assert!(repo.private);
+        assert!(!forge.repository_exists("acme", "missing").await.unwrap());
+    }
+
+    async fn name_taken_server(description: &str) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/user"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"login": "alice"})))
+            .mount(&server).await;
+        Mock::given(method("POST")).and(path("/orgs/acme/repos"))
+            .respond_with(ResponseTemplate::new(422).set_body_json(serde_json::json!({
+                "message": "Repository creation failed.",
+                "errors": [{"resource": "Repository", "code": "custom", "field": "name",
+                            "message": "name already exists on this account"}]
+            })))
+            .mount(&server).await;
+        Mock::given(method("GET")).and(path("/repos/acme/abc"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "name": "abc", "owner": {"login": "acme"}, "description": description, "private": true
+            })))
+            .mount(&server).await;
+        server
+    }
+
+    #[tokio::test]
+    async fn test_existing_repository_is_verified() {
+        let server = name_taken_server("Hidden repository for github.com/acme/app").await;
+        let forge = GitHubForge::new(Some("test-token".to_string()), Some(server.uri()));
+        let existing = crate::forge::ensure_repository(&forge, "acme", "abc", "Hidden repository for github.com/acme/app")
+            .await.unwrap();
+        assert_eq!(existing.url, "git@127.0.0.1:acme/abc.git");
+        assert!(!existing.created);
+
+        // 同名但不是 dot 创建的仓库不能复用
+        let server = name_taken_server("Production service").await;
+        let forge = GitHubForge::new(Some("test-token".to_string()), Some(server.uri()));
+        let error = crate::forge::ensure_repository(&forge, "acme", "abc", "Hidden repository for github.com/acme/app")
+            .await.unwrap_err();
+        assert!(matches!(error, RepositoryError::Forge(ForgeError::NameTaken { .. })), "{:?}", error);
+    }
+
+    async fn forbidden_server() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/user"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"login": "alice"})))
+            .mount(&server).await;
+        Mock::given(method("POST")).and(path("/orgs/acme/repos"))
+            .respond_with(ResponseTemplate::new(403).set_body_json(serde_json::json!({
+                "message": "Members of this organization cannot create private repositories"
+            })))
+            .mount(&server).await;
+        server
+    }
+
+    #[tokio::test]
+    async fn test_forbidden_by_policy_does_not_fall_back() {
+        let server = forbidden_server().await;
+        Mock::given(method("POST")).and(path("/user/repos"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(0)
+            .mount(&server).await;
+
+        let forge = GitHubForge::new(Some("test-token".to_string()), Some(server.uri()));
+        let error = forge.create_repository("acme", "abc", "Hidden repository").await.unwrap_err();
+        match error {
+            RepositoryError::Forge(ForgeError::ForbiddenByPolicy { message, .. }) => {
+                assert!(message.contains("cannot create private repositories"));
+                assert!(message.contains("allow_user_fallback"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_user_fallback_when_enabled() {
+        let server = forbidden_server().await;
+        Mock::given(method("POST")).and(path("/user/repos"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({"name": "abc"})))
+            .expect(1)
+            .mount(&server).await;
+
+        let org_config = OrganizationConfig {
+            api_base_url: Some(server.uri()),
+            allow_user_fallback: true,
+            ..Default::default()
+        };
+        let forge = crate::forge::from_config(&org_config, Some("test-token".to_string()));
+        let created = forge.create_repository("acme", "abc", "Hidden repository").await.unwrap();
+        assert_eq!(created.owner, "alice");
+        assert_eq!(created.url, "git@127.0.0.1:alice/abc.git");
+    }
+
+    #[tokio::test]
+    async fn test_apply_repository_template() {
+        let server = MockServer::start().await;
+        Mock::given(method("PATCH")).and(path("/repos/acme/abc"))
+            .and(body_partial_json(serde_json::json!({"visibility": "internal", "has_wiki": false, "has_issues": false})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&server).await;
+        Mock::given(method("PUT")).and(path("/repos/acme/abc/topics"))
+            .and(body_partial_json(serde_json::json!({"names": ["dot-hidden"]})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"names": ["dot-hidden"]})))
+            .expect(1)
+            .mount(&server).await;
+        Mock::given(method("GET")).and(path("/repos/acme/abc"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "name": "abc", "owner": {"login": "acme"}, "private": true, "default_branch": "main"
+            })))
+            .mount(&server).await;
+        Mock::given(method("POST")).and(path("/repos/acme/abc/branches/main/rename"))
+            .and(body_partial_json(serde_json::json!({"new_name": "trunk"})))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({"name": "trunk"})))
+            .expect(1)
+            .mount(&server).await;
+        Mock::given(method("PUT")).and(path("/orgs/acme/teams/platform/repos/acme/abc"))
+            .and(body_partial_json(serde_json::json!({"permission": "push"})))
+            .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({"message": "Not Found"})))
+            .mount(&server).await;
+
+        let template: RepositoryTemplate = serde_json::from_value(serde_json::json!({
+            "visibility": "internal", "wiki": false, "issues": false, "topics": ["dot-hidden"],
+            "default_branch": "trunk", "teams": [{"team": "platform"}]
+        })).unwrap();
+        let forge = GitHubForge::new(Some("test-token".to_string()), Some(server.uri()));
+        // 最后一步（团队不存在）失败时返回具体错误
+        let error = crate::forge::apply_template(&forge, "acme", "abc", &template).await.unwrap_err();
+        assert!(matches!(error, RepositoryError::Forge(ForgeError::NotFound { .. })), "{:?}", error);
+    }
+
+    #[tokio::test]
+    async fn test_rename_and_archive_repository() {
+        let server = MockServer::start().await;
+        Mock::given(method("PATCH")).and(path("/repos/acme/abc"))
+            .and(body_partial_json(serde_json::json!({"name": "dot-trash-20260101000000-abc"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&server).await;
+        Mock::given(method("PATCH")).and(path("/repos/acme/dot-trash-20260101000000-abc"))
+            .and(body_partial_json(serde_json::json!({"archived": true})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&server).await;
+
+        let forge = GitHubForge::new(Some("test-token".to_string()), Some(server.uri()));
+        forge.rename_repository("acme", "abc", "dot-trash-20260101000000-abc").await.unwrap();
+        forge.archive_repository("acme", "dot-trash-20260101000000-abc").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_deploy_keys() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST")).and(path("/repos/acme/abc/keys"))
+            .and(body_partial_json(serde_json::json!({"title": "dot:ci", "read_only": true})))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({
+                "id": 7, "title": "dot:ci", "key": "ssh-ed25519 AAAA", "read_only": true
+            })))
+            .expect(1)
+            .mount(&server).await;
+        Mock::given(method("GET")).and(path("/repos/acme/abc/keys"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                {"id": 7, "title": "dot:ci", "key": "ssh-ed25519 AAAA", "read_only": true}
+            ])))
+            .mount(&server).await;
+        Mock::given(method("DELETE")).and(path("/repos/acme/abc/keys/7"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server).await;
+
+        let forge = GitHubForge::new(Some("test-token".to_string()), Some(server.uri()));
+        let key = forge.add_deploy_key("acme", "abc", "dot:ci", "ssh-ed25519 AAAA dot:ci", true).await.unwrap();
+        assert_eq!(key.id, "7");
+        assert_eq!(forge.list_deploy_keys("acme", "abc").await.unwrap(), vec![key]);
+        forge.delete_deploy_key("acme", "abc", "7").await.unwrap();
+        assert_eq!(forge.ssh_host().as_deref(), Some("127.0.0.1"));
+    }
+
+    #[test]
+    fn test_clone_url() {
+        let forge = GitHubForge::new(None, None);
+        assert_eq!(forge.clone_url("acme", "abc"), "git@github.com:acme/abc.git");
+    }
+}
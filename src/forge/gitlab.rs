@@ -0,0 +1,348 @@
+use super::{api_error, missing_token, ApiClient, CreatedRepository, DeployKey, Forge, RemoteHosts, RemoteRepository};
+use crate::error::RepositoryError;
+use crate::git_operations::GitCredentials;
+use serde::{Deserialize, Serialize};
+
+pub(crate) const DEFAULT_API_BASE: &str = "https://gitlab.com/api/v4";
+
+/// GitLab API 客户端
+///
+/// 组织对应 GitLab 的 group，支持子组（`group/subgroup`）。
+pub struct GitLabForge {
+    token: Option<String>,
+    api_base: String,
+    hosts: RemoteHosts,
+    api: &'static ApiClient,
+}
+
+#[derive(Debug, Serialize)]
+struct CreateProjectRequest<'a> {
+    name: &'a str,
+    path: &'a str,
+    namespace_id: u64,
+    description: &'a str,
+    visibility: &'a str,
+    initialize_with_readme: bool,
+}
+
+#[derive(Deserialize)]
+struct Namespace {
+    id: u64,
+}
+
+#[derive(Deserialize)]
+struct ProjectResponse {
+    path: String,
+    namespace: ProjectNamespace,
+    description: Option<String>,
+    visibility: Option<String>,
+    #[serde(default)]
+    topics: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ProjectNamespace {
+    full_path: String,
+}
+
+#[derive(Deserialize)]
+struct DeployKeyResponse {
+    id: u64,
+    title: String,
+    key: String,
+    #[serde(default)]
+    can_push: bool,
+}
+
+impl From<DeployKeyResponse> for DeployKey {
+    fn from(key: DeployKeyResponse) -> Self {
+        Self {
+            id: key.id.to_string(),
+            title: key.title,
+            key: key.key,
+            read_only: !key.can_push,
+        }
+    }
+}
+
+/// 对 URL 路径片段进行百分号编码（GitLab 使用编码后的完整路径作为项目 ID）
+fn encode_path(path: &str) -> String {
+    path.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+impl GitLabForge {
+    pub fn new(token: Option<String>, api_base: Option<String>) -> Self {
+        let api_base = api_base
+            .unwrap_or_else(|| DEFAULT_API_BASE.to_string())
+            .trim_end_matches('/')
+            .to_string();
+        Self {
+            token,
+            hosts: RemoteHosts::from_api_base(&api_base),
+            api_base,
+            api: ApiClient::shared(),
+        }
+    }
+
+    /// 使用指定的克隆主机和协议
+    pub fn with_hosts(mut self, hosts: RemoteHosts) -> Self {
+        self.hosts = hosts;
+        self
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> Result<reqwest::RequestBuilder, RepositoryError> {
+        let token = self.token.as_ref().ok_or_else(|| missing_token("GitLab"))?;
+        Ok(self.api.client()
+            .request(method, format!("{}{}", self.api_base, path))
+            .header("PRIVATE-TOKEN", token)
+            .header("User-Agent", "dot-cli"))
+    }
+
+    fn project_path(owner: &str, repo_name: &str) -> String {
+        format!("/projects/{}", encode_path(&format!("{}/{}", owner, repo_name)))
+    }
+
+    /// 发送请求并返回 JSON 响应（空响应为 Null）
+    async fn call(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value, RepositoryError> {
+        let mut request = self.request(method, path)?;
+        if let Some(body) = &body {
+            request = request.json(body);
+        }
+        let response = self.api.send(request).await?;
+        if !response.status.is_success() {
+            return Err(api_error("GitLab", &response));
+        }
+        if response.body.is_empty() {
+            return Ok(serde_json::Value::Null);
+        }
+        response.json()
+    }
+
+    async fn namespace_id(&self, owner: &str) -> Result<u64, RepositoryError> {
+        let request = self.request(reqwest::Method::GET, &format!("/namespaces/{}", encode_path(owner)))?;
+        let response = self.api.send(request).await?;
+
+        let status = response.status;
+        if !status.is_success() {
+            return Err(api_error("GitLab", &response));
+        }
+
+        let namespace: Namespace = response.json()?;
+        Ok(namespace.id)
+    }
+}
+
+#[async_trait::async_trait]
+impl Forge for GitLabForge {
+    fn name(&self) -> &'static str {
+        "GitLab"
+    }
+
+    async fn create_repository(
+        &self,
+        owner: &str,
+        repo_name: &str,
+        description: &str,
+    ) -> Result<CreatedRepository, RepositoryError> {
+        let namespace_id = self.namespace_id(owner).await?;
+
+        let request_body = CreateProjectRequest {
+            name: repo_name,
+            path: repo_name,
+            namespace_id,
+            description,
+            visibility: "private",
+            initialize_with_readme: true,
+        };
+
+        let request = self
+            .request(reqwest::Method::POST, "/projects")?
+            .json(&request_body);
+        let response = self.api.send(request).await?;
+
+        if response.status.is_success() {
+            return Ok(CreatedRepository { owner: owner.to_string(), url: self.clone_url(owner, repo_name) });
+        }
+
+        Err(api_error("GitLab", &response))
+    }
+
+    async fn delete_repository(&self, owner: &str, repo_name: &str) -> Result<(), RepositoryError> {
+        let request = self.request(reqwest::Method::DELETE, &Self::project_path(owner, repo_name))?;
+        let response = self.api.send(request).await?;
+
+        let status = response.status;
+        if status.is_success() || status.as_u16() == 404 {
+            return Ok(());
+        }
+
+        Err(api_error("GitLab", &response))
+    }
+
+    async fn rename_repository(&self, owner: &str, repo_name: &str, new_name: &str) -> Result<(), RepositoryError> {
+        let request = self
+            .request(reqwest::Method::PUT, &Self::project_path(owner, repo_name))?
+            .json(&serde_json::json!({ "name": new_name, "path": new_name }));
+        let response = self.api.send(request).await?;
+        if response.status.is_success() {
+            return Ok(());
+        }
+        Err(api_error("GitLab", &response))
+    }
+
+    async fn archive_repository(&self, owner: &str, repo_name: &str) -> Result<(), RepositoryError> {
+        let request = self.request(reqwest::Method::POST, &format!("{}/archive", Self::project_path(owner, repo_name)))?;
+        let response = self.api.send(request).await?;
+        if response.status.is_success() {
+            return Ok(());
+        }
+        Err(api_error("GitLab", &response))
+    }
+
+    async fn get_repository(&self, owner: &str, repo_name: &str) -> Result<Option<RemoteRepository>, RepositoryError> {
+        let request = self.request(reqwest::Method::GET, &Self::project_path(owner, repo_name))?;
+        let response = self.api.send(request).await?;
+
+        let status = response.status;
+        if status.as_u16() == 404 {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(api_error("GitLab", &response));
+        }
+
+        let project: ProjectResponse = response.json()?;
+        Ok(Some(RemoteRepository {
+            owner: project.namespace.full_path,
+            name: project.path,
+            description: project.description,
+            private: project.visibility.as_deref() != Some("public"),
+            topics: project.topics,
+        }))
+    }
+
+    async fn authenticated_user(&self) -> Result<String, RepositoryError> {
+        let request = self.request(reqwest::Method::GET, "/user")?;
+        let response = self.api.send(request).await?;
+
+        let status = response.status;
+        if !status.is_success() {
+            return Err(api_error("GitLab", &response));
+        }
+
+        #[derive(Deserialize)]
+        struct User {
+            username: String,
+        }
+
+        let user: User = response.json()?;
+        Ok(user.username)
+    }
+
+    fn clone_url(&self, owner: &str, repo_name: &str) -> String {
+        self.hosts.url(owner, repo_name)
+    }
+
+    async fn add_deploy_key(
+        &self,
+        owner: &str,
+        repo_name: &str,
+        title: &str,
+        public_key: &str,
+        read_only: bool,
+    ) -> Result<DeployKey, RepositoryError> {
+        let body = serde_json::json!({ "title": title, "key": public_key, "can_push": !read_only });
+        let path = format!("{}/deploy_keys", Self::project_path(owner, repo_name));
+        let key = self.call(reqwest::Method::POST, &path, Some(body)).await?;
+        Ok(serde_json::from_value::<DeployKeyResponse>(key)?.into())
+    }
+
+    async fn list_deploy_keys(&self, owner: &str, repo_name: &str) -> Result<Vec<DeployKey>, RepositoryError> {
+        let path = format!("{}/deploy_keys", Self::project_path(owner, repo_name));
+        let keys: Vec<DeployKeyResponse> = serde_json::from_value(self.call(reqwest::Method::GET, &path, None).await?)?;
+        Ok(keys.into_iter().map(DeployKey::from).collect())
+    }
+
+    async fn delete_deploy_key(&self, owner: &str, repo_name: &str, id: &str) -> Result<(), RepositoryError> {
+        let path = format!("{}/deploy_keys/{}", Self::project_path(owner, repo_name), id);
+        self.call(reqwest::Method::DELETE, &path, None).await?;
+        Ok(())
+    }
+
+    fn ssh_host(&self) -> Option<String> {
+        Some(self.hosts.ssh_host.clone())
+    }
+
+    fn credentials(&self) -> Option<GitCredentials> {
+        self.token.as_ref().map(|token| GitCredentials::new(&self.hosts.https_host, "oauth2", token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_encode_path() {
+        assert_eq!(encode_path("group/sub/.index"), "group%2Fsub%2F.index");
+    }
+
+    #[tokio::test]
+    async fn test_create_repository_in_subgroup() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/namespaces/acme%2Fteam"))
+            .and(header("PRIVATE-TOKEN", "test-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"id": 42})))
+            .mount(&server).await;
+        Mock::given(method("POST")).and(path("/projects"))
+            .and(body_partial_json(serde_json::json!({"namespace_id": 42, "path": "abc", "visibility": "private"})))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({"id": 7})))
+            .expect(1)
+            .mount(&server).await;
+
+        let forge = GitLabForge::new(Some("test-token".to_string()), Some(server.uri()));
+        let url = forge.create_repository("acme/team", "abc", "Hidden repository").await.unwrap().url;
+        assert_eq!(url, "git@127.0.0.1:acme/team/abc.git");
+    }
+
+    #[tokio::test]
+    async fn test_get_and_delete_repository() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/projects/acme%2Fabc"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "path": "abc",
+                "namespace": {"full_path": "acme"},
+                "description": "Hidden repository",
+                "visibility": "private"
+            })))
+            .mount(&server).await;
+        Mock::given(method("DELETE")).and(path("/projects/acme%2Fabc"))
+            .respond_with(ResponseTemplate::new(202))
+            .expect(1)
+            .mount(&server).await;
+
+        let forge = GitLabForge::new(Some("test-token".to_string()), Some(server.uri()));
+        let repo = forge.get_repository("acme", "abc").await.unwrap().unwrap();
+        assert_eq!(repo.owner, "acme");
+        assert!(repo.private);
+        forge.delete_repository("acme", "abc").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_requires_token() {
+        let forge = GitLabForge::new(None, Some("http://127.0.0.1:9".to_string()));
+        assert!(forge.authenticated_user().await.is_err());
+    }
+}
@@ -0,0 +1,189 @@
+use super::{unsupported, CreatedRepository, Forge, RemoteRepository, RepositoryStep};
+use crate::error::{ForgeError, RepositoryError};
+use std::path::{Path, PathBuf};
+
+/// 本地文件系统“平台”
+///
+/// 将隐藏仓库创建为根目录下的裸仓库（`<root>/<org>/<name>.git`），返回 `file://` URL。
+/// 根目录可以是 NAS 挂载点等共享目录，也可以用于离线使用和集成测试。
+pub struct LocalForge {
+    root: PathBuf,
+}
+
+/// 默认的本地平台根目录: ~/dot-remotes
+pub fn default_root() -> PathBuf {
+    dirs::home_dir().unwrap_or_default().join("dot-remotes")
+}
+
+impl LocalForge {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// 裸仓库在磁盘上的路径
+    pub fn repository_path(&self, owner: &str, repo_name: &str) -> PathBuf {
+        self.root.join(owner).join(format!("{}.git", repo_name))
+    }
+
+    /// 创建裸仓库（已存在时不做任何事），返回其路径
+    pub fn ensure_bare_repository(&self, owner: &str, repo_name: &str) -> Result<PathBuf, RepositoryError> {
+        let path = self.repository_path(owner, repo_name);
+        if git2::Repository::open_bare(&path).is_err() {
+            std::fs::create_dir_all(&path)?;
+            git2::Repository::init_bare(&path)?;
+        }
+        Ok(path)
+    }
+
+    fn read_description(path: &Path) -> Option<String> {
+        let description = std::fs::read_to_string(path.join("description")).ok()?;
+        let description = description.trim();
+        // git init 写入的默认描述
+        if description.is_empty() || description.starts_with("Unnamed repository") {
+            return None;
+        }
+        Some(description.to_string())
+    }
+}
+
+#[async_trait::async_trait]
+impl Forge for LocalForge {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn create_repository(
+        &self,
+        owner: &str,
+        repo_name: &str,
+        description: &str,
+    ) -> Result<CreatedRepository, RepositoryError> {
+        if git2::Repository::open_bare(self.repository_path(owner, repo_name)).is_ok() {
+            return Err(ForgeError::NameTaken {
+                forge: self.name().to_string(),
+                message: format!("{}/{} already exists", owner, repo_name),
+            }
+            .into());
+        }
+
+        let path = self.ensure_bare_repository(owner, repo_name)?;
+        std::fs::write(path.join("description"), format!("{}\n", description))?;
+        Ok(CreatedRepository { owner: owner.to_string(), url: self.clone_url(owner, repo_name) })
+    }
+
+    async fn delete_repository(&self, owner: &str, repo_name: &str) -> Result<(), RepositoryError> {
+        let path = self.repository_path(owner, repo_name);
+        if path.exists() {
+            std::fs::remove_dir_all(&path)?;
+        }
+        Ok(())
+    }
+
+    async fn rename_repository(&self, owner: &str, repo_name: &str, new_name: &str) -> Result<(), RepositoryError> {
+        let target = self.repository_path(owner, new_name);
+        if target.exists() {
+            return Err(ForgeError::NameTaken {
+                forge: self.name().to_string(),
+                message: format!("{}/{} already exists", owner, new_name),
+            }
+            .into());
+        }
+        std::fs::rename(self.repository_path(owner, repo_name), target)?;
+        Ok(())
+    }
+
+    async fn get_repository(&self, owner: &str, repo_name: &str) -> Result<Option<RemoteRepository>, RepositoryError> {
+        let path = self.repository_path(owner, repo_name);
+        if git2::Repository::open_bare(&path).is_err() {
+            return Ok(None);
+        }
+
+        Ok(Some(RemoteRepository {
+            owner: owner.to_string(),
+            name: repo_name.to_string(),
+            description: Self::read_description(&path),
+            private: true,
+            topics: Vec::new(),
+        }))
+    }
+
+    async fn list_repositories(&self, owner: &str) -> Result<Vec<RemoteRepository>, RepositoryError> {
+        let owner_dir = self.root.join(owner);
+        if !owner_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut repos = Vec::new();
+        for entry in std::fs::read_dir(&owner_dir)? {
+            let file_name = entry?.file_name();
+            let Some(repo_name) = file_name.to_str().and_then(|n| n.strip_suffix(".git")) else {
+                continue;
+            };
+            if let Some(repo) = self.get_repository(owner, repo_name).await? {
+                repos.push(repo);
+            }
+        }
+        repos.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(repos)
+    }
+
+    /// 本地裸仓库只支持设置默认分支（HEAD）
+    async fn apply_step(&self, owner: &str, repo_name: &str, step: &RepositoryStep) -> Result<(), RepositoryError> {
+        match step {
+            RepositoryStep::DefaultBranch(branch) => {
+                let repo = git2::Repository::open_bare(self.repository_path(owner, repo_name))?;
+                repo.set_head(&format!("refs/heads/{}", branch))?;
+                Ok(())
+            }
+            _ => Err(unsupported(self.name(), step)),
+        }
+    }
+
+    async fn authenticated_user(&self) -> Result<String, RepositoryError> {
+        Ok(std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .unwrap_or_else(|_| "local".to_string()))
+    }
+
+    fn clone_url(&self, owner: &str, repo_name: &str) -> String {
+        format!("file://{}", self.repository_path(owner, repo_name).display())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_create_get_and_delete_repository() {
+        let temp_dir = TempDir::new().unwrap();
+        let forge = LocalForge::new(temp_dir.path().to_path_buf());
+
+        assert!(!forge.repository_exists("acme", "abc").await.unwrap());
+
+        let url = forge.create_repository("acme", "abc", "Hidden repository").await.unwrap().url;
+        let path = temp_dir.path().join("acme").join("abc.git");
+        assert_eq!(url, format!("file://{}", path.display()));
+        assert!(git2::Repository::open_bare(&path).unwrap().is_bare());
+
+        // 重复创建返回名称冲突，不覆盖已有描述
+        let error = forge.create_repository("acme", "abc", "Other").await.unwrap_err();
+        assert!(matches!(error, RepositoryError::Forge(ForgeError::NameTaken { .. })));
+        let existing = crate::forge::ensure_repository(&forge, "acme", "abc", "Hidden repository").await.unwrap();
+        assert_eq!(existing.url, url);
+        assert!(!existing.created);
+        assert!(crate::forge::ensure_repository(&forge, "acme", "abc", "Other").await.is_err());
+
+        let repo = forge.get_repository("acme", "abc").await.unwrap().unwrap();
+        assert_eq!(repo.description.as_deref(), Some("Hidden repository"));
+
+        forge.create_repository("acme", "def", "Another").await.unwrap();
+        let names: Vec<String> = forge.list_repositories("acme").await.unwrap()
+            .into_iter().map(|r| r.name).collect();
+        assert_eq!(names, vec!["abc", "def"]);
+
+        forge.delete_repository("acme", "abc").await.unwrap();
+        assert!(!path.exists());
+    }
+}
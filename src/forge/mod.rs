@@ -0,0 +1,480 @@
+//! 代码托管平台（forge）抽象
+//!
+//! 隐藏仓库可以托管在 GitHub、GitLab、Gitea/Forgejo、Bitbucket 上，
+//! 或者作为裸仓库保存在本地目录中，每个组织在配置中选择自己的平台。
+
+use crate::config::{CloneProtocol, ConfigManager, ForgeKind, OrganizationConfig, RemoteCleanup, RepositoryTemplate, Visibility};
+use std::fmt;
+use crate::error::{ForgeError, RepositoryError};
+use crate::git_operations::GitCredentials;
+
+mod api;
+mod bitbucket;
+mod gitea;
+mod github;
+mod gitlab;
+mod local;
+
+pub use api::{ApiClient, ApiResponse};
+pub use bitbucket::BitbucketForge;
+pub use gitea::GiteaForge;
+pub use github::GitHubForge;
+pub use gitlab::GitLabForge;
+pub use local::LocalForge;
+
+/// 远程仓库信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteRepository {
+    pub owner: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub private: bool,
+    pub topics: Vec<String>,
+}
+
+impl RemoteRepository {
+    /// 是否带有 dot 的标记（描述一致或带有 `dot-hidden` 主题）
+    pub fn is_marked_hidden(&self, expected_description: &str) -> bool {
+        self.description.as_deref() == Some(expected_description)
+            || self.topics.iter().any(|topic| topic == HIDDEN_REPOSITORY_TOPIC)
+    }
+}
+
+/// 标记隐藏仓库的主题
+pub const HIDDEN_REPOSITORY_TOPIC: &str = "dot-hidden";
+
+/// 仓库的部署密钥
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeployKey {
+    pub id: String,
+    pub title: String,
+    pub key: String,
+    pub read_only: bool,
+}
+
+/// 创建后应用到隐藏仓库的一项设置（来自组织的 `repository_template`）
+#[derive(Debug, Clone, PartialEq)]
+pub enum RepositoryStep {
+    /// 可见性以及 wiki/issues/projects 开关
+    Settings {
+        visibility: Option<Visibility>,
+        wiki: Option<bool>,
+        issues: Option<bool>,
+        projects: Option<bool>,
+    },
+    Topics(Vec<String>),
+    DefaultBranch(String),
+    TeamAccess { team: String, permission: String },
+}
+
+impl RepositoryStep {
+    /// 按模板生成需要执行的步骤，未配置的项不生成
+    pub fn from_template(template: &RepositoryTemplate) -> Vec<Self> {
+        let mut steps = Vec::new();
+        if template.visibility.is_some()
+            || template.wiki.is_some()
+            || template.issues.is_some()
+            || template.projects.is_some()
+        {
+            steps.push(Self::Settings {
+                visibility: template.visibility,
+                wiki: template.wiki,
+                issues: template.issues,
+                projects: template.projects,
+            });
+        }
+        if !template.topics.is_empty() {
+            steps.push(Self::Topics(template.topics.clone()));
+        }
+        if let Some(branch) = &template.default_branch {
+            steps.push(Self::DefaultBranch(branch.clone()));
+        }
+        for access in &template.teams {
+            steps.push(Self::TeamAccess { team: access.team.clone(), permission: access.permission.clone() });
+        }
+        steps
+    }
+}
+
+impl fmt::Display for RepositoryStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Settings { visibility, wiki, issues, projects } => {
+                let mut parts = Vec::new();
+                if let Some(visibility) = visibility {
+                    parts.push(format!("visibility {:?}", visibility).to_lowercase());
+                }
+                for (feature, enabled) in [("wiki", wiki), ("issues", issues), ("projects", projects)] {
+                    if let Some(enabled) = enabled {
+                        parts.push(format!("{} {}", feature, if *enabled { "enabled" } else { "disabled" }));
+                    }
+                }
+                write!(f, "Settings: {}", parts.join(", "))
+            }
+            Self::Topics(topics) => write!(f, "Topics: {}", topics.join(", ")),
+            Self::DefaultBranch(branch) => write!(f, "Default branch: {}", branch),
+            Self::TeamAccess { team, permission } => write!(f, "Team access: {} ({})", team, permission),
+        }
+    }
+}
+
+/// 克隆地址使用的主机和协议
+///
+/// 默认从 API 地址推导（`https://api.github.com` -> `github.com`），
+/// GitHub Enterprise 等自建服务可以在组织配置中单独指定 SSH/HTTPS 主机。
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteHosts {
+    pub ssh_host: String,
+    pub https_host: String,
+    pub protocol: CloneProtocol,
+}
+
+impl RemoteHosts {
+    pub fn from_api_base(api_base: &str) -> Self {
+        Self {
+            ssh_host: ssh_host_from_api_base(api_base),
+            https_host: https_host_from_api_base(api_base),
+            protocol: CloneProtocol::default(),
+        }
+    }
+
+    /// 使用组织配置覆盖默认主机和协议
+    pub fn resolve(org_config: &OrganizationConfig, default_api_base: &str) -> Self {
+        let api_base = org_config.api_base_url.as_deref().unwrap_or(default_api_base);
+        let mut hosts = Self::from_api_base(api_base);
+        if let Some(ssh_host) = &org_config.ssh_host {
+            hosts.ssh_host = ssh_host.clone();
+        }
+        if let Some(https_host) = &org_config.https_host {
+            hosts.https_host = https_host.clone();
+        }
+        hosts.protocol = org_config.protocol.unwrap_or_default();
+        hosts
+    }
+
+    /// 按首选协议生成克隆 URL
+    pub fn url(&self, owner: &str, repo_name: &str) -> String {
+        match self.protocol {
+            CloneProtocol::Ssh => format!("git@{}:{}/{}.git", self.ssh_host, owner, repo_name),
+            CloneProtocol::Https => format!("https://{}/{}/{}.git", self.https_host, owner, repo_name),
+        }
+    }
+
+    /// 网页地址，例如 `https://github.com`
+    pub fn web_url(&self) -> String {
+        format!("https://{}", self.https_host)
+    }
+}
+
+/// 代码托管平台
+#[async_trait::async_trait]
+pub trait Forge: Send + Sync {
+    /// 平台名称，用于输出
+    fn name(&self) -> &'static str;
+
+    /// 创建私有仓库，返回仓库实际所在的账户和克隆 URL
+    ///
+    /// 同名仓库已存在时返回 [`ForgeError::NameTaken`]，由调用方决定是否复用（见 [`ensure_repository`]）。
+    async fn create_repository(
+        &self,
+        owner: &str,
+        repo_name: &str,
+        description: &str,
+    ) -> Result<CreatedRepository, RepositoryError>;
+
+    /// 删除仓库（不存在时视为成功）
+    async fn delete_repository(&self, owner: &str, repo_name: &str) -> Result<(), RepositoryError>;
+
+    /// 重命名仓库（回滚时移入 `dot-trash-` 前缀的名称，或随目录一起改名）
+    async fn rename_repository(&self, owner: &str, repo_name: &str, new_name: &str) -> Result<(), RepositoryError> {
+        let _ = (owner, new_name);
+        Err(unsupported(self.name(), format!("Renaming {}", repo_name)))
+    }
+
+    /// 归档仓库（只读保留）
+    async fn archive_repository(&self, owner: &str, repo_name: &str) -> Result<(), RepositoryError> {
+        let _ = owner;
+        Err(unsupported(self.name(), format!("Archiving {}", repo_name)))
+    }
+
+    /// 获取仓库信息，不存在时返回 None
+    async fn get_repository(&self, owner: &str, repo_name: &str) -> Result<Option<RemoteRepository>, RepositoryError>;
+
+    async fn repository_exists(&self, owner: &str, repo_name: &str) -> Result<bool, RepositoryError> {
+        Ok(self.get_repository(owner, repo_name).await?.is_some())
+    }
+
+    /// 列出组织下的所有仓库（用于批量校验、清理隐藏仓库）
+    async fn list_repositories(&self, owner: &str) -> Result<Vec<RemoteRepository>, RepositoryError> {
+        Err(unsupported(self.name(), format!("Listing repositories of '{}'", owner)))
+    }
+
+    /// 为仓库添加部署密钥
+    async fn add_deploy_key(
+        &self,
+        owner: &str,
+        repo_name: &str,
+        title: &str,
+        public_key: &str,
+        read_only: bool,
+    ) -> Result<DeployKey, RepositoryError> {
+        let _ = (owner, title, public_key, read_only);
+        Err(unsupported(self.name(), format!("Deploy keys for {}", repo_name)))
+    }
+
+    /// 列出仓库的部署密钥
+    async fn list_deploy_keys(&self, owner: &str, repo_name: &str) -> Result<Vec<DeployKey>, RepositoryError> {
+        let _ = owner;
+        Err(unsupported(self.name(), format!("Deploy keys for {}", repo_name)))
+    }
+
+    /// 删除仓库的部署密钥
+    async fn delete_deploy_key(&self, owner: &str, repo_name: &str, id: &str) -> Result<(), RepositoryError> {
+        let _ = (owner, id);
+        Err(unsupported(self.name(), format!("Deploy keys for {}", repo_name)))
+    }
+
+    /// SSH 克隆使用的主机（本地平台为 None）
+    fn ssh_host(&self) -> Option<String> {
+        None
+    }
+
+    /// 当前认证用户的用户名
+    async fn authenticated_user(&self) -> Result<String, RepositoryError>;
+
+    /// 仓库的克隆 URL
+    fn clone_url(&self, owner: &str, repo_name: &str) -> String;
+
+    /// 对新建的仓库应用一项设置
+    async fn apply_step(&self, owner: &str, repo_name: &str, step: &RepositoryStep) -> Result<(), RepositoryError> {
+        let _ = (owner, repo_name);
+        Err(unsupported(self.name(), step))
+    }
+
+    /// 通过 HTTPS 克隆和推送时使用的凭据（没有令牌时为 None）
+    fn credentials(&self) -> Option<GitCredentials> {
+        None
+    }
+}
+
+/// [`Forge::create_repository`] 新建的仓库
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreatedRepository {
+    /// 仓库实际所在的账户（GitHub 开启 `allow_user_fallback` 时可能是个人账户而不是组织）
+    pub owner: String,
+    pub url: String,
+}
+
+/// [`ensure_repository`] 的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnsuredRepository {
+    pub owner: String,
+    pub url: String,
+    /// 本次新建（而不是复用已有仓库）
+    pub created: bool,
+}
+
+/// 创建隐藏仓库；同名仓库已存在时，只有带有 dot 标记（描述一致或 `dot-hidden` 主题）才复用
+///
+/// 避免把组织中恰好同名的无关仓库当作隐藏仓库推送。
+pub async fn ensure_repository(
+    forge: &dyn Forge,
+    owner: &str,
+    repo_name: &str,
+    description: &str,
+) -> Result<EnsuredRepository, RepositoryError> {
+    let error = match forge.create_repository(owner, repo_name, description).await {
+        Ok(CreatedRepository { owner, url }) => return Ok(EnsuredRepository { owner, url, created: true }),
+        Err(RepositoryError::Forge(error @ ForgeError::NameTaken { .. })) => error,
+        Err(e) => return Err(e),
+    };
+
+    match forge.get_repository(owner, repo_name).await? {
+        Some(existing) if existing.is_marked_hidden(description) => {
+            println!("  Repository {}/{} already exists, reusing it", owner, repo_name);
+            Ok(EnsuredRepository {
+                owner: owner.to_string(),
+                url: forge.clone_url(owner, repo_name),
+                created: false,
+            })
+        }
+        Some(existing) => Err(ForgeError::NameTaken {
+            forge: forge.name().to_string(),
+            message: format!(
+                "{}/{} exists but is not a dot hidden repository (description: {:?})",
+                owner,
+                repo_name,
+                existing.description.unwrap_or_default()
+            ),
+        }
+        .into()),
+        None => Err(error.into()),
+    }
+}
+
+/// 依次应用模板中的设置并输出每一步的结果，遇到第一个失败即返回
+///
+/// 调用方负责在失败时回滚刚创建的仓库。
+pub async fn apply_template(
+    forge: &dyn Forge,
+    owner: &str,
+    repo_name: &str,
+    template: &RepositoryTemplate,
+) -> Result<(), RepositoryError> {
+    for step in RepositoryStep::from_template(template) {
+        match forge.apply_step(owner, repo_name, &step).await {
+            Ok(()) => println!("  ✓ {}", step),
+            Err(e) => {
+                println!("  ✗ {}: {}", step, e);
+                return Err(e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 移除远程仓库时保留下来的仓库名：`dot-trash-<时间戳>-<原名>`
+pub fn trash_name(repo_name: &str) -> String {
+    format!("dot-trash-{}-{}", chrono::Utc::now().format("%Y%m%d%H%M%S"), repo_name)
+}
+
+/// 按 `mode` 移除远程仓库：删除，或改名为 `dot-trash-*`（`Archive` 时再归档）
+///
+/// 返回改名后的仓库名，直接删除时返回 `None`。平台不支持归档时只改名。
+pub async fn dispose_repository(
+    forge: &dyn Forge,
+    owner: &str,
+    repo_name: &str,
+    mode: RemoteCleanup,
+) -> Result<Option<String>, RepositoryError> {
+    if mode == RemoteCleanup::Delete {
+        println!("  Deleting remote repository {}/{}", owner, repo_name);
+        forge.delete_repository(owner, repo_name).await?;
+        return Ok(None);
+    }
+
+    let trash_name = trash_name(repo_name);
+    println!("  Moving remote repository {}/{} to {}/{}", owner, repo_name, owner, trash_name);
+    forge.rename_repository(owner, repo_name, &trash_name).await?;
+
+    if mode == RemoteCleanup::Archive {
+        match forge.archive_repository(owner, &trash_name).await {
+            Ok(()) => println!("  Archived {}/{}", owner, trash_name),
+            Err(RepositoryError::IoError(e)) if e.kind() == std::io::ErrorKind::Unsupported => {}
+            Err(e) => eprintln!("Warning: Failed to archive {}/{}: {}", owner, trash_name, e),
+        }
+    }
+    Ok(Some(trash_name))
+}
+
+/// 根据组织配置创建对应平台的客户端
+pub fn for_organization(config: &ConfigManager, organization: &str) -> Box<dyn Forge> {
+    from_config(&config.get_organization_config(organization), config.get_forge_token(organization))
+}
+
+/// 根据平台配置和令牌创建客户端
+pub fn from_config(org_config: &OrganizationConfig, token: Option<String>) -> Box<dyn Forge> {
+    let api_base = org_config.api_base_url.clone();
+
+    match org_config.forge {
+        ForgeKind::GitHub => Box::new(GitHubForge::new(token, api_base)
+            .with_hosts(RemoteHosts::resolve(org_config, github::DEFAULT_API_BASE))
+            .with_user_fallback(org_config.allow_user_fallback)),
+        ForgeKind::GitLab => Box::new(GitLabForge::new(token, api_base)
+            .with_hosts(RemoteHosts::resolve(org_config, gitlab::DEFAULT_API_BASE))),
+        ForgeKind::Gitea => Box::new(GiteaForge::new(token, api_base)
+            .with_hosts(RemoteHosts::resolve(org_config, gitea::DEFAULT_API_BASE))),
+        ForgeKind::Bitbucket => Box::new(BitbucketForge::new(token, api_base)
+            .with_hosts(RemoteHosts::resolve(org_config, bitbucket::DEFAULT_API_BASE))),
+        ForgeKind::Local => Box::new(local_forge(org_config)),
+    }
+}
+
+/// 本地平台的客户端（未配置根目录时使用 ~/dot-remotes）
+pub fn local_forge(org_config: &OrganizationConfig) -> LocalForge {
+    LocalForge::new(org_config.root.clone().unwrap_or_else(local::default_root))
+}
+
+/// 从 API 地址推导 SSH 主机名（不含端口），例如 `https://api.github.com` -> `github.com`，
+/// `https://gitlab.example.com:8443/api/v4` -> `gitlab.example.com`
+pub(crate) fn ssh_host_from_api_base(api_base: &str) -> String {
+    let host = https_host_from_api_base(api_base);
+    host.split(':').next().unwrap_or(&host).to_string()
+}
+
+/// 从 API 地址推导 HTTPS 克隆主机，保留非默认端口（`https://ghe.example:8443/api/v3` -> `ghe.example:8443`）
+pub(crate) fn https_host_from_api_base(api_base: &str) -> String {
+    let without_scheme = api_base
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .unwrap_or(api_base);
+    let authority = without_scheme.split('/').next().unwrap_or(without_scheme);
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    let host = host.strip_suffix(":443").unwrap_or(host);
+    host.strip_prefix("api.").unwrap_or(host).to_string()
+}
+
+pub(crate) fn request_error(e: reqwest::Error) -> RepositoryError {
+    RepositoryError::IoError(std::io::Error::other(format!("Failed to send request: {}", e)))
+}
+
+pub(crate) fn api_error(forge: &str, response: &ApiResponse) -> RepositoryError {
+    response.error(forge).into()
+}
+
+/// 平台不支持的操作
+pub(crate) fn unsupported(forge: &str, operation: impl fmt::Display) -> RepositoryError {
+    RepositoryError::IoError(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!("{} is not supported on {}", operation, forge),
+    ))
+}
+
+pub(crate) fn missing_token(forge: &str) -> RepositoryError {
+    ForgeError::Authentication {
+        forge: forge.to_string(),
+        message: "no token configured. Set \"token\" for the organization in ~/.dot/dot.conf".to_string(),
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_host_from_api_base() {
+        assert_eq!(ssh_host_from_api_base("https://api.github.com"), "github.com");
+        assert_eq!(ssh_host_from_api_base("https://gitlab.com/api/v4"), "gitlab.com");
+        assert_eq!(ssh_host_from_api_base("https://gitlab.example.com:8443/api/v4"), "gitlab.example.com");
+        assert_eq!(ssh_host_from_api_base("https://api.bitbucket.org/2.0"), "bitbucket.org");
+        assert_eq!(ssh_host_from_api_base("http://127.0.0.1:3000/api/v1"), "127.0.0.1");
+        assert_eq!(https_host_from_api_base("https://api.github.com"), "github.com");
+        assert_eq!(https_host_from_api_base("https://ghe.example:8443/api/v3"), "ghe.example:8443");
+        assert_eq!(https_host_from_api_base("https://ghe.example:443/api/v3"), "ghe.example");
+    }
+
+    #[test]
+    fn test_remote_hosts_for_enterprise() {
+        let org_config = OrganizationConfig {
+            api_base_url: Some("https://ghe.example.com/api/v3".to_string()),
+            ..Default::default()
+        };
+        let hosts = RemoteHosts::resolve(&org_config, github::DEFAULT_API_BASE);
+        assert_eq!(hosts.url("acme", "abc"), "git@ghe.example.com:acme/abc.git");
+        assert_eq!(hosts.web_url(), "https://ghe.example.com");
+
+        let org_config = OrganizationConfig {
+            ssh_host: Some("ssh.ghe.example.com".to_string()),
+            https_host: Some("ghe.example.com".to_string()),
+            protocol: Some(CloneProtocol::Https),
+            ..org_config
+        };
+        let hosts = RemoteHosts::resolve(&org_config, github::DEFAULT_API_BASE);
+        assert_eq!(hosts.ssh_host, "ssh.ghe.example.com");
+        assert_eq!(hosts.url("acme", "abc"), "https://ghe.example.com/acme/abc.git");
+
+        let forge = from_config(&OrganizationConfig::default(), None);
+        assert_eq!(forge.clone_url("acme", ".index"), "git@github.com:acme/.index.git");
+    }
+}
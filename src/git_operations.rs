@@ -5,6 +5,87 @@ use std::process::Command;
 
 pub struct GitOperations;
 
+/// HTTPS 访问令牌凭据
+///
+/// 令牌只通过凭据回调（git2）或内联凭据助手的环境变量（git 命令行）提供，
+/// 不会写入远程 URL 或 git 配置文件，并且只提供给平台的 HTTPS 主机。
+#[derive(Clone)]
+pub struct GitCredentials {
+    /// 接受令牌的 HTTPS 主机（可以带端口），例如 `github.com`
+    pub host: String,
+    pub username: String,
+    pub token: String,
+}
+
+impl std::fmt::Debug for GitCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GitCredentials")
+            .field("host", &self.host)
+            .field("username", &self.username)
+            .field("token", &"<redacted>")
+            .finish()
+    }
+}
+
+/// 内联凭据助手：只响应 `get`，并且只在请求的协议为 HTTPS、主机为 `DOT_GIT_HOST` 时
+/// 从环境变量读取用户名和令牌（重定向到其他主机时不提供令牌）
+const CREDENTIAL_HELPER: &str = concat!(
+    "!f() { test \"$1\" = get || return 0; protocol=; host=; ",
+    "while IFS== read -r key value; do test -z \"$key\" && break; ",
+    "case \"$key\" in protocol) protocol=$value;; host) host=$value;; esac; done; ",
+    "if test \"$protocol\" = https && test \"$host\" = \"$DOT_GIT_HOST\"; then ",
+    "echo \"username=$DOT_GIT_USERNAME\"; echo \"password=$DOT_GIT_TOKEN\"; fi; }; f"
+);
+
+impl GitCredentials {
+    pub fn new(host: impl Into<String>, username: impl Into<String>, token: impl Into<String>) -> Self {
+        Self { host: host.into(), username: username.into(), token: token.into() }
+    }
+    
+    /// URL 是否指向接受令牌的 HTTPS 主机
+    pub(crate) fn accepts(&self, url: &str) -> bool {
+        let Some(rest) = url.strip_prefix("https://") else {
+            return false;
+        };
+        let authority = rest.split('/').next().unwrap_or_default();
+        let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+        let normalize = |host: &str| host.strip_suffix(":443").unwrap_or(host).to_ascii_lowercase();
+        normalize(host) == normalize(&self.host)
+    }
+    
+    /// 为 git 命令行配置凭据助手（清除用户配置的其他助手，禁止交互式提示）
+    pub fn apply(&self, command: &mut Command) {
+        command
+            .args(["-c", "credential.helper=", "-c"])
+            .arg(format!("credential.helper={}", CREDENTIAL_HELPER))
+            .env("DOT_GIT_HOST", &self.host)
+            .env("DOT_GIT_USERNAME", &self.username)
+            .env("DOT_GIT_TOKEN", &self.token)
+            .env("GIT_TERMINAL_PROMPT", "0");
+    }
+    
+    /// git2 的凭据回调
+    pub fn remote_callbacks(&self) -> git2::RemoteCallbacks<'_> {
+        let mut callbacks = git2::RemoteCallbacks::new();
+        let mut attempts = 0;
+        callbacks.credentials(move |url, username_from_url, allowed| {
+            attempts += 1;
+            if attempts > 3 {
+                return Err(git2::Error::from_str("authentication failed"));
+            }
+            if allowed.contains(git2::CredentialType::USER_PASS_PLAINTEXT) {
+                if !self.accepts(url) {
+                    return Err(git2::Error::from_str(&format!("no credentials for {}", url)));
+                }
+                git2::Cred::userpass_plaintext(&self.username, &self.token)
+            } else {
+                git2::Cred::ssh_key_from_agent(username_from_url.unwrap_or("git"))
+            }
+        });
+        callbacks
+    }
+}
+
 impl GitOperations {
     /// 检查 git 是否可用
     pub fn check_git_available() -> Result<(), RepositoryError> {
@@ -40,11 +121,41 @@ impl GitOperations {
         let base_key = Self::generate_base_key(remote_url)?;
         
         match directory {
-            Some(dir) => Ok(format!("{}/{}", base_key, dir)),
+            Some(dir) => Ok(format!("{}/{}", base_key, Self::normalize_hidden_path(dir)?)),
             None => Ok(base_key),
         }
     }
     
+    /// 规范化隐藏目录路径：统一使用 `/` 分隔，去掉 `.` 和空的路径段
+    ///
+    /// 拒绝绝对路径、`..` 和 `.git`，保证隐藏目录位于项目内部。
+    pub fn normalize_hidden_path(directory: &str) -> Result<String, RepositoryError> {
+        let invalid = |reason: &str| RepositoryError::InvalidHiddenDirectory {
+            path: directory.to_string(),
+            reason: reason.to_string(),
+        };
+        
+        let unified = directory.replace('\\', "/");
+        if unified.starts_with('/') || unified.as_bytes().get(1) == Some(&b':') {
+            return Err(invalid("absolute paths are not allowed"));
+        }
+        
+        let mut components = Vec::new();
+        for component in unified.split('/') {
+            match component {
+                "" | "." => continue,
+                ".." => return Err(invalid("'..' is not allowed")),
+                ".git" => return Err(invalid("'.git' is reserved")),
+                _ => components.push(component),
+            }
+        }
+        if components.is_empty() {
+            return Err(invalid("the path is empty"));
+        }
+        
+        Ok(components.join("/"))
+    }
+    
     /// 生成基础 Repository Key
     pub fn generate_base_key(remote_url: &str) -> Result<String, RepositoryError> {
         // 移除协议部分 (everything before and including @)
@@ -82,11 +193,13 @@ impl GitOperations {
     /// 添加文件到 git index
     pub fn add_files<P: AsRef<Path>>(repo_path: P, files: &[String]) -> Result<(), RepositoryError> {
         let repo = Repository::open(repo_path)?;
+        let workdir = repo.workdir().map(Path::to_path_buf).unwrap_or_default();
         let mut index = repo.index()?;
         
+        // 路径相对于仓库的工作目录
         for file in files {
             let file_path = Path::new(file);
-            if file_path.exists() {
+            if workdir.join(file_path).exists() {
                 index.add_path(file_path)?;
             }
         }
@@ -139,8 +252,25 @@ impl GitOperations {
         Ok(commit_id)
     }
     
+    /// 创建 git 命令，提供凭据时通过凭据助手认证
+    pub fn git_command(credentials: Option<&GitCredentials>) -> Command {
+        let mut command = Command::new("git");
+        if let Some(credentials) = credentials {
+            credentials.apply(&mut command);
+        }
+        command
+    }
+    
     /// 推送到远程仓库
     pub fn push<P: AsRef<Path>>(repo_path: P) -> Result<(), RepositoryError> {
+        Self::push_with_credentials(repo_path, None)
+    }
+    
+    /// 推送到远程仓库，HTTPS 远程使用给定的凭据
+    pub fn push_with_credentials<P: AsRef<Path>>(
+        repo_path: P,
+        credentials: Option<&GitCredentials>
+    ) -> Result<(), RepositoryError> {
         let path = repo_path.as_ref();
         
         // 获取当前分支名
@@ -149,10 +279,10 @@ impl GitOperations {
         let branch_name = head.shorthand().unwrap_or("main");
         
         // 使用 git 命令行推送，更可靠地处理 SSH 认证和首次推送
-        let output = std::process::Command::new("git")
+        let output = Self::git_command(credentials)
             .args(["-C", path.to_str().unwrap_or("."), "push", "-u", "origin", branch_name])
             .output()
-            .map_err(|e| RepositoryError::IoError(e))?;
+            .map_err(RepositoryError::IoError)?;
         
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -160,8 +290,7 @@ impl GitOperations {
             if stderr.contains("Everything up-to-date") || stderr.contains("up to date") {
                 return Ok(());
             }
-            return Err(RepositoryError::IoError(std::io::Error::new(
-                std::io::ErrorKind::Other,
+            return Err(RepositoryError::IoError(std::io::Error::other(
                 format!("git push failed: {}", stderr)
             )));
         }
@@ -169,6 +298,59 @@ impl GitOperations {
         Ok(())
     }
     
+    /// 远程仓库的分支及其提交（`git ls-remote --heads` 的输出），空仓库返回空字符串
+    pub fn remote_heads(url: &str, credentials: Option<&GitCredentials>) -> Result<String, RepositoryError> {
+        let output = Self::git_command(credentials)
+            .args(["ls-remote", "--heads", url])
+            .output()
+            .map_err(RepositoryError::IoError)?;
+        
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(RepositoryError::IoError(std::io::Error::other(
+                format!("git ls-remote failed: {}", stderr.trim())
+            )));
+        }
+        
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+    
+    /// 当前分支是否有未推送到上游的提交（没有上游分支时视为未推送，空仓库视为没有）
+    pub fn has_unpushed_commits<P: AsRef<Path>>(repo_path: P) -> Result<bool, RepositoryError> {
+        let repo = Repository::open(repo_path)?;
+        let head = match repo.head() {
+            Ok(head) => head,
+            Err(_) => return Ok(false),
+        };
+        let Some(local) = head.target() else {
+            return Ok(false);
+        };
+
+        let branch = git2::Branch::wrap(head);
+        let upstream = match branch.upstream() {
+            Ok(upstream) => upstream,
+            Err(_) => return Ok(true),
+        };
+        let Some(remote) = upstream.get().target() else {
+            return Ok(true);
+        };
+
+        let (ahead, _) = repo.graph_ahead_behind(local, remote)?;
+        Ok(ahead > 0)
+    }
+
+    /// 工作区和暂存区是否没有任何更改（包括未跟踪、类型变化、重命名和冲突的文件，不包括忽略的文件）
+    pub fn is_clean<P: AsRef<Path>>(repo_path: P) -> Result<bool, RepositoryError> {
+        let repo = Repository::open(repo_path)?;
+        let mut options = git2::StatusOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false);
+        let statuses = repo.statuses(Some(&mut options))?;
+        Ok(statuses.iter().all(|entry| entry.status() == git2::Status::CURRENT))
+    }
+    
     /// 获取 git 状态
     pub fn get_status<P: AsRef<Path>>(repo_path: P) -> Result<String, RepositoryError> {
         let repo = Repository::open(repo_path)?;
@@ -207,6 +389,24 @@ impl GitOperations {
         Repository::clone(url, path).map_err(RepositoryError::GitError)
     }
     
+    /// 克隆仓库，HTTPS 远程通过凭据回调认证
+    pub fn clone_repository_with_credentials(
+        url: &str,
+        path: &Path,
+        credentials: Option<&GitCredentials>
+    ) -> Result<Repository, RepositoryError> {
+        let Some(credentials) = credentials else {
+            return Self::clone_repository(url, path);
+        };
+        
+        let mut fetch_options = git2::FetchOptions::new();
+        fetch_options.remote_callbacks(credentials.remote_callbacks());
+        git2::build::RepoBuilder::new()
+            .fetch_options(fetch_options)
+            .clone(url, path)
+            .map_err(RepositoryError::GitError)
+    }
+    
     /// 获取 git signature
     fn get_signature(repo: &Repository) -> Result<Signature<'_>, RepositoryError> {
         let config = repo.config()?;
@@ -253,6 +453,22 @@ mod tests {
         assert_eq!(key, "github.com:user/repo/.kiro");
     }
     
+    #[test]
+    fn test_nested_hidden_paths_are_normalized() {
+        let url = "git@github.com:user/repo.git";
+        for dir in ["config/.secrets", "./config//.secrets/", "config\\.secrets"] {
+            let key = GitOperations::generate_repository_key(url, Some(dir)).unwrap();
+            assert_eq!(key, "github.com:user/repo/config/.secrets");
+        }
+        
+        for dir in ["../.kiro", ".kiro/../../x", "/etc/.kiro", "C:\\.kiro", ".git", "sub/.git", "", "./"] {
+            assert!(
+                matches!(GitOperations::normalize_hidden_path(dir), Err(RepositoryError::InvalidHiddenDirectory { .. })),
+                "{} should be rejected", dir
+            );
+        }
+    }
+    
     #[test]
     fn test_generate_repository_key_without_directory() {
         let url = "git@github.com:user/repo.git";
@@ -273,7 +489,7 @@ mod tests {
         let repo_path = temp_dir.path();
         
         // 初始化仓库
-        let repo = GitOperations::init_repository(repo_path).unwrap();
+        let _repo = GitOperations::init_repository(repo_path).unwrap();
         assert!(GitOperations::is_git_initialized(repo_path));
         
         // 创建一个测试文件
@@ -287,4 +503,66 @@ mod tests {
         let status = GitOperations::get_status(repo_path).unwrap();
         assert!(status.contains("test.txt"));
     }
-}
\ No newline at end of file
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_is_clean_detects_type_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        let repo = GitOperations::init_repository(repo_path).unwrap();
+        repo.config().unwrap().set_str("user.name", "testuser").unwrap();
+        repo.config().unwrap().set_str("user.email", "test@example.com").unwrap();
+        std::fs::write(repo_path.join("notes.md"), "notes").unwrap();
+        GitOperations::add_files(repo_path, &["notes.md".to_string()]).unwrap();
+        GitOperations::commit(repo_path, "notes").unwrap();
+        assert!(GitOperations::is_clean(repo_path).unwrap());
+        
+        // 文件变成符号链接：get_status 不列出，但内容会随目录一起丢失
+        std::fs::remove_file(repo_path.join("notes.md")).unwrap();
+        std::os::unix::fs::symlink("/etc/hostname", repo_path.join("notes.md")).unwrap();
+        assert!(!GitOperations::is_clean(repo_path).unwrap());
+    }
+    
+    #[test]
+    fn test_credential_helper_supplies_token() {
+        let credentials = GitCredentials::new("example.com", "x-access-token", "secret-token");
+        let fill = |request: &[u8]| {
+            let mut child = GitOperations::git_command(Some(&credentials))
+                .args(["credential", "fill"])
+                .stdin(std::process::Stdio::piped())
+                .stdout(std::process::Stdio::piped())
+                .stderr(std::process::Stdio::null())
+                .spawn()
+                .unwrap();
+            {
+                use std::io::Write;
+                let stdin = child.stdin.as_mut().unwrap();
+                stdin.write_all(request).unwrap();
+            }
+            let output = child.wait_with_output().unwrap();
+            (output.status.success(), String::from_utf8_lossy(&output.stdout).to_string())
+        };
+        
+        let (success, stdout) = fill(b"protocol=https\nhost=example.com\n\n");
+        assert!(success);
+        assert!(stdout.contains("username=x-access-token"));
+        assert!(stdout.contains("password=secret-token"));
+        assert!(!format!("{:?}", credentials).contains("secret-token"));
+        
+        // 其他主机或非 HTTPS 请求拿不到令牌（没有提示时 git credential fill 失败）
+        for request in [&b"protocol=https\nhost=evil.example.org\n\n"[..], b"protocol=http\nhost=example.com\n\n"] {
+            let (success, stdout) = fill(request);
+            assert!(!success || !stdout.contains("secret-token"), "{}", stdout);
+        }
+    }
+    
+    #[test]
+    fn test_credentials_only_for_configured_host() {
+        let credentials = GitCredentials::new("ghe.example.com", "x-access-token", "secret-token");
+        assert!(credentials.accepts("https://ghe.example.com/acme/repo.git"));
+        assert!(credentials.accepts("https://user@GHE.example.com/acme/repo.git"));
+        assert!(!credentials.accepts("https://ghe.example.com.evil.org/acme/repo.git"));
+        assert!(!credentials.accepts("https://evil.org/ghe.example.com/repo.git"));
+        assert!(!credentials.accepts("http://ghe.example.com/acme/repo.git"));
+    }
+}
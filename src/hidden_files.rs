@@ -0,0 +1,400 @@
+//! 隐藏文件
+//!
+//! 项目根目录下的单个文件（如 `.env`、`.npmrc`）保存在项目的 `.dot-files` 隐藏仓库中，
+//! 按相同的相对路径存放，再以符号链接或副本的形式呈现在父仓库的工作区里。
+//! `.dot-files` 仓库索引中的文件就是项目的隐藏文件列表。
+
+use crate::config::HiddenFileMode;
+use crate::error::RepositoryError;
+use crate::git_operations::GitOperations;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 保存隐藏文件的隐藏目录
+pub const STORE_DIRECTORY: &str = ".dot-files";
+
+/// 隐藏文件在工作区中的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileState {
+    /// 指向 `.dot-files` 的符号链接
+    Linked,
+    /// 与 `.dot-files` 中内容一致的副本
+    Copied,
+    /// 内容与 `.dot-files` 不同的副本（`dot add` 时同步）
+    Modified,
+    /// 工作区中不存在
+    Missing,
+    /// 工作区中是无关的文件或指向其他位置的链接
+    Conflict,
+}
+
+impl std::fmt::Display for FileState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = match self {
+            FileState::Linked => "linked",
+            FileState::Copied => "copied",
+            FileState::Modified => "modified",
+            FileState::Missing => "missing",
+            FileState::Conflict => "conflict",
+        };
+        f.write_str(state)
+    }
+}
+
+fn store_path(project_path: &Path, file: &str) -> PathBuf {
+    project_path.join(STORE_DIRECTORY).join(file)
+}
+
+/// 从工作区中的文件指向 `.dot-files` 中对应文件的相对链接目标
+fn link_target(file: &str) -> PathBuf {
+    let depth = file.matches('/').count();
+    let mut target = PathBuf::new();
+    for _ in 0..depth {
+        target.push("..");
+    }
+    target.join(STORE_DIRECTORY).join(file)
+}
+
+/// `.dot-files` 仓库中登记的隐藏文件（仓库不存在时为空）
+pub fn tracked_files(project_path: &Path) -> Result<Vec<String>, RepositoryError> {
+    let store = project_path.join(STORE_DIRECTORY);
+    if !store.join(".git").exists() {
+        return Ok(Vec::new());
+    }
+
+    let repo = git2::Repository::open(&store)?;
+    let index = repo.index()?;
+    let mut files: Vec<String> = index
+        .iter()
+        .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// 已隐藏的文件，用于撤销 [`hide`]
+#[derive(Debug)]
+pub struct HiddenFile {
+    pub file: String,
+    /// 排除规则是否由本次隐藏添加
+    excluded: bool,
+}
+
+impl HiddenFile {
+    /// 撤销隐藏：从 `.dot-files` 的索引中移除，删除工作区中的链接或副本，
+    /// 把文件移回原位置，并删除本次添加的排除规则
+    pub fn undo(&self, project_path: &Path) -> Result<(), RepositoryError> {
+        let file = self.file.as_str();
+        let stored = store_path(project_path, file);
+
+        if let Ok(repo) = git2::Repository::open(project_path.join(STORE_DIRECTORY)) {
+            let mut index = repo.index()?;
+            if index.get_path(Path::new(file), 0).is_some() {
+                index.remove_path(Path::new(file))?;
+                index.write()?;
+            }
+        }
+
+        if stored.is_file() {
+            let target = project_path.join(file);
+            match fs::symlink_metadata(&target) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                _ => match state(project_path, file, HiddenFileMode::Copy)? {
+                    FileState::Linked | FileState::Copied => fs::remove_file(&target)?,
+                    _ => {
+                        return Err(RepositoryError::InvalidHiddenDirectory {
+                            path: file.to_string(),
+                            reason: format!("changed in the working tree; the original is kept in {}", STORE_DIRECTORY),
+                        })
+                    }
+                },
+            }
+            fs::rename(&stored, &target)?;
+        }
+
+        if self.excluded {
+            remove_exclude(project_path, file)?;
+        }
+        Ok(())
+    }
+}
+
+/// 打开 `.dot-files` 仓库，它必须是已检出的 git 仓库
+fn open_store(project_path: &Path) -> Result<git2::Repository, RepositoryError> {
+    let invalid = || RepositoryError::InvalidHiddenDirectory {
+        path: STORE_DIRECTORY.to_string(),
+        reason: "not a checked-out git repository; run 'dot clone' or 'dot pull' first".to_string(),
+    };
+    let repo = git2::Repository::open(project_path.join(STORE_DIRECTORY)).map_err(|_| invalid())?;
+    if repo.is_bare() {
+        return Err(invalid());
+    }
+    repo.index()?;
+    Ok(repo)
+}
+
+/// 将工作区中的文件移入 `.dot-files` 并暂存，然后在原位置呈现
+///
+/// 文件同时写入父仓库的 `.git/info/exclude`，避免被父仓库跟踪。任何一步失败时
+/// 文件移回原位置，排除规则也被删除。
+pub fn hide(project_path: &Path, file: &str, mode: HiddenFileMode) -> Result<HiddenFile, RepositoryError> {
+    let source = project_path.join(file);
+    if !source.is_file() || source.is_symlink() {
+        return Err(RepositoryError::InvalidHiddenDirectory {
+            path: file.to_string(),
+            reason: "not a regular file".to_string(),
+        });
+    }
+
+    let parent = git2::Repository::open(project_path)?;
+    if parent.index()?.get_path(Path::new(file), 0).is_some() {
+        return Err(RepositoryError::InvalidHiddenDirectory {
+            path: file.to_string(),
+            reason: "tracked by the parent repository; run 'git rm --cached' first".to_string(),
+        });
+    }
+
+    open_store(project_path)?;
+    let stored = store_path(project_path, file);
+    if stored.exists() {
+        return Err(RepositoryError::InvalidHiddenDirectory {
+            path: file.to_string(),
+            reason: format!("already stored in {}", STORE_DIRECTORY),
+        });
+    }
+    if let Some(dir) = stored.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::rename(&source, &stored)?;
+
+    let mut hidden = HiddenFile { file: file.to_string(), excluded: false };
+    let result = exclude(project_path, file)
+        .and_then(|excluded| {
+            hidden.excluded = excluded;
+            materialize(project_path, file, mode)
+        })
+        .and_then(|_| GitOperations::add_files(project_path.join(STORE_DIRECTORY), &[file.to_string()]));
+    if let Err(e) = result {
+        if let Err(undo_error) = hidden.undo(project_path) {
+            eprintln!("Warning: Failed to restore {}: {}", file, undo_error);
+        }
+        return Err(e);
+    }
+    Ok(hidden)
+}
+
+/// 在工作区中呈现 `.dot-files` 中的文件，已经正确呈现时什么也不做
+///
+/// 工作区中已有无关文件时返回错误，不覆盖。
+pub fn materialize(project_path: &Path, file: &str, mode: HiddenFileMode) -> Result<(), RepositoryError> {
+    match state(project_path, file, mode)? {
+        FileState::Missing => {}
+        FileState::Conflict => {
+            return Err(RepositoryError::InvalidHiddenDirectory {
+                path: file.to_string(),
+                reason: "a different file already exists in the working tree".to_string(),
+            })
+        }
+        _ => return Ok(()),
+    }
+
+    let target = project_path.join(file);
+    if let Some(dir) = target.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    match mode {
+        HiddenFileMode::Symlink => symlink(&link_target(file), &target)?,
+        HiddenFileMode::Copy => {
+            fs::copy(store_path(project_path, file), &target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+/// 隐藏文件在工作区中的状态
+pub fn state(project_path: &Path, file: &str, mode: HiddenFileMode) -> Result<FileState, RepositoryError> {
+    let target = project_path.join(file);
+    let metadata = match fs::symlink_metadata(&target) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(FileState::Missing),
+        Err(e) => return Err(e.into()),
+    };
+
+    if metadata.file_type().is_symlink() {
+        let points_to_store = fs::read_link(&target)? == link_target(file);
+        return Ok(if points_to_store { FileState::Linked } else { FileState::Conflict });
+    }
+
+    let stored = fs::read(store_path(project_path, file))?;
+    Ok(match (mode, fs::read(&target)? == stored) {
+        (_, true) => FileState::Copied,
+        (HiddenFileMode::Copy, false) => FileState::Modified,
+        (HiddenFileMode::Symlink, false) => FileState::Conflict,
+    })
+}
+
+/// 把修改过的副本同步回 `.dot-files`，返回是否有更改
+pub fn sync_copy(project_path: &Path, file: &str) -> Result<bool, RepositoryError> {
+    if state(project_path, file, HiddenFileMode::Copy)? != FileState::Modified {
+        return Ok(false);
+    }
+    fs::copy(project_path.join(file), store_path(project_path, file))?;
+    Ok(true)
+}
+
+/// 将路径加入父仓库的 `.git/info/exclude`（已存在时不重复添加），返回是否新加了规则
+pub fn exclude(project_path: &Path, path: &str) -> Result<bool, RepositoryError> {
+    let repo = git2::Repository::open(project_path)?;
+    let exclude_file = repo.path().join("info").join("exclude");
+    let rule = format!("/{}", path);
+
+    let mut content = fs::read_to_string(&exclude_file).unwrap_or_default();
+    if content.lines().any(|line| line.trim() == rule) {
+        return Ok(false);
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&rule);
+    content.push('\n');
+
+    if let Some(dir) = exclude_file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&exclude_file, content)?;
+    Ok(true)
+}
+
+/// 从父仓库的 `.git/info/exclude` 中删除 [`exclude`] 添加的规则
+pub fn remove_exclude(project_path: &Path, path: &str) -> Result<(), RepositoryError> {
+    let repo = git2::Repository::open(project_path)?;
+    let exclude_file = repo.path().join("info").join("exclude");
+    let rule = format!("/{}", path);
+
+    let Ok(content) = fs::read_to_string(&exclude_file) else {
+        return Ok(());
+    };
+    if !content.lines().any(|line| line.trim() == rule) {
+        return Ok(());
+    }
+    let kept: String = content
+        .lines()
+        .filter(|line| line.trim() != rule)
+        .map(|line| format!("{}\n", line))
+        .collect();
+    fs::write(&exclude_file, kept)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn project() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        GitOperations::init_repository(temp_dir.path()).unwrap();
+        GitOperations::init_repository(temp_dir.path().join(STORE_DIRECTORY)).unwrap();
+        temp_dir
+    }
+
+    #[test]
+    fn test_hide_with_symlink() {
+        let temp_dir = project();
+        let path = temp_dir.path();
+        fs::create_dir_all(path.join("config")).unwrap();
+        fs::write(path.join("config").join(".npmrc"), "registry=x").unwrap();
+
+        hide(path, "config/.npmrc", HiddenFileMode::Symlink).unwrap();
+        assert_eq!(tracked_files(path).unwrap(), vec!["config/.npmrc"]);
+        assert_eq!(state(path, "config/.npmrc", HiddenFileMode::Symlink).unwrap(), FileState::Linked);
+        assert_eq!(fs::read_link(path.join("config").join(".npmrc")).unwrap(), Path::new("../.dot-files/config/.npmrc"));
+        assert_eq!(fs::read_to_string(path.join("config").join(".npmrc")).unwrap(), "registry=x");
+
+        // 父仓库不会看到隐藏文件
+        let status = GitOperations::get_status(path).unwrap();
+        assert!(!status.contains(".npmrc"), "{}", status);
+
+        // 删除后重新呈现
+        fs::remove_file(path.join("config").join(".npmrc")).unwrap();
+        assert_eq!(state(path, "config/.npmrc", HiddenFileMode::Symlink).unwrap(), FileState::Missing);
+        materialize(path, "config/.npmrc", HiddenFileMode::Symlink).unwrap();
+        assert_eq!(state(path, "config/.npmrc", HiddenFileMode::Symlink).unwrap(), FileState::Linked);
+    }
+
+    #[test]
+    fn test_hide_with_copy() {
+        let temp_dir = project();
+        let path = temp_dir.path();
+        fs::write(path.join(".env"), "TOKEN=1").unwrap();
+
+        hide(path, ".env", HiddenFileMode::Copy).unwrap();
+        assert_eq!(state(path, ".env", HiddenFileMode::Copy).unwrap(), FileState::Copied);
+
+        fs::write(path.join(".env"), "TOKEN=2").unwrap();
+        assert_eq!(state(path, ".env", HiddenFileMode::Copy).unwrap(), FileState::Modified);
+        assert!(sync_copy(path, ".env").unwrap());
+        assert_eq!(fs::read_to_string(path.join(STORE_DIRECTORY).join(".env")).unwrap(), "TOKEN=2");
+        assert!(!sync_copy(path, ".env").unwrap());
+
+        // 已隐藏或不存在的文件
+        assert!(hide(path, ".env", HiddenFileMode::Copy).is_err());
+        assert!(hide(path, ".missing", HiddenFileMode::Copy).is_err());
+    }
+
+    fn excludes(path: &Path) -> String {
+        fs::read_to_string(path.join(".git").join("info").join("exclude")).unwrap_or_default()
+    }
+
+    #[test]
+    fn test_hide_requires_checked_out_store() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path();
+        GitOperations::init_repository(path).unwrap();
+        // `.dot-files` 已注册但没有检出
+        fs::create_dir_all(path.join(STORE_DIRECTORY)).unwrap();
+        fs::write(path.join(".env"), "TOKEN=1").unwrap();
+        let before = excludes(path);
+
+        assert!(hide(path, ".env", HiddenFileMode::Symlink).is_err());
+        assert_eq!(fs::read_to_string(path.join(".env")).unwrap(), "TOKEN=1");
+        assert!(!path.join(STORE_DIRECTORY).join(".env").exists());
+        assert_eq!(excludes(path), before);
+    }
+
+    #[test]
+    fn test_failed_hide_restores_file() {
+        let temp_dir = project();
+        let path = temp_dir.path();
+        fs::write(path.join(".env"), "TOKEN=1").unwrap();
+        let before = excludes(path);
+
+        // 暂存失败：`.dot-files` 的 index 被锁定
+        let lock = path.join(STORE_DIRECTORY).join(".git").join("index.lock");
+        fs::write(&lock, "").unwrap();
+        assert!(hide(path, ".env", HiddenFileMode::Symlink).is_err());
+        assert!(!path.join(".env").is_symlink());
+        assert_eq!(fs::read_to_string(path.join(".env")).unwrap(), "TOKEN=1");
+        assert!(!path.join(STORE_DIRECTORY).join(".env").exists());
+        assert_eq!(excludes(path), before);
+
+        // 撤销成功隐藏的文件
+        fs::remove_file(&lock).unwrap();
+        let hidden = hide(path, ".env", HiddenFileMode::Copy).unwrap();
+        assert!(excludes(path).contains("/.env"));
+        hidden.undo(path).unwrap();
+        assert_eq!(fs::read_to_string(path.join(".env")).unwrap(), "TOKEN=1");
+        assert!(tracked_files(path).unwrap().is_empty());
+        assert_eq!(excludes(path), before);
+    }
+}
@@ -1,278 +1,448 @@
-use crate::config::ConfigManager;
+use crate::config::{ConfigManager, IndexBackend};
 use crate::error::IndexError;
+use crate::index_crypto::IndexCipher;
+use crate::index_store::{self, IndexStore};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
-use std::process::Command;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ProjectRegistration {
     pub repository_key: String,
-    pub repository_name: String,  // MD5 hash of repository_key
+    /// 远程仓库名（按注册时的命名策略生成，旧索引中为 MD5）
+    #[serde(default)]
+    pub repository_name: String,
+    /// 隐藏仓库所在的组织（旧索引中缺失时为加载该索引的组织）
+    #[serde(default)]
+    pub organization: String,
+    /// 远程仓库实际所在的账户，只在与组织不同时记录（GitHub 开启 `allow_user_fallback` 后
+    /// 可能创建在个人账户下）；组织仍然用来选择平台和令牌
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
     pub git_user: String,
     pub project_git_path: String,
+    /// 已弃用：检出位置改为记录在本机状态文件中（见 `dot where`）。本版本仍然写入，
+    /// 让旧版本客户端可以读取新索引；读取旧索引时用来补充本机的检出记录
+    #[serde(default)]
     pub project_disk_path: String,
     pub hidden_directory: String,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    /// 接管的已有仓库（`dot init --adopt`）的远程 URL，原样使用，不由 dot 在平台上管理
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_url: Option<String>,
+    /// 链接的共享隐藏仓库名（`dot init --shared` / `dot link`）；全局索引中的链接记录在
+    /// [`SharedRepository`] 中，查询时展开为注册信息
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shared: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl ProjectRegistration {
+    /// 父项目的 base key（repository_key 去掉隐藏目录）
+    pub fn base_key(&self) -> &str {
+        self.repository_key
+            .strip_suffix(self.hidden_directory.as_str())
+            .map(|base| base.trim_end_matches('/'))
+            .unwrap_or(&self.repository_key)
+    }
+    
+    /// 是否为接管的已有仓库（远程仓库不由 dot 创建、改名或删除）
+    pub fn is_adopted(&self) -> bool {
+        self.remote_url.is_some()
+    }
+    
+    /// 是否为链接到项目中的共享隐藏仓库（远程仓库被多个项目使用，不随单个项目改名或删除）
+    pub fn is_shared(&self) -> bool {
+        self.shared.is_some()
+    }
+    
+    /// 远程仓库名，缺失时按旧版本的 MD5 规则推导
+    pub fn remote_name(&self) -> String {
+        if self.repository_name.is_empty() {
+            crate::naming::legacy_name(&self.repository_key)
+        } else {
+            self.repository_name.clone()
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct IndexData {
     pub projects: HashMap<String, ProjectRegistration>,
+    /// 共享隐藏仓库（名称 → 仓库），每个仓库可以链接到多个父项目
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub shared: HashMap<String, SharedRepository>,
+    /// 索引加密的接收者公钥，所有客户端保存时都加密到这个列表，而不是各自配置中的接收者
+    /// （None 表示还没有记录，空列表表示以明文保存）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipients: Option<Vec<String>>,
 }
 
-impl Default for IndexData {
-    fn default() -> Self {
-        Self {
-            projects: HashMap::new(),
-        }
-    }
+/// 组织级的共享隐藏仓库，例如多个服务共用的 `.kiro` 仓库
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SharedRepository {
+    pub repository_name: String,
+    pub organization: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_url: Option<String>,
+    /// 链接了该仓库的父项目（Repository Key → 链接）
+    #[serde(default)]
+    pub links: HashMap<String, SharedLink>,
 }
 
-pub struct IndexManager {
-    local_index_path: PathBuf,
-    remote_organization: String,
-    index_data: IndexData,
+/// 共享隐藏仓库在某个父项目中的检出位置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SharedLink {
+    pub git_user: String,
+    pub project_git_path: String,
+    pub hidden_directory: String,
+    pub linked_at: chrono::DateTime<chrono::Utc>,
 }
 
-impl IndexManager {
-    pub async fn new(config: &ConfigManager) -> Result<Self, IndexError> {
-        let org = config.get_default_organization()
-            .ok_or(IndexError::NoDefaultOrganization)?
-            .clone();
-            
-        let local_index_path = Self::local_index_path()?;
-        
-        // 检查并设置索引仓库
-        let mut manager = Self {
-            local_index_path,
-            remote_organization: org,
-            index_data: IndexData::default(),
-        };
-        
-        manager.ensure_index_repository().await?;
-        manager.load_index_data().await?;
-        
-        Ok(manager)
+impl SharedRepository {
+    /// 由注册信息创建共享仓库（还没有链接）
+    fn from_registration(registration: &ProjectRegistration) -> Self {
+        Self {
+            repository_name: registration.repository_name.clone(),
+            organization: registration.organization.clone(),
+            owner: registration.owner.clone(),
+            created_at: registration.created_at,
+            remote_url: registration.remote_url.clone(),
+            links: HashMap::new(),
+        }
     }
     
-    async fn ensure_index_repository(&self) -> Result<(), IndexError> {
-        // 检查本地索引目录是否存在
-        if self.local_index_path.exists() {
-            // 本地已存在，尝试更新
-            self.update_local_index().await?;
-            return Ok(());
-        }
-        
-        // 尝试克隆远程 .index 仓库
-        let clone_result = self.clone_index_repository().await;
-        
-        match clone_result {
-            Ok(_) => Ok(()),
-            Err(_) => {
-                // 克隆失败，可能是仓库不存在
-                // 创建本地索引目录和初始文件
-                println!("⚠️  无法克隆索引仓库，将创建本地索引");
-                println!("   请确保在 GitHub 上创建了 .index 仓库");
-                self.create_local_index().await?;
-                Ok(())
-            }
+    /// 把链接展开为注册信息
+    pub fn registration(&self, name: &str, repository_key: &str, link: &SharedLink) -> ProjectRegistration {
+        ProjectRegistration {
+            repository_key: repository_key.to_string(),
+            repository_name: self.repository_name.clone(),
+            organization: self.organization.clone(),
+            owner: self.owner.clone(),
+            git_user: link.git_user.clone(),
+            project_git_path: link.project_git_path.clone(),
+            project_disk_path: String::new(),
+            hidden_directory: link.hidden_directory.clone(),
+            created_at: link.linked_at,
+            remote_url: self.remote_url.clone(),
+            shared: Some(name.to_string()),
         }
     }
     
-    async fn clone_index_repository(&self) -> Result<(), IndexError> {
-        // 使用 SSH URL（利用用户的 Git 凭证）
-        let clone_url = format!("git@github.com:{}/{}.git", self.remote_organization, ".index");
+    fn registrations<'a>(&'a self, name: &'a str) -> impl Iterator<Item = ProjectRegistration> + 'a {
+        self.links.iter().map(move |(key, link)| self.registration(name, key, link))
+    }
+}
+
+/// 单个组织的索引
+struct OrganizationIndex {
+    organization: String,
+    store: Box<dyn IndexStore>,
+    data: IndexData,
+}
+
+impl OrganizationIndex {
+    async fn open(
+        organization: String,
+        store: Box<dyn IndexStore>,
+        cipher: Option<&IndexCipher>
+    ) -> Result<Self, IndexError> {
+        // 检查并设置索引存储
+        store.prepare().await?;
+        let mut data = match store.load().await? {
+            Some(content) => decode_index(&content, cipher)?,
+            None => IndexData::default(),
+        };
         
-        // 确保父目录存在
-        if let Some(parent) = self.local_index_path.parent() {
-            tokio::fs::create_dir_all(parent).await?;
+        // 旧索引没有记录组织，使用加载该索引的组织
+        for project in data.projects.values_mut() {
+            if project.organization.is_empty() {
+                project.organization = organization.clone();
+            }
         }
-        
-        // 使用 git 命令克隆（利用系统的 Git 凭证）
-        let output = Command::new("git")
-            .args(["clone", &clone_url, self.local_index_path.to_str().unwrap()])
-            .output()
-            .map_err(|e| IndexError::IoError(e))?;
-            
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(IndexError::IoError(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Failed to clone index repository: {}", stderr)
-            )));
+        for repository in data.shared.values_mut() {
+            if repository.organization.is_empty() {
+                repository.organization = organization.clone();
+            }
         }
         
-        // 如果仓库是空的，创建初始的 index.json 文件
-        let index_file = self.local_index_path.join("index.json");
-        if !index_file.exists() {
-            self.initialize_index_file().await?;
+        // 还没有记录接收者的索引使用本机配置的接收者，下次保存时一起写入
+        if data.recipients.is_none() {
+            data.recipients = cipher.filter(|c| c.encrypts()).map(IndexCipher::recipient_keys);
         }
         
-        Ok(())
+        Ok(Self { organization, store, data })
     }
     
-    async fn create_local_index(&self) -> Result<(), IndexError> {
-        // 创建本地索引目录
-        tokio::fs::create_dir_all(&self.local_index_path).await?;
-        
-        // 初始化 Git 仓库
-        let output = Command::new("git")
-            .args(["init"])
-            .current_dir(&self.local_index_path)
-            .output()
-            .map_err(|e| IndexError::IoError(e))?;
-            
-        if !output.status.success() {
-            return Err(IndexError::IoError(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Failed to initialize local index repository"
-            )));
+    async fn save(&self) -> Result<(), IndexError> {
+        let content = encode_index(&self.data)?;
+        self.store.save(&content).await
+    }
+}
+
+/// 解析 index.json 的内容，加密的内容先解密
+fn decode_index(content: &str, cipher: Option<&IndexCipher>) -> Result<IndexData, IndexError> {
+    if IndexCipher::is_encrypted(content) {
+        let cipher = cipher.ok_or_else(|| IndexError::Encryption(
+            "index is encrypted but index_encryption is not configured in ~/.dot/dot.conf".to_string()
+        ))?;
+        return Ok(serde_json::from_str(&cipher.decrypt(content)?)?);
+    }
+    Ok(serde_json::from_str(content)?)
+}
+
+/// 序列化索引数据，索引记录了接收者时加密
+fn encode_index(data: &IndexData) -> Result<String, IndexError> {
+    let json = serde_json::to_string_pretty(data)?;
+    match &data.recipients {
+        Some(recipients) if !recipients.is_empty() => IndexCipher::encrypt_to(recipients, &json),
+        _ => Ok(json),
+    }
+}
+
+pub struct IndexManager {
+    /// 第一个为默认组织的索引
+    indexes: Vec<OrganizationIndex>,
+    /// 索引加载失败的组织，不能向其注册项目
+    unavailable: Vec<String>,
+}
+
+impl IndexManager {
+    /// 加载所有已授权组织的索引
+    ///
+    /// 默认后端为每个组织使用其平台上独立的 `.index` 仓库（缓存在 `~/.dot/indexes/<org>/`）；
+    /// 自定义 Git URL 和本地目录后端是所有组织共享的单个索引。
+    pub async fn new(config: &ConfigManager) -> Result<Self, IndexError> {
+        let default_org = config.get_default_organization()
+            .ok_or(IndexError::NoDefaultOrganization)?
+            .clone();
+        
+        let backend = config.get_index_backend();
+        let mut organizations = vec![default_org.clone()];
+        if *backend == IndexBackend::Forge {
+            for org in config.get_authorized_organizations() {
+                if !organizations.contains(org) {
+                    organizations.push(org.clone());
+                }
+            }
         }
         
-        // 设置远程 origin
-        let remote_url = format!("git@github.com:{}/{}.git", self.remote_organization, ".index");
-        let _ = Command::new("git")
-            .args(["remote", "add", "origin", &remote_url])
-            .current_dir(&self.local_index_path)
-            .output();
+        index_store::migrate_legacy_local_path(&default_org)?;
         
-        // 创建初始 index.json
-        self.initialize_index_file().await?;
+        let mut stores = Vec::new();
+        for org in organizations {
+            let local_path = index_store::organization_local_path(&org)?;
+            let store = index_store::from_backend(config, &org, local_path)?;
+            stores.push((org, store));
+        }
         
-        Ok(())
+        let cipher = match config.get_index_encryption() {
+            Some(encryption) => Some(IndexCipher::from_keys(
+                &encryption.recipients,
+                &encryption.identity_path()?
+            )?),
+            None => None,
+        };
+        
+        Self::with_stores(stores, cipher).await
     }
     
-    async fn initialize_index_file(&self) -> Result<(), IndexError> {
-        let index_file = self.local_index_path.join("index.json");
-        let initial_data = IndexData::default();
-        let content = serde_json::to_string_pretty(&initial_data)?;
-        tokio::fs::write(&index_file, &content).await?;
-        
-        // Git add and commit
-        let _ = Command::new("git")
-            .args(["add", "index.json"])
-            .current_dir(&self.local_index_path)
-            .output();
-            
-        let _ = Command::new("git")
-            .args(["commit", "-m", "Initialize index repository"])
-            .current_dir(&self.local_index_path)
-            .output();
-            
-        Ok(())
+    /// 使用指定的存储后端创建索引管理器
+    pub async fn with_store(organization: String, store: Box<dyn IndexStore>) -> Result<Self, IndexError> {
+        Self::with_stores(vec![(organization, store)], None).await
     }
     
-    async fn update_local_index(&self) -> Result<(), IndexError> {
-        // 使用 git pull 更新本地索引
-        let output = Command::new("git")
-            .args(["pull", "--rebase"])
-            .current_dir(&self.local_index_path)
-            .output();
-            
-        // 忽略 pull 失败（可能是远程仓库不存在或网络问题）
-        if let Ok(out) = output {
-            if !out.status.success() {
-                // 静默忽略，使用本地数据
+    /// 使用多个组织的存储后端创建索引管理器，第一个为默认组织
+    ///
+    /// 默认组织的索引必须可用；其他组织的索引加载失败时只给出警告。
+    /// 提供 `cipher` 时加密的索引在加载时解密；没有记录接收者的索引之后加密到 `cipher` 的接收者。
+    pub async fn with_stores(
+        stores: Vec<(String, Box<dyn IndexStore>)>,
+        cipher: Option<IndexCipher>
+    ) -> Result<Self, IndexError> {
+        let mut indexes = Vec::new();
+        let mut unavailable = Vec::new();
+        
+        for (organization, store) in stores {
+            let is_default = indexes.is_empty();
+            let description = store.description();
+            match OrganizationIndex::open(organization.clone(), store, cipher.as_ref()).await {
+                Ok(index) => indexes.push(index),
+                Err(e) if is_default => return Err(e),
+                Err(e) => {
+                    eprintln!("⚠️  Failed to load index for organization {} ({}): {}",
+                        organization, description, e);
+                    unavailable.push(organization);
+                }
             }
         }
         
-        Ok(())
-    }
-    
-    async fn load_index_data(&mut self) -> Result<(), IndexError> {
-        let index_file = self.local_index_path.join("index.json");
-        
-        if index_file.exists() {
-            let content = tokio::fs::read_to_string(&index_file).await?;
-            self.index_data = serde_json::from_str(&content)?;
-        } else {
-            self.index_data = IndexData::default();
+        if indexes.is_empty() {
+            return Err(IndexError::NoDefaultOrganization);
         }
         
-        Ok(())
+        Ok(Self { indexes, unavailable })
     }
     
-    pub async fn register_project(&mut self, registration: ProjectRegistration) -> Result<(), IndexError> {
+    /// 注册项目到其所属组织的索引（共享索引时写入默认索引）
+    ///
+    /// 共享隐藏仓库的注册信息记录为该仓库的一个链接，仓库还不存在时一起创建。
+    pub async fn register_project(&mut self, mut registration: ProjectRegistration) -> Result<(), IndexError> {
         // 检查是否已存在
-        if self.index_data.projects.contains_key(&registration.repository_key) {
+        if self.project_exists(&registration.repository_key) {
             return Err(IndexError::ProjectAlreadyExists(registration.repository_key));
         }
         
+        if registration.organization.is_empty() {
+            registration.organization = self.get_organization().to_string();
+        }
+        
+        // 组织的索引没有加载成功时不能写入其它组织的索引，否则之后按组织查询时找不到
+        if self.unavailable.contains(&registration.organization) {
+            return Err(IndexError::OrganizationIndexUnavailable(registration.organization));
+        }
+        let position = self.indexes
+            .iter()
+            .position(|i| i.organization == registration.organization)
+            .unwrap_or(0);
+        if let Some(name) = registration.shared.clone() {
+            let position = self.indexes
+                .iter()
+                .position(|i| i.data.shared.contains_key(&name))
+                .unwrap_or(position);
+            let index = &mut self.indexes[position];
+            let repository = index.data.shared
+                .entry(name)
+                .or_insert_with(|| SharedRepository::from_registration(&registration));
+            repository.links.insert(registration.repository_key, SharedLink {
+                git_user: registration.git_user,
+                project_git_path: registration.project_git_path,
+                hidden_directory: registration.hidden_directory,
+                linked_at: registration.created_at,
+            });
+            index.save().await?;
+            return Ok(());
+        }
+        
+        let index = &mut self.indexes[position];
+        
         // 添加到索引
-        self.index_data.projects.insert(
+        index.data.projects.insert(
             registration.repository_key.clone(),
             registration
         );
         
         // 保存并推送更改
-        self.save_and_push_index().await?;
+        index.save().await?;
         
         Ok(())
     }
     
-    pub fn project_exists(&self, repository_key: &str) -> bool {
-        self.index_data.projects.contains_key(repository_key)
+    /// 从所属组织的索引中移除项目并保存，返回被移除的注册信息（不存在时为 None）
+    ///
+    /// 共享隐藏仓库只移除该项目的链接，仓库本身保留在索引中。
+    pub async fn unregister_project(&mut self, repository_key: &str) -> Result<Option<ProjectRegistration>, IndexError> {
+        for index in &mut self.indexes {
+            if let Some(removed) = index.data.projects.remove(repository_key) {
+                index.save().await?;
+                return Ok(Some(removed));
+            }
+            
+            let linked = index.data.shared.iter_mut().find_map(|(name, repository)| {
+                let link = repository.links.remove(repository_key)?;
+                Some(repository.registration(name, repository_key, &link))
+            });
+            if let Some(removed) = linked {
+                index.save().await?;
+                return Ok(Some(removed));
+            }
+        }
+        
+        Ok(None)
     }
     
-    pub fn get_organization(&self) -> &str {
-        &self.remote_organization
+    /// 各组织索引记录的加密接收者（空列表表示明文保存）
+    pub fn recipients(&self) -> Vec<(&str, &[String])> {
+        self.indexes
+            .iter()
+            .map(|i| (i.organization.as_str(), i.data.recipients.as_deref().unwrap_or_default()))
+            .collect()
     }
     
-    pub fn find_projects_by_base_key(&self, base_key: &str) -> Vec<&ProjectRegistration> {
-        self.index_data.projects
-            .values()
-            .filter(|p| p.repository_key.starts_with(base_key))
-            .collect()
+    /// 向所有索引添加加密接收者并重新加密保存，返回是否有索引发生变化
+    pub async fn add_recipient(&mut self, recipient: &str) -> Result<bool, IndexError> {
+        IndexCipher::parse_recipient(recipient)?;
+        let mut changed = false;
+        for index in &mut self.indexes {
+            let recipients = index.data.recipients.get_or_insert_with(Vec::new);
+            if !recipients.iter().any(|r| r == recipient) {
+                recipients.push(recipient.to_string());
+                index.save().await?;
+                changed = true;
+            }
+        }
+        Ok(changed)
     }
     
-    async fn save_and_push_index(&self) -> Result<(), IndexError> {
-        let index_file = self.local_index_path.join("index.json");
-        let content = serde_json::to_string_pretty(&self.index_data)?;
-        tokio::fs::write(&index_file, content).await?;
-        
-        // Git add
-        let _ = Command::new("git")
-            .args(["add", "index.json"])
-            .current_dir(&self.local_index_path)
-            .output();
-        
-        // Git commit
-        let _ = Command::new("git")
-            .args(["commit", "-m", "Update index"])
-            .current_dir(&self.local_index_path)
-            .output();
-        
-        // Git push（使用系统的 Git 凭证）
-        let push_output = Command::new("git")
-            .args(["push", "-u", "origin", "main"])
-            .current_dir(&self.local_index_path)
-            .output();
-            
-        // 如果 main 分支不存在，尝试 master
-        if let Ok(out) = push_output {
-            if !out.status.success() {
-                let _ = Command::new("git")
-                    .args(["push", "-u", "origin", "master"])
-                    .current_dir(&self.local_index_path)
-                    .output();
+    /// 从所有索引移除加密接收者并重新保存（没有接收者时保存为明文），返回是否有索引发生变化
+    pub async fn remove_recipient(&mut self, recipient: &str) -> Result<bool, IndexError> {
+        let mut changed = false;
+        for index in &mut self.indexes {
+            let Some(recipients) = index.data.recipients.as_mut() else {
+                continue;
+            };
+            let before = recipients.len();
+            recipients.retain(|r| r != recipient);
+            if recipients.len() != before {
+                index.save().await?;
+                changed = true;
             }
         }
-        
-        Ok(())
+        Ok(changed)
     }
     
-    fn local_index_path() -> Result<PathBuf, IndexError> {
-        let home = dirs::home_dir().ok_or(IndexError::IoError(
-            std::io::Error::new(std::io::ErrorKind::NotFound, "Home directory not found")
-        ))?;
-        Ok(home.join(".dot").join(".index"))
+    pub fn project_exists(&self, repository_key: &str) -> bool {
+        self.indexes.iter().any(|i| {
+            i.data.projects.contains_key(repository_key)
+                || i.data.shared.values().any(|s| s.links.contains_key(repository_key))
+        })
+    }
+    
+    /// 按名称查找共享隐藏仓库
+    pub fn shared_repository(&self, name: &str) -> Option<&SharedRepository> {
+        self.indexes.iter().find_map(|i| i.data.shared.get(name))
+    }
+    
+    /// 默认组织
+    pub fn get_organization(&self) -> &str {
+        &self.indexes[0].organization
+    }
+    
+    /// 已加载索引的组织
+    pub fn organizations(&self) -> Vec<&str> {
+        self.indexes.iter().map(|i| i.organization.as_str()).collect()
+    }
+    
+    /// 所有组织索引中的项目（包括共享隐藏仓库的链接）
+    pub fn projects(&self) -> impl Iterator<Item = ProjectRegistration> + '_ {
+        self.indexes.iter().flat_map(|i| {
+            let linked = i.data.shared.iter().flat_map(|(name, s)| s.registrations(name));
+            i.data.projects.values().cloned().chain(linked)
+        })
+    }
+    
+    pub fn find_projects_by_base_key(&self, base_key: &str) -> Vec<ProjectRegistration> {
+        self.projects()
+            .filter(|p| p.base_key() == base_key)
+            .collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::index_store::LocalIndexStore;
     
     #[test]
     fn test_index_data_serialization() {
@@ -281,11 +451,15 @@ mod tests {
         let registration = ProjectRegistration {
             repository_key: "github.com/user/repo/.kiro".to_string(),
             repository_name: "abc123def456".to_string(),
+            organization: "test-org".to_string(),
+            owner: None,
             git_user: "testuser".to_string(),
             project_git_path: "git@github.com:user/repo.git".to_string(),
-            project_disk_path: "/home/user/repo".to_string(),
+            project_disk_path: String::new(),
             hidden_directory: ".kiro".to_string(),
             created_at: chrono::Utc::now(),
+            remote_url: None,
+            shared: None,
         };
         
         index_data.projects.insert(registration.repository_key.clone(), registration);
@@ -295,4 +469,285 @@ mod tests {
         
         assert_eq!(index_data.projects.len(), deserialized.projects.len());
     }
+    
+    #[tokio::test]
+    async fn test_index_manager_with_local_store() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let store = Box::new(LocalIndexStore::new(temp_dir.path().to_path_buf()));
+        let mut manager = IndexManager::with_store("test-org".to_string(), store).await.unwrap();
+        
+        let registration = ProjectRegistration {
+            repository_key: "github.com:user/repo/.kiro".to_string(),
+            repository_name: "abc123def456".to_string(),
+            organization: "test-org".to_string(),
+            owner: None,
+            git_user: "testuser".to_string(),
+            project_git_path: "git@github.com:user/repo.git".to_string(),
+            project_disk_path: String::new(),
+            hidden_directory: ".kiro".to_string(),
+            created_at: chrono::Utc::now(),
+            remote_url: None,
+            shared: None,
+        };
+        manager.register_project(registration.clone()).await.unwrap();
+        assert!(manager.project_exists("github.com:user/repo/.kiro"));
+        assert!(manager.register_project(registration.clone()).await.is_err());
+        
+        // 移除后可以重新注册
+        let removed = manager.unregister_project("github.com:user/repo/.kiro").await.unwrap();
+        assert_eq!(removed.unwrap().repository_name, "abc123def456");
+        assert!(!manager.project_exists("github.com:user/repo/.kiro"));
+        assert!(manager.unregister_project("github.com:user/repo/.kiro").await.unwrap().is_none());
+        manager.register_project(registration).await.unwrap();
+        
+        // 名称前缀相同的项目不会被匹配
+        let mut similar = manager.projects().next().unwrap().clone();
+        similar.repository_key = "github.com:user/repo2/.kiro".to_string();
+        similar.project_git_path = "git@github.com:user/repo2.git".to_string();
+        manager.register_project(similar).await.unwrap();
+        
+        // 重新加载后数据仍然存在
+        let store = Box::new(LocalIndexStore::new(temp_dir.path().to_path_buf()));
+        let reloaded = IndexManager::with_store("test-org".to_string(), store).await.unwrap();
+        assert_eq!(reloaded.find_projects_by_base_key("github.com:user/repo").len(), 1);
+        assert_eq!(reloaded.find_projects_by_base_key("github.com:user/repo2").len(), 1);
+    }
+    
+    #[test]
+    fn test_remote_name_falls_back_to_legacy_md5() {
+        let mut registration: ProjectRegistration = serde_json::from_str(r#"{
+            "repository_key": "github.com:user/repo/.kiro",
+            "git_user": "testuser",
+            "project_git_path": "git@github.com:user/repo.git",
+            "hidden_directory": ".kiro",
+            "created_at": "2024-01-01T00:00:00Z"
+        }"#).unwrap();
+        assert_eq!(registration.remote_name(), format!("{:x}", md5::compute("github.com:user/repo/.kiro")));
+        
+        registration.repository_name = "repo--kiro-1a2b3c4d".to_string();
+        assert_eq!(registration.remote_name(), "repo--kiro-1a2b3c4d");
+    }
+    
+    #[tokio::test]
+    async fn test_register_to_unavailable_organization_fails() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let broken_dir = temp_dir.path().join("org-b");
+        std::fs::create_dir_all(&broken_dir).unwrap();
+        std::fs::write(broken_dir.join("index.json"), "not json").unwrap();
+        
+        let stores: Vec<(String, Box<dyn IndexStore>)> = vec![
+            ("org-a".to_string(), Box::new(LocalIndexStore::new(temp_dir.path().join("org-a")))),
+            ("org-b".to_string(), Box::new(LocalIndexStore::new(broken_dir.clone()))),
+        ];
+        let mut manager = IndexManager::with_stores(stores, None).await.unwrap();
+        
+        let registration = ProjectRegistration {
+            repository_key: "github.com:user/repo/.kiro".to_string(),
+            repository_name: "def456".to_string(),
+            organization: "org-b".to_string(),
+            owner: None,
+            git_user: "testuser".to_string(),
+            project_git_path: "git@github.com:user/repo.git".to_string(),
+            project_disk_path: String::new(),
+            hidden_directory: ".kiro".to_string(),
+            created_at: chrono::Utc::now(),
+            remote_url: None,
+            shared: None,
+        };
+        let result = manager.register_project(registration).await;
+        assert!(matches!(result, Err(IndexError::OrganizationIndexUnavailable(org)) if org == "org-b"));
+        
+        // 不会写入默认组织的索引
+        assert!(!manager.project_exists("github.com:user/repo/.kiro"));
+    }
+    
+    #[tokio::test]
+    async fn test_multiple_organization_indexes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        
+        // 旧格式的索引：没有 organization 字段
+        let legacy_dir = temp_dir.path().join("org-b");
+        std::fs::create_dir_all(&legacy_dir).unwrap();
+        std::fs::write(legacy_dir.join("index.json"), r#"{"projects": {"github.com:user/other/.kiro": {
+            "repository_key": "github.com:user/other/.kiro",
+            "repository_name": "abc123",
+            "git_user": "testuser",
+            "project_git_path": "git@github.com:user/other.git",
+            "project_disk_path": "/home/user/other",
+            "hidden_directory": ".kiro",
+            "created_at": "2024-01-01T00:00:00Z"
+        }}}"#).unwrap();
+        
+        let stores: Vec<(String, Box<dyn IndexStore>)> = vec![
+            ("org-a".to_string(), Box::new(LocalIndexStore::new(temp_dir.path().join("org-a")))),
+            ("org-b".to_string(), Box::new(LocalIndexStore::new(legacy_dir.clone()))),
+        ];
+        let mut manager = IndexManager::with_stores(stores, None).await.unwrap();
+        assert_eq!(manager.get_organization(), "org-a");
+        assert_eq!(manager.organizations(), vec!["org-a", "org-b"]);
+        
+        // 其他组织的项目也能被查询到
+        let projects = manager.find_projects_by_base_key("github.com:user/other");
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].organization, "org-b");
+        assert_eq!(projects[0].project_disk_path, "/home/user/other");
+        
+        // 注册到 org-b 的项目写入 org-b 的索引
+        let registration = ProjectRegistration {
+            repository_key: "github.com:user/repo/.kiro".to_string(),
+            repository_name: "def456".to_string(),
+            organization: "org-b".to_string(),
+            owner: None,
+            git_user: "testuser".to_string(),
+            project_git_path: "git@github.com:user/repo.git".to_string(),
+            project_disk_path: "/home/user/repo".to_string(),
+            hidden_directory: ".kiro".to_string(),
+            created_at: chrono::Utc::now(),
+            remote_url: None,
+            shared: None,
+        };
+        manager.register_project(registration).await.unwrap();
+        
+        let org_b = std::fs::read_to_string(legacy_dir.join("index.json")).unwrap();
+        assert!(org_b.contains("github.com:user/repo/.kiro"));
+        // 旧版本客户端要求 project_disk_path 字段
+        assert!(org_b.contains(r#""project_disk_path": "/home/user/repo""#));
+        let org_a = std::fs::read_to_string(temp_dir.path().join("org-a").join("index.json")).unwrap();
+        assert!(!org_a.contains("github.com:user/repo/.kiro"));
+    }
+    
+    #[tokio::test]
+    async fn test_shared_repository_links() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let store = Box::new(LocalIndexStore::new(temp_dir.path().to_path_buf()));
+        let mut manager = IndexManager::with_store("test-org".to_string(), store).await.unwrap();
+        
+        let link = |project: &str, directory: &str| ProjectRegistration {
+            repository_key: format!("github.com:user/{}/{}", project, directory),
+            repository_name: "kiro-steering".to_string(),
+            organization: "test-org".to_string(),
+            owner: None,
+            git_user: "testuser".to_string(),
+            project_git_path: format!("git@github.com:user/{}.git", project),
+            project_disk_path: String::new(),
+            hidden_directory: directory.to_string(),
+            created_at: chrono::Utc::now(),
+            remote_url: None,
+            shared: Some("kiro-steering".to_string()),
+        };
+        manager.register_project(link("api", ".kiro")).await.unwrap();
+        manager.register_project(link("web", ".kiro")).await.unwrap();
+        manager.register_project(link("web", "docs/.kiro")).await.unwrap();
+        assert!(manager.register_project(link("api", ".kiro")).await.is_err());
+        
+        // 一个仓库链接到多个项目，一个项目可以链接多次
+        let store = Box::new(LocalIndexStore::new(temp_dir.path().to_path_buf()));
+        let mut reloaded = IndexManager::with_store("test-org".to_string(), store).await.unwrap();
+        assert_eq!(reloaded.shared_repository("kiro-steering").unwrap().links.len(), 3);
+        assert!(reloaded.indexes[0].data.projects.is_empty());
+        let web = reloaded.find_projects_by_base_key("github.com:user/web");
+        assert_eq!(web.len(), 2);
+        assert!(web.iter().all(|p| p.remote_name() == "kiro-steering" && p.is_shared()));
+        
+        // 移除链接后共享仓库仍然保留
+        let removed = reloaded.unregister_project("github.com:user/api/.kiro").await.unwrap().unwrap();
+        assert_eq!(removed.shared.as_deref(), Some("kiro-steering"));
+        assert!(!reloaded.project_exists("github.com:user/api/.kiro"));
+        assert_eq!(reloaded.shared_repository("kiro-steering").unwrap().links.len(), 2);
+    }
+    
+    #[tokio::test]
+    async fn test_encrypted_index() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let index_dir = temp_dir.path().join("index");
+        let identity = age::x25519::Identity::generate();
+        let recipient = identity.to_public();
+        
+        let cipher = IndexCipher::new(vec![recipient.clone()], vec![identity.clone()]);
+        let stores: Vec<(String, Box<dyn IndexStore>)> = vec![
+            ("test-org".to_string(), Box::new(LocalIndexStore::new(index_dir.clone()))),
+        ];
+        let mut manager = IndexManager::with_stores(stores, Some(cipher)).await.unwrap();
+        manager.register_project(ProjectRegistration {
+            repository_key: "github.com:user/secret-project/.kiro".to_string(),
+            repository_name: "abc123def456".to_string(),
+            organization: "test-org".to_string(),
+            owner: None,
+            git_user: "testuser".to_string(),
+            project_git_path: "git@github.com:user/secret-project.git".to_string(),
+            project_disk_path: String::new(),
+            hidden_directory: ".kiro".to_string(),
+            created_at: chrono::Utc::now(),
+            remote_url: None,
+            shared: None,
+        }).await.unwrap();
+        
+        // 磁盘上的内容已加密
+        let on_disk = std::fs::read_to_string(index_dir.join("index.json")).unwrap();
+        assert!(IndexCipher::is_encrypted(&on_disk));
+        assert!(!on_disk.contains("secret-project"));
+        assert!(!on_disk.contains("testuser"));
+        
+        // 没有私钥时无法加载
+        let stores: Vec<(String, Box<dyn IndexStore>)> = vec![
+            ("test-org".to_string(), Box::new(LocalIndexStore::new(index_dir.clone()))),
+        ];
+        assert!(IndexManager::with_stores(stores, None).await.is_err());
+        
+        // 使用私钥可以解密；移除所有接收者后重新保存为明文
+        let cipher = IndexCipher::new(vec![], vec![identity]);
+        let stores: Vec<(String, Box<dyn IndexStore>)> = vec![
+            ("test-org".to_string(), Box::new(LocalIndexStore::new(index_dir.clone()))),
+        ];
+        let mut manager = IndexManager::with_stores(stores, Some(cipher)).await.unwrap();
+        assert!(manager.project_exists("github.com:user/secret-project/.kiro"));
+        assert_eq!(manager.recipients(), vec![("test-org", &[recipient.to_string()][..])]);
+        assert!(manager.remove_recipient(&recipient.to_string()).await.unwrap());
+        
+        let on_disk = std::fs::read_to_string(index_dir.join("index.json")).unwrap();
+        assert!(!IndexCipher::is_encrypted(&on_disk));
+        assert!(on_disk.contains("secret-project"));
+    }
+    
+    #[tokio::test]
+    async fn test_recipients_are_stored_with_the_index() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let index_dir = temp_dir.path().join("index");
+        let alice = age::x25519::Identity::generate();
+        let alice_public = alice.to_public().to_string();
+        let bob = age::x25519::Identity::generate();
+        let stores = || -> Vec<(String, Box<dyn IndexStore>)> {
+            vec![("test-org".to_string(), Box::new(LocalIndexStore::new(index_dir.clone())))]
+        };
+        let registration = |key: &str| ProjectRegistration {
+            repository_key: key.to_string(),
+            repository_name: "abc123def456".to_string(),
+            organization: "test-org".to_string(),
+            owner: None,
+            git_user: "testuser".to_string(),
+            project_git_path: "git@github.com:user/repo.git".to_string(),
+            project_disk_path: String::new(),
+            hidden_directory: ".kiro".to_string(),
+            created_at: chrono::Utc::now(),
+            remote_url: None,
+            shared: None,
+        };
+        
+        // alice 的配置只有自己，把 bob 加入索引的接收者
+        let cipher = IndexCipher::new(vec![alice.to_public()], vec![alice.clone()]);
+        let mut manager = IndexManager::with_stores(stores(), Some(cipher)).await.unwrap();
+        assert!(manager.add_recipient(&bob.to_public().to_string()).await.unwrap());
+        assert!(!manager.add_recipient(&bob.to_public().to_string()).await.unwrap());
+        
+        // bob 的配置里没有 alice，保存时仍然加密到索引记录的接收者，alice 还能读取
+        let cipher = IndexCipher::new(vec![bob.to_public()], vec![bob.clone()]);
+        let mut manager = IndexManager::with_stores(stores(), Some(cipher)).await.unwrap();
+        manager.register_project(registration("github.com:user/repo/.kiro")).await.unwrap();
+        
+        let cipher = IndexCipher::new(vec![], vec![alice]);
+        let manager = IndexManager::with_stores(stores(), Some(cipher)).await.unwrap();
+        assert!(manager.project_exists("github.com:user/repo/.kiro"));
+        let expected = [alice_public, bob.to_public().to_string()];
+        assert_eq!(manager.recipients(), vec![("test-org", &expected[..])]);
+    }
 }
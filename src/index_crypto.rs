@@ -0,0 +1,206 @@
+use crate::error::IndexError;
+use age::secrecy::ExposeSecret;
+use age::x25519::{Identity, Recipient};
+use std::io::{Read, Write};
+use std::path::Path;
+
+const ARMOR_HEADER: &str = "-----BEGIN AGE ENCRYPTED FILE-----";
+
+/// 索引加密（age / X25519）
+///
+/// 使用本地身份文件中的私钥解密 index.json。接收者列表随索引保存（见 `IndexData::recipients`），
+/// 配置中的接收者只用于还没有记录接收者的索引；没有接收者时不加密。
+pub struct IndexCipher {
+    recipients: Vec<Recipient>,
+    identities: Vec<Identity>,
+}
+
+impl IndexCipher {
+    pub fn new(recipients: Vec<Recipient>, identities: Vec<Identity>) -> Self {
+        Self { recipients, identities }
+    }
+
+    /// 从接收者公钥列表和本地身份文件创建（身份文件不存在时只能加密）
+    pub fn from_keys(recipients: &[String], identity_file: &Path) -> Result<Self, IndexError> {
+        let recipients = recipients
+            .iter()
+            .map(|r| Self::parse_recipient(r))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let identities = if identity_file.exists() {
+            Self::read_identity_file(identity_file)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self::new(recipients, identities))
+    }
+
+    /// 内容是否为 age 加密（ASCII armor）格式
+    pub fn is_encrypted(content: &str) -> bool {
+        content.trim_start().starts_with(ARMOR_HEADER)
+    }
+
+    /// 是否会对保存的索引进行加密
+    pub fn encrypts(&self) -> bool {
+        !self.recipients.is_empty()
+    }
+
+    /// 接收者公钥（`age1...` 格式）
+    pub fn recipient_keys(&self) -> Vec<String> {
+        self.recipients.iter().map(Recipient::to_string).collect()
+    }
+
+    /// 加密到指定的接收者公钥
+    pub fn encrypt_to(recipients: &[String], plaintext: &str) -> Result<String, IndexError> {
+        let recipients = recipients
+            .iter()
+            .map(|r| Self::parse_recipient(r))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(recipients, Vec::new()).encrypt(plaintext)
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, IndexError> {
+        let recipients = self.recipients.iter().map(|r| r as &dyn age::Recipient);
+        let encryptor = age::Encryptor::with_recipients(recipients)
+            .map_err(|e| IndexError::Encryption(e.to_string()))?;
+
+        let mut ciphertext = Vec::new();
+        let armored = age::armor::ArmoredWriter::wrap_output(&mut ciphertext, age::armor::Format::AsciiArmor)?;
+        let mut writer = encryptor.wrap_output(armored)?;
+        writer.write_all(plaintext.as_bytes())?;
+        writer.finish()?.finish()?;
+
+        String::from_utf8(ciphertext).map_err(|e| IndexError::Encryption(e.to_string()))
+    }
+
+    pub fn decrypt(&self, ciphertext: &str) -> Result<String, IndexError> {
+        if self.identities.is_empty() {
+            return Err(IndexError::Encryption(
+                "index is encrypted but no identity is available; run 'dot index-key generate' and ask a recipient to add your public key".to_string()
+            ));
+        }
+
+        let reader = age::armor::ArmoredReader::new(ciphertext.as_bytes());
+        let decryptor = age::Decryptor::new_buffered(reader)
+            .map_err(|e| IndexError::Encryption(e.to_string()))?;
+        let identities = self.identities.iter().map(|i| i as &dyn age::Identity);
+        let mut reader = decryptor.decrypt(identities)
+            .map_err(|e| IndexError::Encryption(e.to_string()))?;
+
+        let mut plaintext = String::new();
+        reader.read_to_string(&mut plaintext)?;
+        Ok(plaintext)
+    }
+
+    pub fn parse_recipient(recipient: &str) -> Result<Recipient, IndexError> {
+        recipient.trim().parse::<Recipient>()
+            .map_err(|e| IndexError::Encryption(format!("Invalid recipient '{}': {}", recipient, e)))
+    }
+
+    /// 读取 age 身份文件（忽略注释和空行）
+    pub fn read_identity_file(path: &Path) -> Result<Vec<Identity>, IndexError> {
+        let content = std::fs::read_to_string(path)?;
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| line.parse::<Identity>()
+                .map_err(|e| IndexError::Encryption(format!("Invalid identity in {}: {}", path.display(), e))))
+            .collect()
+    }
+
+    /// 生成新的身份文件，返回对应的公钥
+    pub fn generate_identity_file(path: &Path) -> Result<String, IndexError> {
+        if path.exists() {
+            return Err(IndexError::Encryption(format!("Identity file already exists: {}", path.display())));
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let identity = Identity::generate();
+        let public_key = identity.to_public().to_string();
+        let content = format!(
+            "# created: {}\n# public key: {}\n{}\n",
+            chrono::Utc::now().to_rfc3339(),
+            public_key,
+            identity.to_string().expose_secret()
+        );
+        std::fs::write(path, content)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+
+        Ok(public_key)
+    }
+
+    /// 身份文件中第一个身份的公钥
+    pub fn public_key(path: &Path) -> Result<String, IndexError> {
+        Self::read_identity_file(path)?
+            .first()
+            .map(|i| i.to_public().to_string())
+            .ok_or_else(|| IndexError::Encryption(format!("No identity found in {}", path.display())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_roundtrip_with_multiple_recipients() {
+        let alice = Identity::generate();
+        let bob = Identity::generate();
+        let cipher = IndexCipher::new(vec![alice.to_public(), bob.to_public()], vec![]);
+
+        let ciphertext = cipher.encrypt(r#"{"projects": {}}"#).unwrap();
+        assert!(IndexCipher::is_encrypted(&ciphertext));
+        assert!(!ciphertext.contains("projects"));
+
+        // 每个接收者都能独立解密
+        for identity in [alice, bob] {
+            let reader = IndexCipher::new(vec![], vec![identity]);
+            assert_eq!(reader.decrypt(&ciphertext).unwrap(), r#"{"projects": {}}"#);
+        }
+    }
+
+    #[test]
+    fn test_decrypt_with_wrong_identity_fails() {
+        let alice = Identity::generate();
+        let mallory = Identity::generate();
+        let cipher = IndexCipher::new(vec![alice.to_public()], vec![]);
+        let ciphertext = cipher.encrypt("secret").unwrap();
+
+        let reader = IndexCipher::new(vec![], vec![mallory]);
+        assert!(matches!(reader.decrypt(&ciphertext), Err(IndexError::Encryption(_))));
+
+        let no_identity = IndexCipher::new(vec![], vec![]);
+        assert!(no_identity.decrypt(&ciphertext).is_err());
+    }
+
+    #[test]
+    fn test_identity_file_generation() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("keys").join("identity.txt");
+
+        let public_key = IndexCipher::generate_identity_file(&path).unwrap();
+        assert!(public_key.starts_with("age1"));
+        assert_eq!(IndexCipher::public_key(&path).unwrap(), public_key);
+        assert!(IndexCipher::generate_identity_file(&path).is_err());
+
+        let cipher = IndexCipher::from_keys(&[public_key], &path).unwrap();
+        assert!(cipher.encrypts());
+        let ciphertext = cipher.encrypt("hello").unwrap();
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_invalid_recipient() {
+        assert!(IndexCipher::parse_recipient("not-a-key").is_err());
+    }
+}
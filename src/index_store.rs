@@ -0,0 +1,409 @@
+use crate::config::{ConfigManager, ForgeKind, IndexBackend};
+use crate::error::IndexError;
+use crate::forge;
+use crate::git_operations::{GitCredentials, GitOperations};
+use crate::index::IndexData;
+use git2::{Repository, Signature};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const INDEX_FILE: &str = "index.json";
+
+/// 索引存储后端
+///
+/// `IndexManager` 负责索引数据的增删查以及序列化（和可选的加密），
+/// 存储后端只负责保存和同步 index.json 的内容。
+#[async_trait::async_trait]
+pub trait IndexStore: Send + Sync {
+    /// 准备存储（克隆、更新或创建本地副本）
+    async fn prepare(&self) -> Result<(), IndexError>;
+    /// 读取 index.json 的内容，不存在时返回 None
+    async fn load(&self) -> Result<Option<String>, IndexError>;
+    /// 保存 index.json 的内容（并同步到远程，如果有）
+    async fn save(&self, content: &str) -> Result<(), IndexError>;
+    fn description(&self) -> String;
+}
+
+/// 根据配置创建索引存储后端
+///
+/// `local_path` 为 Git 后端的本地缓存目录，本地目录后端使用配置中的路径。
+/// 本地平台上的 `.index` 裸仓库在这里按需创建。
+pub fn from_backend(config: &ConfigManager, organization: &str, local_path: PathBuf) -> Result<Box<dyn IndexStore>, IndexError> {
+    Ok(match config.get_index_backend() {
+        IndexBackend::Forge => {
+            let org_config = config.get_organization_config(organization);
+            if org_config.forge == ForgeKind::Local {
+                forge::local_forge(&org_config)
+                    .ensure_bare_repository(organization, ".index")
+                    .map_err(|e| IndexError::IoError(std::io::Error::other(e.to_string())))?;
+            }
+            let forge = forge::for_organization(config, organization);
+            let remote_url = forge.clone_url(organization, ".index");
+            Box::new(GitIndexStore::new(remote_url, local_path).with_credentials(forge.credentials()))
+        }
+        IndexBackend::Git { url } => Box::new(GitIndexStore::new(url.clone(), local_path)),
+        IndexBackend::Local { path } => Box::new(LocalIndexStore::new(path.clone())),
+    })
+}
+
+fn dot_dir() -> Result<PathBuf, IndexError> {
+    let home = dirs::home_dir().ok_or(IndexError::IoError(
+        std::io::Error::new(std::io::ErrorKind::NotFound, "Home directory not found")
+    ))?;
+    Ok(home.join(".dot"))
+}
+
+/// 组织索引的本地缓存目录: ~/.dot/indexes/<org>
+pub fn organization_local_path(organization: &str) -> Result<PathBuf, IndexError> {
+    Ok(dot_dir()?.join("indexes").join(organization))
+}
+
+/// 将旧版本的 ~/.dot/.index 迁移为默认组织的缓存目录
+pub fn migrate_legacy_local_path(default_organization: &str) -> Result<(), IndexError> {
+    let legacy_path = dot_dir()?.join(".index");
+    let new_path = organization_local_path(default_organization)?;
+    
+    if legacy_path.exists() && !new_path.exists() {
+        if let Some(parent) = new_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(&legacy_path, &new_path)?;
+    }
+    Ok(())
+}
+
+async fn read_index_file(dir: &Path) -> Result<Option<String>, IndexError> {
+    let index_file = dir.join(INDEX_FILE);
+    if !index_file.exists() {
+        return Ok(None);
+    }
+    Ok(Some(tokio::fs::read_to_string(&index_file).await?))
+}
+
+async fn write_index_file(dir: &Path, content: &str) -> Result<(), IndexError> {
+    tokio::fs::write(dir.join(INDEX_FILE), content).await?;
+    Ok(())
+}
+
+fn empty_index() -> Result<String, IndexError> {
+    Ok(serde_json::to_string_pretty(&IndexData::default())?)
+}
+
+/// 基于 Git 仓库的索引存储
+///
+/// 远程仓库可以是组织所在平台上的 `.index` 仓库，也可以是任意 Git URL（自建服务、本地裸仓库等）。
+/// 克隆、拉取和推送使用系统 git 命令，以便复用用户的 Git 凭证；
+/// HTTPS 远程可以额外提供令牌凭据。
+pub struct GitIndexStore {
+    remote_url: String,
+    local_path: PathBuf,
+    credentials: Option<GitCredentials>,
+}
+
+impl GitIndexStore {
+    pub fn new(remote_url: impl Into<String>, local_path: PathBuf) -> Self {
+        Self {
+            remote_url: remote_url.into(),
+            local_path,
+            credentials: None,
+        }
+    }
+
+    /// 使用 HTTPS 凭据访问远程仓库
+    pub fn with_credentials(mut self, credentials: Option<GitCredentials>) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    fn git(&self) -> Command {
+        GitOperations::git_command(self.credentials.as_ref())
+    }
+
+    pub fn remote_url(&self) -> &str {
+        &self.remote_url
+    }
+
+    pub fn local_path(&self) -> &Path {
+        &self.local_path
+    }
+
+    fn clone_repository(&self) -> Result<(), IndexError> {
+        // 确保父目录存在
+        if let Some(parent) = self.local_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let output = self.git()
+            .arg("clone")
+            .arg(&self.remote_url)
+            .arg(&self.local_path)
+            .output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(IndexError::IoError(std::io::Error::other(
+                format!("Failed to clone index repository: {}", stderr)
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn create_local_repository(&self) -> Result<(), IndexError> {
+        std::fs::create_dir_all(&self.local_path)?;
+        let repo = Repository::init(&self.local_path)?;
+        repo.remote("origin", &self.remote_url)?;
+        Ok(())
+    }
+
+    async fn initialize_index_file(&self) -> Result<(), IndexError> {
+        write_index_file(&self.local_path, &empty_index()?).await?;
+        self.commit_index_file("Initialize index repository")?;
+        Ok(())
+    }
+
+    /// 提交 index.json，没有变化时不创建提交
+    fn commit_index_file(&self, message: &str) -> Result<(), IndexError> {
+        let repo = Repository::open(&self.local_path)?;
+        let mut index = repo.index()?;
+        index.add_path(Path::new(INDEX_FILE))?;
+        index.write()?;
+        let tree_id = index.write_tree()?;
+
+        let parent = match repo.head() {
+            Ok(head) => Some(head.peel_to_commit()?),
+            Err(_) => None,
+        };
+        if let Some(parent) = &parent {
+            if parent.tree_id() == tree_id {
+                return Ok(());
+            }
+        }
+
+        let tree = repo.find_tree(tree_id)?;
+        let config = repo.config()?;
+        let name = config.get_string("user.name")
+            .unwrap_or_else(|_| "dot-cli".to_string());
+        let email = config.get_string("user.email")
+            .unwrap_or_else(|_| "dot-cli@example.com".to_string());
+        let signature = Signature::now(&name, &email)?;
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+
+        repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)?;
+        Ok(())
+    }
+
+    fn pull(&self) {
+        // 忽略 pull 失败（可能是远程仓库不存在、为空或网络问题），使用本地数据
+        let _ = self.git()
+            .args(["pull", "--rebase"])
+            .current_dir(&self.local_path)
+            .output();
+    }
+
+    fn push(&self) {
+        let output = self.git()
+            .args(["push", "-u", "origin", "HEAD"])
+            .current_dir(&self.local_path)
+            .output();
+
+        match output {
+            Ok(out) if out.status.success() => {}
+            Ok(out) => {
+                let stderr = String::from_utf8_lossy(&out.stderr);
+                eprintln!("⚠️  索引推送失败，更改仅保存在本地: {}", stderr.trim());
+            }
+            Err(e) => eprintln!("⚠️  索引推送失败，更改仅保存在本地: {}", e),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl IndexStore for GitIndexStore {
+    async fn prepare(&self) -> Result<(), IndexError> {
+        // 本地已存在，尝试更新
+        if self.local_path.exists() {
+            self.pull();
+            return Ok(());
+        }
+
+        if self.clone_repository().is_err() {
+            // 克隆失败，可能是仓库不存在，创建本地索引
+            println!("⚠️  无法克隆索引仓库，将创建本地索引");
+            println!("   请确保远程索引仓库存在: {}", self.remote_url);
+            self.create_local_repository()?;
+        }
+
+        // 如果仓库是空的，创建初始的 index.json 文件
+        if !self.local_path.join(INDEX_FILE).exists() {
+            self.initialize_index_file().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn load(&self) -> Result<Option<String>, IndexError> {
+        read_index_file(&self.local_path).await
+    }
+
+    async fn save(&self, content: &str) -> Result<(), IndexError> {
+        write_index_file(&self.local_path, content).await?;
+        self.commit_index_file("Update index")?;
+        self.push();
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!("git index {}", self.remote_url)
+    }
+}
+
+/// 基于本地目录的索引存储，不做任何同步（离线或单机使用）
+pub struct LocalIndexStore {
+    path: PathBuf,
+}
+
+impl LocalIndexStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+#[async_trait::async_trait]
+impl IndexStore for LocalIndexStore {
+    async fn prepare(&self) -> Result<(), IndexError> {
+        tokio::fs::create_dir_all(&self.path).await?;
+        if !self.path.join(INDEX_FILE).exists() {
+            write_index_file(&self.path, &empty_index()?).await?;
+        }
+        Ok(())
+    }
+
+    async fn load(&self) -> Result<Option<String>, IndexError> {
+        read_index_file(&self.path).await
+    }
+
+    async fn save(&self, content: &str) -> Result<(), IndexError> {
+        write_index_file(&self.path, content).await
+    }
+
+    fn description(&self) -> String {
+        format!("local index {}", self.path.display())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::ProjectRegistration;
+    use tempfile::TempDir;
+
+    fn sample_registration(key: &str) -> ProjectRegistration {
+        ProjectRegistration {
+            repository_key: key.to_string(),
+            repository_name: format!("{:x}", md5::compute(key.as_bytes())),
+            organization: "test-org".to_string(),
+            owner: None,
+            git_user: "testuser".to_string(),
+            project_git_path: "git@github.com:user/repo.git".to_string(),
+            project_disk_path: String::new(),
+            hidden_directory: ".kiro".to_string(),
+            created_at: chrono::Utc::now(),
+            remote_url: None,
+            shared: None,
+        }
+    }
+
+    fn init_bare(path: &Path) {
+        Repository::init_bare(path).unwrap();
+    }
+
+    async fn load_data(store: &dyn IndexStore) -> IndexData {
+        let content = store.load().await.unwrap().unwrap();
+        serde_json::from_str(&content).unwrap()
+    }
+
+    async fn save_data(store: &dyn IndexStore, data: &IndexData) {
+        store.save(&serde_json::to_string_pretty(data).unwrap()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_local_store_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = LocalIndexStore::new(temp_dir.path().join("index"));
+
+        store.prepare().await.unwrap();
+        assert!(load_data(&store).await.projects.is_empty());
+
+        let mut data = IndexData::default();
+        let registration = sample_registration("github.com:user/repo/.kiro");
+        data.projects.insert(registration.repository_key.clone(), registration);
+        save_data(&store, &data).await;
+
+        let loaded = load_data(&store).await;
+        assert!(loaded.projects.contains_key("github.com:user/repo/.kiro"));
+    }
+
+    #[tokio::test]
+    async fn test_git_store_against_local_bare_repo() {
+        let temp_dir = TempDir::new().unwrap();
+        let remote = temp_dir.path().join("index.git");
+        init_bare(&remote);
+        let remote_url = remote.to_string_lossy().to_string();
+
+        // 第一台机器：克隆空仓库并写入
+        let first = GitIndexStore::new(remote_url.clone(), temp_dir.path().join("first"));
+        first.prepare().await.unwrap();
+        let mut data = load_data(&first).await;
+        let registration = sample_registration("github.com:user/repo/.kiro");
+        data.projects.insert(registration.repository_key.clone(), registration);
+        save_data(&first, &data).await;
+
+        // 第二台机器：克隆后应能看到第一台写入的数据
+        let second = GitIndexStore::new(remote_url, temp_dir.path().join("second"));
+        second.prepare().await.unwrap();
+        let loaded = load_data(&second).await;
+        assert!(loaded.projects.contains_key("github.com:user/repo/.kiro"));
+    }
+
+    #[tokio::test]
+    async fn test_git_store_falls_back_to_local_repository() {
+        let temp_dir = TempDir::new().unwrap();
+        let missing_remote = temp_dir.path().join("missing.git");
+        let local_path = temp_dir.path().join("index");
+
+        let store = GitIndexStore::new(missing_remote.to_string_lossy().to_string(), local_path.clone());
+        store.prepare().await.unwrap();
+
+        assert!(local_path.join(INDEX_FILE).exists());
+        let repo = Repository::open(&local_path).unwrap();
+        assert!(repo.find_remote("origin").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_forge_backend_with_local_forge() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("remotes");
+        let config: crate::config::DotConfig = serde_json::from_value(serde_json::json!({
+            "authorized_organizations": ["acme"],
+            "default_organization": "acme",
+            "organizations": {"acme": {"forge": "local", "root": root}}
+        })).unwrap();
+        let config = ConfigManager::new(temp_dir.path().join("dot.conf"), config);
+
+        // `.index` 裸仓库按需创建，URL 为 file://
+        let store = from_backend(&config, "acme", temp_dir.path().join("first")).unwrap();
+        assert!(Repository::open_bare(root.join("acme").join(".index.git")).is_ok());
+        assert!(store.description().contains("file://"));
+
+        store.prepare().await.unwrap();
+        let mut data = load_data(store.as_ref()).await;
+        let registration = sample_registration("github.com:user/repo/.kiro");
+        data.projects.insert(registration.repository_key.clone(), registration);
+        save_data(store.as_ref(), &data).await;
+
+        let second = from_backend(&config, "acme", temp_dir.path().join("second")).unwrap();
+        second.prepare().await.unwrap();
+        assert!(load_data(second.as_ref()).await.projects.contains_key("github.com:user/repo/.kiro"));
+    }
+}
@@ -7,10 +7,19 @@
 pub mod error;
 pub mod config;
 pub mod index;
+pub mod index_store;
+pub mod index_crypto;
+pub mod manifest;
+pub mod hidden_files;
+pub mod template;
+pub mod naming;
+pub mod deploy_key;
+pub mod foreach;
 pub mod git_operations;
 pub mod atomic;
 pub mod repository;
 pub mod setup;
-pub mod github;
+pub mod state;
+pub mod forge;
 
 pub use error::*;
@@ -1,5 +1,6 @@
 use clap::{Parser, Subcommand};
-use dot::{config::ConfigManager, index::IndexManager, repository::RepositoryManager, error::DotError, setup::SetupWizard};
+use std::path::PathBuf;
+use dot::{config::{default_identity_path, ConfigManager}, foreach::ForeachOptions, index_crypto::IndexCipher, index::IndexManager, repository::{DeinitOptions, InitOptions, RepositoryManager}, error::{DotError, RepositoryError}, setup::SetupWizard};
 
 #[derive(Parser)]
 #[command(name = "dot")]
@@ -23,7 +24,89 @@ enum Commands {
     /// Initialize dot project with hidden directories
     Init { 
         /// Hidden directories to manage
-        directories: Vec<String> 
+        directories: Vec<String>,
+        /// Record hidden directories in the project-local manifest instead of the global index
+        #[arg(long)]
+        manifest: bool,
+        /// Organization to create hidden repositories in (must be authorized)
+        #[arg(long)]
+        org: Option<String>,
+        /// Register the existing git repositories in the directories with their current origin instead of creating new ones
+        #[arg(long)]
+        adopt: bool,
+        /// Hide a single file such as .env (stored in the project's .dot-files repository); can be repeated
+        #[arg(long = "file", value_name = "FILE")]
+        files: Vec<String>,
+        /// Create a shared hidden repository with this name that other projects can link with `dot link`
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["adopt", "manifest", "files"])]
+        shared: Option<String>,
+        /// Seed new hidden repositories with a template defined in ~/.dot/dot.conf and commit it
+        #[arg(long, value_name = "NAME", conflicts_with = "adopt")]
+        template: Option<String>,
+    },
+    /// Link a shared hidden repository into a directory of the current project
+    Link {
+        /// Name of the shared hidden repository
+        name: String,
+        /// Hidden directory to check it out into
+        directory: String,
+        /// Organization to look the repository up in when it is not in the index yet (must be authorized)
+        #[arg(long)]
+        org: Option<String>,
+    },
+    /// Stop managing a hidden directory with dot
+    Deinit {
+        /// Hidden directory to detach
+        directory: String,
+        /// Keep the local directory (including its .git)
+        #[arg(long)]
+        keep_local: bool,
+        /// Keep the remote hidden repository
+        #[arg(long)]
+        keep_remote: bool,
+        /// Remove the directory's .git so the parent repository tracks its files
+        #[arg(long, conflicts_with = "keep_local")]
+        fold_into_parent: bool,
+    },
+    /// Rename a hidden directory together with its registration and remote repository
+    Mv {
+        /// Current hidden directory
+        from: String,
+        /// New hidden directory
+        to: String,
+    },
+    /// Move hidden repositories registered under a project's old URL to its current origin
+    Relink {
+        /// Previous remote URL of the parent repository
+        #[arg(long)]
+        from: String,
+        /// Also rename the hidden remote repositories to match the new repository keys
+        #[arg(long)]
+        rename_remotes: bool,
+    },
+    /// Run a shell command in every hidden repository, like git submodule foreach
+    Foreach {
+        /// Also run the command in the parent repository (first)
+        #[arg(long)]
+        include_parent: bool,
+        /// Run up to N commands at a time; output is printed per repository when it finishes
+        #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+        parallel: u16,
+        /// Keep running in the remaining repositories after a command fails
+        #[arg(long)]
+        continue_on_error: bool,
+        /// Command to run; it gets DOT_DIR, DOT_REPO_KEY and DOT_REMOTE in its environment
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+    /// Run git in the parent and every hidden repository, e.g. dot git -- log --oneline -5
+    Git {
+        /// Only run in this directory ("." for the parent repository); can be repeated
+        #[arg(long, value_name = "DIR")]
+        only: Vec<String>,
+        /// Arguments passed to git
+        #[arg(required = true, last = true, allow_hyphen_values = true)]
+        args: Vec<String>,
     },
     /// Show status of all repositories
     Status,
@@ -47,6 +130,92 @@ enum Commands {
         /// Target directory name (optional)
         target: Option<String>,
     },
+    /// List known local checkouts of a project
+    Where {
+        /// Repository URL, base key, or trailing path such as user/repo
+        project: String,
+    },
+    /// Manage keys for the encrypted global index
+    IndexKey {
+        #[command(subcommand)]
+        action: IndexKeyAction,
+    },
+    /// Manage the project-local manifest (.git/dot/manifest.json)
+    Manifest {
+        #[command(subcommand)]
+        action: ManifestAction,
+    },
+    /// Manage deploy keys that give CI and servers access to hidden repositories
+    DeployKey {
+        #[command(subcommand)]
+        action: DeployKeyAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum DeployKeyAction {
+    /// Generate ed25519 keys and register them on every hidden repository of a project
+    Add {
+        /// Project: "." for the current directory, a repository URL, base key, or trailing path such as user/repo
+        project: String,
+        /// Grant write access (read-only by default)
+        #[arg(long)]
+        write: bool,
+        /// Key name, used as the key title and directory under ~/.dot/deploy-keys (default: hostname)
+        #[arg(long)]
+        name: Option<String>,
+        /// Write the SSH config snippet to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// List deploy keys added by dot on the project's hidden repositories
+    List {
+        /// Project: "." for the current directory, a repository URL, base key, or trailing path
+        project: String,
+    },
+    /// Remove deploy keys and the local key files
+    Revoke {
+        /// Project: "." for the current directory, a repository URL, base key, or trailing path
+        project: String,
+        /// Key name (default: hostname)
+        #[arg(long)]
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum IndexKeyAction {
+    /// Generate a local identity (private key) for decrypting the index
+    Generate,
+    /// Show the public key of the local identity
+    Show,
+    /// List recipients the index is encrypted to
+    List,
+    /// Add a recipient public key and re-encrypt the index
+    Add {
+        /// age X25519 public key (age1...)
+        recipient: String,
+    },
+    /// Remove a recipient and re-encrypt the index (plaintext if none remain)
+    Remove {
+        /// age X25519 public key (age1...)
+        recipient: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ManifestAction {
+    /// Export this project's hidden directory mapping
+    Export {
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Import a previously exported mapping and clone missing hidden repositories
+    Import {
+        /// Manifest file to import
+        file: PathBuf,
+    },
 }
 
 #[tokio::main]
@@ -65,7 +234,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
     
     // Load configuration
-    let config = match ConfigManager::load().await {
+    let mut config = match ConfigManager::load().await {
         Ok(config) => config,
         Err(e) => {
             eprintln!("Failed to load configuration: {}", e);
@@ -75,6 +244,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
     
+    // 索引密钥管理在加载索引之前处理（没有私钥时可能无法加载加密的索引）
+    if let Commands::IndexKey { action } = &cli.command {
+        if let Err(e) = run_index_key(action, &mut config).await {
+            eprintln!("dot: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    
     // 检查是否有配置默认组织
     if config.get_default_organization().is_none() {
         eprintln!("Error: No default organization configured");
@@ -99,17 +277,44 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     // Execute command
     let result = match cli.command {
-        Commands::Setup => {
+        Commands::Setup | Commands::IndexKey { .. } => {
             // 已在前面处理
             Ok(())
         },
-        Commands::Init { directories } => {
-            if directories.is_empty() {
-                eprintln!("Error: At least one directory must be specified");
-                eprintln!("Usage: dot init <directory1> [directory2] ...");
+        Commands::Init { directories, manifest, org, adopt, files, shared, template } => {
+            if directories.is_empty() && files.is_empty() {
+                eprintln!("Error: At least one directory or --file must be specified");
+                eprintln!("Usage: dot init <directory1> [directory2] ... [--file <file>]...");
                 std::process::exit(1);
             }
-            repo_manager.init_project(directories, cli.skip_hidden, cli.no_atomic).await
+            let options = InitOptions { manifest, organization: org, adopt, files, shared, template };
+            repo_manager.init_project(directories, cli.skip_hidden, cli.no_atomic, options).await
+                .map_err(DotError::from)
+        },
+        Commands::Link { name, directory, org } => {
+            repo_manager.link_shared_repository(&name, &directory, org).await
+                .map_err(DotError::from)
+        },
+        Commands::Deinit { directory, keep_local, keep_remote, fold_into_parent } => {
+            let options = DeinitOptions { keep_local, keep_remote, fold_into_parent };
+            repo_manager.deinit_project(&directory, cli.no_atomic, options).await
+                .map_err(DotError::from)
+        },
+        Commands::Mv { from, to } => {
+            repo_manager.move_hidden_directory(&from, &to, cli.no_atomic).await
+                .map_err(DotError::from)
+        },
+        Commands::Relink { from, rename_remotes } => {
+            repo_manager.relink_project(&from, rename_remotes, cli.no_atomic).await
+                .map_err(DotError::from)
+        },
+        Commands::Foreach { include_parent, parallel, continue_on_error, command } => {
+            let options = ForeachOptions { parallel: parallel.into(), continue_on_error, prefix_output: false };
+            repo_manager.foreach(&command.join(" "), include_parent, cli.skip_hidden, options).await
+                .map_err(DotError::from)
+        },
+        Commands::Git { only, args } => {
+            repo_manager.git_passthrough(args, &only, cli.skip_hidden).await
                 .map_err(DotError::from)
         },
         Commands::Status => {
@@ -147,6 +352,66 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             repo_manager.clone_project(url, target).await
                 .map_err(DotError::from)
         },
+        Commands::Where { project } => {
+            match repo_manager.where_project(&project) {
+                Ok(checkouts) => {
+                    println!("{}", checkouts);
+                    Ok(())
+                },
+                Err(e) => Err(DotError::from(e)),
+            }
+        },
+        Commands::Manifest { action: ManifestAction::Export { output } } => {
+            match repo_manager.export_manifest().await.and_then(|m| m.to_json()) {
+                Ok(json) => match output {
+                    Some(path) => std::fs::write(&path, json)
+                        .map(|_| println!("Manifest exported to {}", path.display()))
+                        .map_err(|e| DotError::from(RepositoryError::from(e))),
+                    None => {
+                        println!("{}", json);
+                        Ok(())
+                    }
+                },
+                Err(e) => Err(DotError::from(e)),
+            }
+        },
+        Commands::Manifest { action: ManifestAction::Import { file } } => {
+            repo_manager.import_manifest(&file).await
+                .map_err(DotError::from)
+        },
+        Commands::DeployKey { action: DeployKeyAction::Add { project, write, name, output } } => {
+            match repo_manager.add_deploy_keys(&project, name, write).await {
+                Ok(snippet) => match output {
+                    Some(path) => std::fs::write(&path, snippet)
+                        .map(|_| println!("SSH config written to {} (add 'Include {}' to ~/.ssh/config)", path.display(), path.display()))
+                        .map_err(|e| DotError::from(RepositoryError::from(e))),
+                    None => {
+                        println!();
+                        print!("{}", snippet);
+                        Ok(())
+                    }
+                },
+                Err(e) => Err(DotError::from(e)),
+            }
+        },
+        Commands::DeployKey { action: DeployKeyAction::List { project } } => {
+            match repo_manager.list_deploy_keys(&project).await {
+                Ok(keys) => {
+                    println!("{}", keys);
+                    Ok(())
+                },
+                Err(e) => Err(DotError::from(e)),
+            }
+        },
+        Commands::DeployKey { action: DeployKeyAction::Revoke { project, name } } => {
+            match repo_manager.revoke_deploy_keys(&project, name).await {
+                Ok(message) => {
+                    println!("{}", message);
+                    Ok(())
+                },
+                Err(e) => Err(DotError::from(e)),
+            }
+        },
     };
     
     match result {
@@ -157,3 +422,60 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 }
+
+async fn run_index_key(action: &IndexKeyAction, config: &mut ConfigManager) -> Result<(), DotError> {
+    let identity_path = match config.get_index_encryption() {
+        Some(encryption) => encryption.identity_path()?,
+        None => default_identity_path()?,
+    };
+    
+    match action {
+        IndexKeyAction::Generate => {
+            let public_key = IndexCipher::generate_identity_file(&identity_path)?;
+            println!("Identity written to {}", identity_path.display());
+            println!("Public key: {}", public_key);
+            println!();
+            println!("Ask someone who can read the index to run: dot index-key add {}", public_key);
+        },
+        IndexKeyAction::Show => {
+            println!("{}", IndexCipher::public_key(&identity_path)?);
+        },
+        IndexKeyAction::List => {
+            let index_manager = IndexManager::new(config).await?;
+            let indexes = index_manager.recipients();
+            for (organization, recipients) in &indexes {
+                if indexes.len() > 1 {
+                    println!("{}:", organization);
+                }
+                if recipients.is_empty() {
+                    println!("Index encryption is disabled (no recipients recorded in the index)");
+                }
+                for recipient in recipients.iter() {
+                    println!("{}", recipient);
+                }
+            }
+        },
+        // 接收者随索引保存；索引重新保存成功后才更新本机配置（用于之后新建的索引）
+        IndexKeyAction::Add { recipient } => {
+            IndexCipher::parse_recipient(recipient)?;
+            let changed = IndexManager::new(config).await?.add_recipient(recipient).await?;
+            let configured = config.add_index_recipient(recipient.clone()).await?;
+            if !changed && !configured {
+                println!("Recipient already configured");
+                return Ok(());
+            }
+            println!("Added recipient and re-encrypted the index");
+        },
+        IndexKeyAction::Remove { recipient } => {
+            let changed = IndexManager::new(config).await?.remove_recipient(recipient).await?;
+            let configured = config.remove_index_recipient(recipient).await?;
+            if !changed && !configured {
+                println!("Recipient not configured");
+                return Ok(());
+            }
+            println!("Removed recipient and re-wrote the index");
+        },
+    }
+    
+    Ok(())
+}
@@ -0,0 +1,136 @@
+use crate::error::RepositoryError;
+use crate::index::ProjectRegistration;
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// 项目本地清单
+///
+/// 保存在父仓库的 `.git/dot/manifest.json` 中，不会被 git 跟踪，
+/// 用于不希望使用共享 `.index` 仓库的团队记录项目的隐藏目录。
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProjectManifest {
+    pub projects: HashMap<String, ProjectRegistration>,
+}
+
+impl ProjectManifest {
+    /// 清单文件路径（位于仓库的 git 目录中）
+    pub fn path<P: AsRef<Path>>(repo_path: P) -> Result<PathBuf, RepositoryError> {
+        let repo = Repository::open(repo_path)?;
+        Ok(repo.path().join("dot").join("manifest.json"))
+    }
+
+    pub fn exists<P: AsRef<Path>>(repo_path: P) -> bool {
+        Self::path(repo_path).map(|p| p.exists()).unwrap_or(false)
+    }
+
+    /// 读取清单，不存在时返回 None
+    pub fn load<P: AsRef<Path>>(repo_path: P) -> Result<Option<Self>, RepositoryError> {
+        let path = Self::path(repo_path)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(Self::read_from(&path)?))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, repo_path: P) -> Result<(), RepositoryError> {
+        let path = Self::path(repo_path)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        self.write_to(&path)
+    }
+
+    pub fn read_from(path: &Path) -> Result<Self, RepositoryError> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn write_to(&self, path: &Path) -> Result<(), RepositoryError> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> Result<String, RepositoryError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn contains(&self, repository_key: &str) -> bool {
+        self.projects.contains_key(repository_key)
+    }
+
+    pub fn register(&mut self, registration: ProjectRegistration) -> Result<(), RepositoryError> {
+        if self.contains(&registration.repository_key) {
+            return Err(RepositoryError::ProjectAlreadyExists(registration.repository_key));
+        }
+        self.projects.insert(registration.repository_key.clone(), registration);
+        Ok(())
+    }
+
+    /// 移除项目，返回被移除的注册信息
+    pub fn unregister(&mut self, repository_key: &str) -> Option<ProjectRegistration> {
+        self.projects.remove(repository_key)
+    }
+
+    pub fn find_projects_by_base_key(&self, base_key: &str) -> Vec<&ProjectRegistration> {
+        self.projects
+            .values()
+            .filter(|p| p.base_key() == base_key)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_operations::GitOperations;
+    use tempfile::TempDir;
+
+    fn registration(key: &str, dir: &str) -> ProjectRegistration {
+        ProjectRegistration {
+            repository_key: key.to_string(),
+            repository_name: format!("{:x}", md5::compute(key.as_bytes())),
+            organization: "test-org".to_string(),
+            owner: None,
+            git_user: "testuser".to_string(),
+            project_git_path: "git@github.com:user/repo.git".to_string(),
+            project_disk_path: String::new(),
+            hidden_directory: dir.to_string(),
+            created_at: chrono::Utc::now(),
+            remote_url: None,
+            shared: None,
+        }
+    }
+
+    #[test]
+    fn test_manifest_is_stored_inside_git_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        GitOperations::init_repository(temp_dir.path()).unwrap();
+
+        assert!(!ProjectManifest::exists(temp_dir.path()));
+        assert!(ProjectManifest::load(temp_dir.path()).unwrap().is_none());
+
+        let mut manifest = ProjectManifest::default();
+        manifest.register(registration("github.com:user/repo/.kiro", ".kiro")).unwrap();
+        manifest.save(temp_dir.path()).unwrap();
+
+        let path = ProjectManifest::path(temp_dir.path()).unwrap();
+        assert!(path.starts_with(temp_dir.path().join(".git")));
+
+        // 清单不应出现在工作区状态中
+        let status = GitOperations::get_status(temp_dir.path()).unwrap();
+        assert_eq!(status, "nothing to commit, working tree clean");
+
+        let loaded = ProjectManifest::load(temp_dir.path()).unwrap().unwrap();
+        assert_eq!(loaded.find_projects_by_base_key("github.com:user/repo").len(), 1);
+    }
+
+    #[test]
+    fn test_manifest_rejects_duplicates() {
+        let mut manifest = ProjectManifest::default();
+        manifest.register(registration("github.com:user/repo/.kiro", ".kiro")).unwrap();
+        let result = manifest.register(registration("github.com:user/repo/.kiro", ".kiro"));
+        assert!(matches!(result, Err(RepositoryError::ProjectAlreadyExists(_))));
+    }
+}
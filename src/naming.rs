@@ -0,0 +1,201 @@
+//! 隐藏仓库的远程仓库命名
+//!
+//! 早期版本使用 `md5(repository_key)` 作为仓库名，在组织的仓库列表中无法辨认。
+//! 新的命名策略在配置中选择，生成的名称保存在 [`ProjectRegistration`](crate::index::ProjectRegistration)
+//! 中，因此修改策略不会影响已有项目。
+
+use crate::config::RepositoryNaming;
+use crate::error::ConfigError;
+use sha2::{Digest, Sha256};
+
+/// 平台允许的最大仓库名长度（GitHub 为 100）
+const MAX_NAME_LENGTH: usize = 100;
+
+/// 截断的 SHA-256 长度（十六进制字符数）
+const SHA256_LENGTH: usize = 32;
+
+/// 名称后缀中使用的短哈希长度
+const SHORT_HASH_LENGTH: usize = 8;
+
+/// 旧版本使用的仓库名
+pub fn legacy_name(repository_key: &str) -> String {
+    format!("{:x}", md5::compute(repository_key.as_bytes()))
+}
+
+/// 按命名策略生成远程仓库名
+///
+/// `repository_key` 形如 `github.com:owner/project/.kiro`，`directory` 为隐藏目录。
+pub fn repository_name(
+    strategy: &RepositoryNaming,
+    repository_key: &str,
+    directory: &str,
+) -> Result<String, ConfigError> {
+    let hash = sha256_hex(repository_key);
+    let short_hash = &hash[..SHORT_HASH_LENGTH];
+
+    match strategy {
+        RepositoryNaming::Md5 => Ok(legacy_name(repository_key)),
+        RepositoryNaming::Sha256 => Ok(hash[..SHA256_LENGTH].to_string()),
+        RepositoryNaming::Slug => {
+            let parts = KeyParts::parse(repository_key, directory);
+            let prefix = format!("{}--{}", slugify(parts.project), slugify(directory));
+            Ok(with_hash_suffix(&prefix, short_hash))
+        }
+        RepositoryNaming::Template(template) => render_template(template, repository_key, directory, short_hash),
+    }
+}
+
+/// repository_key 中的各个部分
+struct KeyParts<'a> {
+    host: &'a str,
+    owner: &'a str,
+    project: &'a str,
+}
+
+impl<'a> KeyParts<'a> {
+    fn parse(repository_key: &'a str, directory: &str) -> Self {
+        let base_key = repository_key
+            .strip_suffix(directory)
+            .map(|base| base.trim_end_matches('/'))
+            .unwrap_or(repository_key);
+        let (host, path) = base_key
+            .split_once(':')
+            .or_else(|| base_key.split_once('/'))
+            .unwrap_or(("", base_key));
+        let (owner, project) = path.rsplit_once('/').unwrap_or(("", path));
+        Self { host, owner, project }
+    }
+}
+
+/// 渲染用户模板，支持 `{host}` `{owner}` `{project}` `{dir}` `{hash}` `{md5}`
+fn render_template(
+    template: &str,
+    repository_key: &str,
+    directory: &str,
+    short_hash: &str,
+) -> Result<String, ConfigError> {
+    let parts = KeyParts::parse(repository_key, directory);
+    let mut name = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        name.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| ConfigError::InvalidNamingTemplate(format!("unclosed '{{' in \"{}\"", template)))?;
+        let placeholder = &rest[start + 1..start + end];
+        let value = match placeholder {
+            "host" => slugify(parts.host),
+            "owner" => slugify(parts.owner),
+            "project" => slugify(parts.project),
+            "dir" => slugify(directory),
+            "hash" => short_hash.to_string(),
+            "md5" => legacy_name(repository_key),
+            other => {
+                return Err(ConfigError::InvalidNamingTemplate(format!(
+                    "unknown placeholder {{{}}} in \"{}\"",
+                    other, template
+                )))
+            }
+        };
+        name.push_str(&value);
+        rest = &rest[start + end + 1..];
+    }
+    name.push_str(rest);
+
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err(ConfigError::InvalidNamingTemplate(format!(
+            "\"{}\" renders to invalid repository name \"{}\"",
+            template, name
+        )));
+    }
+    Ok(name)
+}
+
+/// 转换为仓库名可用的形式：小写字母、数字和单个 `-`
+fn slugify(value: &str) -> String {
+    let mut slug = String::new();
+    for c in value.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// 追加短哈希以避免冲突，过长时截断前缀
+fn with_hash_suffix(prefix: &str, short_hash: &str) -> String {
+    let max_prefix = MAX_NAME_LENGTH - short_hash.len() - 1;
+    let prefix = if prefix.len() > max_prefix {
+        prefix[..max_prefix].trim_end_matches('-')
+    } else {
+        prefix
+    };
+    if prefix.is_empty() {
+        short_hash.to_string()
+    } else {
+        format!("{}-{}", prefix, short_hash)
+    }
+}
+
+fn sha256_hex(value: &str) -> String {
+    Sha256::digest(value.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "github.com:acme/My_Project/.kiro";
+
+    #[test]
+    fn test_legacy_and_hash_names() {
+        let md5 = repository_name(&RepositoryNaming::Md5, KEY, ".kiro").unwrap();
+        assert_eq!(md5, format!("{:x}", md5::compute(KEY)));
+
+        let sha = repository_name(&RepositoryNaming::Sha256, KEY, ".kiro").unwrap();
+        assert_eq!(sha.len(), SHA256_LENGTH);
+        assert!(sha.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn test_slug_name() {
+        let name = repository_name(&RepositoryNaming::Slug, KEY, ".kiro").unwrap();
+        let hash = &sha256_hex(KEY)[..SHORT_HASH_LENGTH];
+        assert_eq!(name, format!("my-project--kiro-{}", hash));
+
+        // 同名项目在不同 owner 下不会冲突
+        let other = repository_name(&RepositoryNaming::Slug, "github.com:other/My_Project/.kiro", ".kiro").unwrap();
+        assert_ne!(name, other);
+
+        let nested = repository_name(&RepositoryNaming::Slug, "gitlab.com/group/sub/app/.config/app", ".config/app").unwrap();
+        assert!(nested.starts_with("app--config-app-"), "{}", nested);
+
+        let long_key = format!("github.com:acme/{}/.kiro", "x".repeat(200));
+        assert_eq!(repository_name(&RepositoryNaming::Slug, &long_key, ".kiro").unwrap().len(), MAX_NAME_LENGTH);
+    }
+
+    #[test]
+    fn test_template_name() {
+        let template = RepositoryNaming::Template("dot-{owner}-{project}-{dir}-{hash}".to_string());
+        let name = repository_name(&template, KEY, ".kiro").unwrap();
+        assert_eq!(name, format!("dot-acme-my-project-kiro-{}", &sha256_hex(KEY)[..SHORT_HASH_LENGTH]));
+
+        let https = repository_name(&template, "github.com/acme/app/.kiro", ".kiro").unwrap();
+        assert!(https.starts_with("dot-acme-app-kiro-"));
+
+        for invalid in ["{project", "{unknown}", "{project}/{dir}", ""] {
+            let strategy = RepositoryNaming::Template(invalid.to_string());
+            assert!(repository_name(&strategy, KEY, ".kiro").is_err(), "{}", invalid);
+        }
+    }
+}
@@ -1,41 +1,151 @@
-use crate::config::ConfigManager;
-use crate::index::{IndexManager, ProjectRegistration};
-use crate::git_operations::GitOperations;
-use crate::atomic::{AtomicOperations, AddOperation, CommitOperation, PushOperation};
-use crate::github::GitHubClient;
-use crate::error::RepositoryError;
+use crate::config::{ConfigManager, RemoteCleanup};
+use crate::index::{IndexManager, ProjectRegistration, SharedLink, SharedRepository};
+use crate::git_operations::{GitCredentials, GitOperations};
+use crate::atomic::{
+    AtomicOperations, Operation, AddOperation, CommitOperation, PushOperation,
+    DisposeRemoteOperation, IgnoreRuleOperation, MovePathOperation, RegistrationStore,
+    RenameRemoteOperation, ReplaceRegistrationOperation, SetOriginOperation, UnregisterOperation,
+};
+use crate::forge::{self, Forge};
+use crate::deploy_key;
+use crate::foreach::{self, ForeachOptions, Invocation};
+use crate::hidden_files::{self, STORE_DIRECTORY};
+use crate::template::Template;
+use crate::manifest::ProjectManifest;
+use crate::state::StateManager;
+use crate::error::{ConfigError, RepositoryError};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::env;
-use md5;
+
+/// `dot init` 的选项
+#[derive(Debug, Default)]
+pub struct InitOptions {
+    /// 记录到项目本地清单而不是全局索引
+    pub manifest: bool,
+    /// 创建隐藏仓库的组织（默认为配置中的默认组织）
+    pub organization: Option<String>,
+    /// 接管目录中已有的 git 仓库，原样登记其 origin 而不创建新仓库
+    pub adopt: bool,
+    /// 保存到项目 `.dot-files` 隐藏仓库中的单个文件（如 `.env`）
+    pub files: Vec<String>,
+    /// 创建名为该值的共享隐藏仓库并链接到当前项目（其他项目用 `dot link` 链接）
+    pub shared: Option<String>,
+    /// 复制到新隐藏仓库中的模板（配置中 `templates` 的名称）
+    pub template: Option<String>,
+}
+
+/// `dot deinit` 的选项
+#[derive(Debug, Default)]
+pub struct DeinitOptions {
+    /// 保留本地目录（包括其中的 `.git`），只是不再由 dot 管理
+    pub keep_local: bool,
+    /// 保留远程隐藏仓库
+    pub keep_remote: bool,
+    /// 删除目录中的 `.git`，让其中的文件改由父仓库跟踪
+    pub fold_into_parent: bool,
+}
 
 pub struct RepositoryManager {
-    #[allow(dead_code)]
     config: ConfigManager,
     index_manager: IndexManager,
-    github_client: GitHubClient,
+    /// 本次运行中新建的远程仓库，按 (组织, 仓库名) 记录
+    created_remotes: HashMap<(String, String), CreatedRemote>,
+}
+
+/// 本次运行中新建的远程仓库
+struct CreatedRemote {
+    /// 仓库实际所在的账户
+    owner: String,
+    /// 创建时的分支快照（读取失败时为 None）
+    heads: Option<String>,
 }
 
 impl RepositoryManager {
     pub fn new(config: ConfigManager, index_manager: IndexManager) -> Self {
-        let github_token = config.get_github_token();
-        let github_client = GitHubClient::new(github_token);
-        Self { config, index_manager, github_client }
+        Self { config, index_manager, created_remotes: HashMap::new() }
+    }
+    
+    /// 组织所在平台的客户端
+    fn forge(&self, org: &str) -> Box<dyn Forge> {
+        forge::for_organization(&self.config, org)
     }
     
     pub async fn init_project(
         &mut self,
         directories: Vec<String>,
         skip_hidden: bool,
-        no_atomic: bool
+        no_atomic: bool,
+        options: InitOptions
     ) -> Result<(), RepositoryError> {
         let current_dir = env::current_dir()?;
+        // 共享隐藏仓库的链接只记录在全局索引中
+        let use_manifest = options.shared.is_none() && (options.manifest || self.config.use_manifest());
+        
+        // 确定隐藏仓库所在的组织，必须是已授权的组织
+        let org = match options.organization {
+            Some(org) => {
+                if !self.config.is_organization_authorized(&org) {
+                    return Err(ConfigError::OrganizationNotAuthorized.into());
+                }
+                org
+            }
+            None => self.index_manager.get_organization().to_string(),
+        };
+        let template_source = options.template.as_deref()
+            .map(|name| self.config.template(name))
+            .transpose()?;
         
         // 检查并初始化 git
         self.ensure_git_initialized(&current_dir).await?;
         
         // 获取 remote origin
         let remote_url = self.get_remote_origin(&current_dir)?;
-        let _base_key = GitOperations::generate_base_key(&remote_url)?;
+        let base_key = GitOperations::generate_base_key(&remote_url)?;
+        
+        // 规范化隐藏目录和隐藏文件的路径（拒绝绝对路径和 `..`）
+        let mut directories = directories
+            .iter()
+            .map(|dir| GitOperations::normalize_hidden_path(dir))
+            .collect::<Result<Vec<_>, _>>()?;
+        let files = options.files
+            .iter()
+            .map(|file| GitOperations::normalize_hidden_path(file))
+            .collect::<Result<Vec<_>, _>>()?;
+        for file in &files {
+            if !current_dir.join(file).is_file() {
+                return Err(RepositoryError::InvalidHiddenDirectory {
+                    path: file.clone(),
+                    reason: "not a regular file".to_string(),
+                });
+            }
+        }
+        
+        // 隐藏文件保存在 `.dot-files` 隐藏仓库中，还没有时一起创建
+        let store_key = GitOperations::generate_repository_key(&remote_url, Some(STORE_DIRECTORY))?;
+        let store_registered = self.find_registrations(&current_dir, &base_key)?
+            .iter()
+            .any(|p| p.repository_key == store_key);
+        if !files.is_empty() && !store_registered && !directories.iter().any(|d| d == STORE_DIRECTORY) {
+            directories.push(STORE_DIRECTORY.to_string());
+        }
+        
+        if let Some(name) = &options.shared {
+            validate_shared_name(name)?;
+            if directories.len() != 1 {
+                return Err(RepositoryError::InvalidHiddenDirectory {
+                    path: directories.join(" "),
+                    reason: "--shared takes exactly one directory".to_string(),
+                });
+            }
+            if self.index_manager.shared_repository(name).is_some() {
+                return Err(RepositoryError::IoError(std::io::Error::new(
+                    std::io::ErrorKind::AlreadyExists,
+                    format!("Shared repository '{}' already exists; run 'dot link {} {}'", name, name, directories[0]),
+                )));
+            }
+        }
+        let shared = options.shared.as_deref();
         
         // 生成所有 Repository Keys 并检查重复，同时记录目录是否已存在
         let mut repo_keys = Vec::new();
@@ -45,32 +155,79 @@ impl RepositoryManager {
             repo_keys.push((dir.clone(), repo_key, dir_exists));
         }
         
-        // 检查重复
+        // 检查重复（全局索引和项目清单）
+        let manifest = ProjectManifest::load(&current_dir)?;
         for (_, repo_key, _) in &repo_keys {
-            if self.index_manager.project_exists(repo_key) {
+            let in_manifest = manifest.as_ref().is_some_and(|m| m.contains(repo_key));
+            if in_manifest || self.index_manager.project_exists(repo_key) {
                 return Err(RepositoryError::ProjectAlreadyExists(repo_key.clone()));
             }
         }
         
+        // 隐藏目录不能互相包含（如 `.config` 和 `.config/app`），隐藏文件不能位于隐藏目录中
+        let registered = self.find_registrations(&current_dir, &base_key)?;
+        check_overlaps(&directories, &registered)?;
+        let all_directories: Vec<String> = registered.iter()
+            .map(|p| p.hidden_directory.clone())
+            .chain(directories.iter().cloned())
+            .collect();
+        for file in &files {
+            if let Some(directory) = all_directories.iter().find(|d| directories_overlap(file, d)) {
+                return Err(RepositoryError::InvalidHiddenDirectory {
+                    path: file.clone(),
+                    reason: format!("inside hidden directory {}", directory),
+                });
+            }
+        }
+        
         if skip_hidden {
             // 跳过隐藏仓库操作
             return Ok(());
         }
         
+        // 在创建任何仓库之前准备好模板内容
+        let template = match (&options.template, template_source) {
+            (Some(name), Some(source)) => Some(Template::fetch(name, source)?),
+            _ => None,
+        };
+        
         if no_atomic {
             // 非原子操作
             for (dir, repo_key, _) in repo_keys {
-                self.create_hidden_repository(&current_dir, &dir, &repo_key).await?;
+                if options.adopt {
+                    self.adopt_hidden_repository(&current_dir, &dir, &repo_key, &org, use_manifest).await?;
+                } else {
+                    self.create_hidden_repository(&current_dir, &dir, &repo_key, &org, use_manifest, shared).await?;
+                }
+                if let Some(template) = template.as_ref().filter(|_| dir != STORE_DIRECTORY) {
+                    apply_template(template, &current_dir, &dir)?;
+                }
             }
+            self.hide_files(&current_dir, &files)?;
         } else {
             // 原子操作
-            // 记录：(目录名, repo_key, 目录原本是否存在)
-            let mut created_repos: Vec<(String, String, bool)> = Vec::new();
+            // 记录：(目录名, Repository Key, 远程仓库名, 目录原本是否存在)
+            let mut created_repos: Vec<(String, String, String, bool)> = Vec::new();
             let mut rollback_needed = false;
             
             for (dir, repo_key, dir_existed) in repo_keys {
-                match self.create_hidden_repository(&current_dir, &dir, &repo_key).await {
-                    Ok(_) => created_repos.push((dir, repo_key, dir_existed)),
+                let result = if options.adopt {
+                    self.adopt_hidden_repository(&current_dir, &dir, &repo_key, &org, use_manifest).await
+                } else {
+                    self.create_hidden_repository(&current_dir, &dir, &repo_key, &org, use_manifest, shared).await
+                };
+                match result {
+                    Ok(repo_name) => {
+                        created_repos.push((dir.clone(), repo_key, repo_name, dir_existed));
+                        // 模板内容在仓库创建后复制（隐藏文件仓库除外），失败时与创建失败一样回滚
+                        if let Some(template) = template.as_ref().filter(|_| dir != STORE_DIRECTORY) {
+                            if let Err(e) = apply_template(template, &current_dir, &dir) {
+                                rollback_needed = true;
+                                eprintln!("Failed to apply template to {}: {}", dir, e);
+                                break;
+                            }
+                        }
+                    }
                     Err(e) => {
                         rollback_needed = true;
                         eprintln!("Failed to create hidden repository for {}: {}", dir, e);
@@ -79,12 +236,29 @@ impl RepositoryManager {
                 }
             }
             
+            // 隐藏文件在仓库创建之后移入 `.dot-files`，失败时文件先移回原位置，再回滚仓库
+            if !rollback_needed {
+                if let Err(e) = self.hide_files(&current_dir, &files) {
+                    rollback_needed = true;
+                    eprintln!("Failed to hide files: {}", e);
+                }
+            }
+            
             if rollback_needed {
                 // 回滚已创建的仓库
-                for (dir, repo_key, dir_existed) in created_repos {
+                for (dir, repo_key, repo_name, dir_existed) in created_repos {
+                    // 接管的仓库和之前就存在的共享仓库只移除注册信息
+                    let existing_shared = shared.is_some()
+                        && !self.created_remotes.contains_key(&(org.clone(), repo_name.clone()));
+                    if options.adopt || existing_shared {
+                        if let Err(e) = self.unregister_hidden_repository(&current_dir, &repo_key).await {
+                            eprintln!("Failed to rollback {}: {}", dir, e);
+                        }
+                        continue;
+                    }
                     // 只有当目录是我们新创建的才删除
                     let dir_was_created = !dir_existed;
-                    if let Err(e) = self.rollback_hidden_repository(&current_dir, &dir, &repo_key, dir_was_created).await {
+                    if let Err(e) = self.rollback_hidden_repository(&current_dir, &dir, &repo_key, &repo_name, &org, dir_was_created).await {
                         eprintln!("Failed to rollback {}: {}", dir, e);
                     }
                 }
@@ -92,6 +266,7 @@ impl RepositoryManager {
             }
         }
         
+        self.record_checkout(&current_dir);
         Ok(())
     }
     
@@ -100,9 +275,17 @@ impl RepositoryManager {
         
         // 检查是否初始化了 dot
         if !self.is_dot_initialized(&current_dir).await? {
+            if let Some(old_url) = self.detect_moved_project(&current_dir) {
+                return Ok(format!(
+                    "This project has hidden repositories registered for {}. If it was renamed or transferred, run 'dot relink --from {}'.",
+                    old_url, old_url
+                ));
+            }
             return Ok("This directory is not initialized with dot. Run 'dot init <directory>' to initialize.".to_string());
         }
         
+        self.record_checkout(&current_dir);
+        
         let mut status_output = Vec::new();
         
         // 显示父仓库状态
@@ -114,8 +297,18 @@ impl RepositoryManager {
             // 显示隐藏仓库状态
             let hidden_repos = self.get_hidden_repositories(&current_dir).await?;
             
+            let remote_url = GitOperations::get_remote_origin(&current_dir)?;
+            let base_key = GitOperations::generate_base_key(&remote_url)?;
+            let shared: HashMap<String, String> = self.find_registrations(&current_dir, &base_key)?
+                .into_iter()
+                .filter_map(|p| Some((p.hidden_directory, p.shared?)))
+                .collect();
+            
             for (dir_name, repo_path) in hidden_repos {
-                status_output.push(format!("=== Hidden Repository: {} ===", dir_name));
+                match shared.get(&dir_name) {
+                    Some(name) => status_output.push(format!("=== Hidden Repository: {} (shared: {}) ===", dir_name, name)),
+                    None => status_output.push(format!("=== Hidden Repository: {} ===", dir_name)),
+                }
                 if repo_path.exists() {
                     let hidden_status = GitOperations::get_status(&repo_path)?;
                     status_output.push(hidden_status);
@@ -123,6 +316,17 @@ impl RepositoryManager {
                     status_output.push("Repository not found locally".to_string());
                 }
             }
+            
+            // 显示隐藏文件在工作区中的状态
+            let mode = self.config.hidden_file_mode();
+            let files = hidden_files::tracked_files(&current_dir)?;
+            if !files.is_empty() {
+                status_output.push("=== Hidden Files ===".to_string());
+                for file in files {
+                    let state = hidden_files::state(&current_dir, &file, mode)?;
+                    status_output.push(format!("{:<9} {}", state, file));
+                }
+            }
         }
         
         Ok(status_output.join("\n"))
@@ -137,18 +341,26 @@ impl RepositoryManager {
         let current_dir = env::current_dir()?;
         let mut operations = AtomicOperations::new(no_atomic);
         
+        let hidden_repos = self.get_hidden_repositories(&current_dir).await?;
+        let hidden_directories: Vec<String> = hidden_repos.iter().map(|(dir, _)| dir.clone()).collect();
+        let files = self.route_hidden_files(&current_dir, files, skip_hidden)?;
+        let (mut hidden_files, parent_files) = route_files(&files, &hidden_directories);
+        
         // 添加到隐藏仓库
         if !skip_hidden {
-            let hidden_repos = self.get_hidden_repositories(&current_dir).await?;
-            for (_, repo_path) in hidden_repos {
-                if repo_path.exists() {
-                    operations.add_operation(Box::new(AddOperation::new(repo_path, files.clone())));
+            for (dir, repo_path) in hidden_repos {
+                if let Some(files) = hidden_files.remove(&dir) {
+                    if repo_path.exists() {
+                        operations.add_operation(Box::new(AddOperation::new(repo_path, files)));
+                    }
                 }
             }
         }
         
-        // 添加到父仓库
-        operations.add_operation(Box::new(AddOperation::new(current_dir, files)));
+        // 添加到父仓库（隐藏目录中的文件不会加入父仓库）
+        if !parent_files.is_empty() {
+            operations.add_operation(Box::new(AddOperation::new(current_dir, parent_files)));
+        }
         
         operations.execute().await.map_err(RepositoryError::from)
     }
@@ -189,11 +401,14 @@ impl RepositoryManager {
         
         // 先推送隐藏仓库
         if !skip_hidden {
-            let hidden_repos = self.get_hidden_repositories(&current_dir).await?;
-            for (dir_name, repo_path) in hidden_repos {
+            let remote_url = GitOperations::get_remote_origin(&current_dir)?;
+            let base_key = GitOperations::generate_base_key(&remote_url)?;
+            for project in self.find_registrations(&current_dir, &base_key)? {
+                let repo_path = current_dir.join(&project.hidden_directory);
                 if repo_path.exists() {
-                    operations.add_operation(Box::new(PushOperation::new(repo_path.clone())));
-                    results.push(format!("Hidden repository '{}': pushed", dir_name));
+                    let credentials = self.hidden_repo_credentials(&project);
+                    operations.add_operation(Box::new(PushOperation::new(repo_path).with_credentials(credentials)));
+                    results.push(format!("Hidden repository '{}': pushed", project.hidden_directory));
                 }
             }
         }
@@ -216,7 +431,7 @@ impl RepositoryManager {
         let dir_name = target_dir.unwrap_or_else(|| {
             repository_url
                 .split('/')
-                .last()
+                .next_back()
                 .unwrap_or("repo")
                 .strip_suffix(".git")
                 .unwrap_or("repo")
@@ -230,6 +445,7 @@ impl RepositoryManager {
         
         // 生成 base key 并查找关联的隐藏仓库
         let base_key = GitOperations::generate_base_key(&repository_url)?;
+        self.record_checkout(&target_path);
         let associated_projects = self.index_manager.find_projects_by_base_key(&base_key);
         
         if associated_projects.is_empty() {
@@ -240,194 +456,2065 @@ impl RepositoryManager {
         // 克隆所有关联的隐藏仓库
         for project in associated_projects {
             let hidden_dir = target_path.join(&project.hidden_directory);
-            let hidden_repo_url = self.generate_hidden_repo_url(&project.repository_name)?;
+            let hidden_repo_url = self.generate_hidden_repo_url(&project);
+            let credentials = self.hidden_repo_credentials(&project);
             
-            match GitOperations::clone_repository(&hidden_repo_url, &hidden_dir) {
+            match GitOperations::clone_repository_with_credentials(&hidden_repo_url, &hidden_dir, credentials.as_ref()) {
                 Ok(_) => println!("Cloned hidden repository: {}", project.hidden_directory),
                 Err(e) => eprintln!("Failed to clone hidden repository {}: {}", project.hidden_directory, e),
             }
         }
         
+        self.materialize_hidden_files(&target_path);
         Ok(())
     }
     
-    // 私有辅助方法
-    
-    async fn ensure_git_initialized(&self, path: &Path) -> Result<(), RepositoryError> {
-        if !GitOperations::is_git_initialized(path) {
-            GitOperations::init_repository(path)?;
-            println!("Initialized git repository in {}", path.display());
+    /// 列出项目在本机状态文件中已知的本地检出
+    ///
+    /// `project` 可以是仓库 URL、完整的 base key，或结尾的路径片段（如 `user/repo`）。
+    pub fn where_project(&self, project: &str) -> Result<String, RepositoryError> {
+        let mut state = StateManager::load()?;
+        state.seed_legacy_checkouts(self.index_manager.projects())?;
+        let query = if project.contains('@') || project.contains("://") {
+            GitOperations::generate_base_key(project)?
+        } else {
+            project.to_string()
+        };
+        
+        let checkouts = state.find(&query);
+        if checkouts.is_empty() {
+            return Ok(format!("No known checkouts of '{}'", project));
         }
-        Ok(())
-    }
-    
-    fn get_remote_origin(&self, path: &Path) -> Result<String, RepositoryError> {
-        GitOperations::get_remote_origin(path)
-    }
-    
-    async fn create_hidden_repository(
-        &mut self,
-        project_path: &Path,
-        directory: &str,
-        repository_key: &str
-    ) -> Result<(), RepositoryError> {
-        let hidden_dir = project_path.join(directory);
         
-        // 检查目录是否已存在
-        let dir_existed = hidden_dir.exists();
+        let current_host = StateManager::hostname();
+        let mut lines = Vec::new();
+        let mut last_key = "";
+        for (base_key, checkout) in checkouts {
+            if base_key != last_key {
+                lines.push(format!("=== {} ===", base_key));
+                last_key = base_key;
+            }
+            let missing = checkout.hostname == current_host && !checkout.path.exists();
+            lines.push(format!(
+                "{}:{}  (last seen {}){}",
+                checkout.hostname,
+                checkout.path.display(),
+                checkout.last_seen.format("%Y-%m-%d %H:%M"),
+                if missing { "  [missing]" } else { "" }
+            ));
+        }
         
-        // 创建隐藏目录（如果不存在）
-        if !dir_existed {
-            std::fs::create_dir_all(&hidden_dir)?;
+        Ok(lines.join("\n"))
+    }
+    
+    /// 查找项目的所有隐藏仓库
+    ///
+    /// `project` 可以是 `.`（当前目录）、仓库 URL、完整的 base key，或结尾的路径片段（如 `user/repo`）。
+    fn resolve_project(&self, project: &str) -> Result<(String, Vec<ProjectRegistration>), RepositoryError> {
+        if project == "." {
+            let current_dir = env::current_dir()?;
+            let base_key = GitOperations::generate_base_key(&GitOperations::get_remote_origin(&current_dir)?)?;
+            let registrations = self.find_registrations(&current_dir, &base_key)?;
+            return Ok((base_key, registrations));
         }
         
-        // 生成 MD5 仓库名
-        let repo_name = format!("{:x}", md5::compute(repository_key.as_bytes()));
+        let query = if project.contains('@') || project.contains("://") {
+            GitOperations::generate_base_key(project)?
+        } else {
+            project.trim_end_matches('/').to_string()
+        };
+        let matches = |base_key: &str| {
+            base_key == query
+                || base_key.ends_with(&format!("/{}", query))
+                || base_key.ends_with(&format!(":{}", query))
+        };
         
-        // 获取组织名
-        let org = self.index_manager.get_organization().to_string();
+        let mut registrations: Vec<ProjectRegistration> = self.index_manager
+            .projects()
+            .filter(|p| matches(p.base_key()))
+            .collect();
+        registrations.sort_by(|a, b| a.repository_key.cmp(&b.repository_key));
         
-        // 使用 GitHub API 或 gh CLI 创建远程仓库（必须成功）
-        println!("Creating remote repository: {}/{}", org, repo_name);
-        let description = format!("Hidden repository for {}", repository_key);
+        let mut base_keys: Vec<&str> = registrations.iter().map(|p| p.base_key()).collect();
+        base_keys.sort();
+        base_keys.dedup();
+        match base_keys.as_slice() {
+            [] => Err(RepositoryError::IoError(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No hidden repositories registered for '{}'", project),
+            ))),
+            [base_key] => Ok((base_key.to_string(), registrations.clone())),
+            _ => Err(RepositoryError::IoError(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("'{}' matches several projects: {}", project, base_keys.join(", ")),
+            ))),
+        }
+    }
+    
+    /// 为项目的每个隐藏仓库生成并注册部署密钥，返回 SSH 配置片段
+    ///
+    /// 任一仓库注册失败时删除本次已注册的密钥。
+    pub async fn add_deploy_keys(
+        &self,
+        project: &str,
+        name: Option<String>,
+        write: bool,
+    ) -> Result<String, RepositoryError> {
+        let name = name.unwrap_or_else(StateManager::hostname);
+        deploy_key::validate_name(&name)?;
+        let (base_key, registrations) = self.resolve_project(project)?;
+        let key_dir = deploy_key::key_dir(&name)?;
+        let title = deploy_key::key_title(&name);
         
-        let remote_url = match self.github_client.create_repository(&org, &repo_name, &description).await {
-            Ok(url) => {
-                println!("  ✓ Remote repository created successfully");
-                url
+        let mut added: Vec<(String, String, String, String)> = Vec::new();
+        let mut entries = Vec::new();
+        for registration in &registrations {
+            if registration.is_adopted() {
+                println!("  - {}: adopted repository, skipped", registration.hidden_directory);
+                continue;
             }
-            Err(e) => {
-                // 远程仓库创建失败
-                // 只有当目录是我们新创建的才删除，已存在的目录不能删！
-                if !dir_existed && hidden_dir.exists() {
-                    let _ = std::fs::remove_dir_all(&hidden_dir);
+            let org = self.project_organization(registration).to_string();
+            let owner = self.remote_owner(registration).to_string();
+            let repo_name = registration.remote_name();
+            let forge = self.forge(&org);
+            
+            let result = async {
+                let host = forge.ssh_host().ok_or_else(|| forge::unsupported(forge.name(), "Deploy keys"))?;
+                let key_path = key_dir.join(&repo_name);
+                let public_key = deploy_key::ensure_key_pair(&key_path, &title)?;
+                
+                // 重复执行时不重复注册
+                let existing = forge.list_deploy_keys(&owner, &repo_name).await?;
+                if !existing.iter().any(|key| deploy_key::same_key(&key.key, &public_key)) {
+                    let key = forge.add_deploy_key(&owner, &repo_name, &title, &public_key, !write).await?;
+                    added.push((org.clone(), owner.clone(), repo_name.clone(), key.id));
                 }
-                return Err(e);
-            }
-        };
-        
-        // 检查是否已经是 git 仓库
-        let is_git_repo = hidden_dir.join(".git").exists();
-        
-        if !is_git_repo {
-            // 初始化本地 git 仓库
-            GitOperations::init_repository(&hidden_dir)?;
+                println!("  ✓ {} ({}/{})", registration.hidden_directory, owner, repo_name);
+                Ok::<_, RepositoryError>(deploy_key::SshConfigEntry {
+                    hidden_directory: registration.hidden_directory.clone(),
+                    host,
+                    owner: owner.clone(),
+                    repo_name: repo_name.clone(),
+                    identity_file: key_path,
+                })
+            }.await;
             
-            // 设置远程 origin
-            let repo = git2::Repository::open(&hidden_dir)?;
-            repo.remote("origin", &remote_url)?;
-        } else {
-            // 已经是 git 仓库，检查是否需要更新 remote
-            let repo = git2::Repository::open(&hidden_dir)?;
-            if repo.find_remote("origin").is_err() {
-                repo.remote("origin", &remote_url)?;
+            match result {
+                Ok(entry) => entries.push(entry),
+                Err(e) => {
+                    eprintln!("  ✗ {} ({}/{}): {}", registration.hidden_directory, owner, repo_name, e);
+                    for (org, owner, repo_name, id) in added {
+                        if let Err(e) = self.forge(&org).delete_deploy_key(&owner, &repo_name, &id).await {
+                            eprintln!("Warning: Failed to remove deploy key from {}/{}: {}", owner, repo_name, e);
+                        }
+                    }
+                    return Err(e);
+                }
             }
         }
         
-        // 注册到索引
-        let registration = ProjectRegistration {
-            repository_key: repository_key.to_string(),
-            repository_name: repo_name.clone(),
-            git_user: GitOperations::get_git_user(project_path)?,
-            project_git_path: self.get_remote_origin(project_path)?,
-            project_disk_path: project_path.to_string_lossy().to_string(),
-            hidden_directory: directory.to_string(),
-            created_at: chrono::Utc::now(),
-        };
-        
-        self.index_manager.register_project(registration).await?;
-        
-        println!("✓ Created hidden repository: {}", directory);
-        println!("  - Remote: {}", remote_url);
-        Ok(())
+        Ok(deploy_key::ssh_config_snippet(&base_key, &entries))
     }
     
-    async fn rollback_hidden_repository(
-        &self,
-        project_path: &Path,
-        directory: &str,
-        repository_key: &str,
-        dir_was_created: bool,  // 新增参数：目录是否是我们创建的
-    ) -> Result<(), RepositoryError> {
-        // 只有当目录是我们新创建的才删除
-        if dir_was_created {
-            let hidden_dir = project_path.join(directory);
-            if hidden_dir.exists() {
-                std::fs::remove_dir_all(&hidden_dir)?;
+    /// 列出项目隐藏仓库上由 dot 添加的部署密钥
+    pub async fn list_deploy_keys(&self, project: &str) -> Result<String, RepositoryError> {
+        let (base_key, registrations) = self.resolve_project(project)?;
+        let mut lines = vec![format!("=== {} ===", base_key)];
+        for registration in &registrations {
+            if registration.is_adopted() {
+                lines.push(format!("{} (adopted repository, not managed by dot)", registration.hidden_directory));
+                continue;
+            }
+            let owner = self.remote_owner(registration);
+            let repo_name = registration.remote_name();
+            let keys = self.forge(self.project_organization(registration)).list_deploy_keys(owner, &repo_name).await?;
+            lines.push(format!("{} ({}/{})", registration.hidden_directory, owner, repo_name));
+            let managed: Vec<_> = keys.iter().filter(|k| k.title.starts_with(deploy_key::TITLE_PREFIX)).collect();
+            if managed.is_empty() {
+                lines.push("  (no deploy keys)".to_string());
+            }
+            for key in managed {
+                let access = if key.read_only { "read-only" } else { "read-write" };
+                lines.push(format!("  {}  {}  (id {})", key.title, access, key.id));
             }
         }
+        Ok(lines.join("\n"))
+    }
+    
+    /// 删除项目隐藏仓库上名为 `name` 的部署密钥及本地密钥文件
+    pub async fn revoke_deploy_keys(&self, project: &str, name: Option<String>) -> Result<String, RepositoryError> {
+        let name = name.unwrap_or_else(StateManager::hostname);
+        deploy_key::validate_name(&name)?;
+        let (_, registrations) = self.resolve_project(project)?;
+        let key_dir = deploy_key::key_dir(&name)?;
+        let title = deploy_key::key_title(&name);
         
-        // 生成 MD5 仓库名
-        let repo_name = format!("{:x}", md5::compute(repository_key.as_bytes()));
-        let org = self.index_manager.get_organization();
-        
-        // 尝试删除远程仓库
-        println!("Rolling back: deleting remote repository {}/{}", org, repo_name);
-        if let Err(e) = self.github_client.delete_repository(org, &repo_name).await {
-            eprintln!("Warning: Failed to delete remote repository: {}", e);
+        let mut revoked = 0;
+        for registration in registrations.iter().filter(|p| !p.is_adopted()) {
+            let owner = self.remote_owner(registration);
+            let repo_name = registration.remote_name();
+            let forge = self.forge(self.project_organization(registration));
+            for key in forge.list_deploy_keys(owner, &repo_name).await? {
+                if key.title == title {
+                    forge.delete_deploy_key(owner, &repo_name, &key.id).await?;
+                    revoked += 1;
+                }
+            }
+            deploy_key::remove_key_pair(&key_dir.join(&repo_name))?;
         }
-        
-        Ok(())
+        Ok(format!("Revoked {} deploy key(s) named '{}'", revoked, name))
     }
     
-    #[allow(dead_code)]
-    async fn create_remote_hidden_repository(&self, _repository_key: &str) -> Result<String, RepositoryError> {
-        // 这个方法不再使用，保留以兼容
-        unreachable!("This method is deprecated")
+    /// 解除隐藏目录的 dot 管理（`init_project` 的逆操作）
+    ///
+    /// 默认删除本地目录并按 `remote_cleanup` 移除远程仓库。注销、本地目录和远程仓库的
+    /// 处理作为一组原子操作执行：本地目录先移到 `.git/dot/deinit/` 下，全部成功后才删除，
+    /// 远程仓库最后处理，任一步失败时注销和本地更改都会回滚。
+    pub async fn deinit_project(
+        &mut self,
+        directory: &str,
+        no_atomic: bool,
+        options: DeinitOptions
+    ) -> Result<(), RepositoryError> {
+        let current_dir = env::current_dir()?;
+        self.deinit_hidden_repository(&current_dir, directory, no_atomic, options).await
     }
     
-    fn generate_hidden_repo_url(&self, repository_name: &str) -> Result<String, RepositoryError> {
-        let org = self.index_manager.get_organization();
-        Ok(format!("git@github.com:{}/{}.git", org, repository_name))
+    /// 重命名隐藏目录（`dot mv`）
+    ///
+    /// 移动本地目录、以新的 Repository Key 重新注册、在平台上为远程仓库改名以保留历史，
+    /// 并更新隐藏仓库的 origin 和父仓库的忽略规则；这些步骤作为一组原子操作执行。
+    pub async fn move_hidden_directory(&mut self, from: &str, to: &str, no_atomic: bool) -> Result<(), RepositoryError> {
+        let current_dir = env::current_dir()?;
+        self.rename_hidden_repository(&current_dir, from, to, no_atomic).await
     }
     
-    async fn is_dot_initialized(&self, path: &Path) -> Result<bool, RepositoryError> {
-        if !GitOperations::is_git_initialized(path) {
-            return Ok(false);
+    /// 父仓库改名或转移后，把旧 URL 下的隐藏仓库注册信息改到当前的 origin（`dot relink`）
+    ///
+    /// 重写匹配的 Repository Key 和 `project_git_path`；`rename_remotes` 时按当前命名策略
+    /// 为远程仓库改名并更新隐藏仓库的 origin。这些步骤作为一组原子操作执行。
+    pub async fn relink_project(&mut self, from: &str, rename_remotes: bool, no_atomic: bool) -> Result<(), RepositoryError> {
+        let current_dir = env::current_dir()?;
+        self.relink_hidden_repositories(&current_dir, from, rename_remotes, no_atomic).await?;
+        self.record_checkout(&current_dir);
+        Ok(())
+    }
+    
+    /// 把组织中的共享隐藏仓库链接到当前项目的目录（`dot link`）
+    ///
+    /// 索引中还没有该共享仓库时，在组织（默认为默认组织）的平台上查找同名仓库。
+    /// 目录不存在时克隆共享仓库；已经是指向该仓库的检出时原样登记。
+    pub async fn link_shared_repository(
+        &mut self,
+        name: &str,
+        directory: &str,
+        organization: Option<String>
+    ) -> Result<(), RepositoryError> {
+        let current_dir = env::current_dir()?;
+        self.link_hidden_repository(&current_dir, name, directory, organization).await?;
+        self.record_checkout(&current_dir);
+        Ok(())
+    }
+    
+    /// 在每个隐藏仓库（以及可选的父仓库）中运行 shell 命令（`dot foreach`）
+    ///
+    /// 本地没有检出的隐藏仓库被跳过；有命令失败时返回汇总了退出状态的错误。
+    pub async fn foreach(
+        &self,
+        command: &str,
+        include_parent: bool,
+        skip_hidden: bool,
+        options: ForeachOptions
+    ) -> Result<(), RepositoryError> {
+        let current_dir = env::current_dir()?;
+        let targets = self.foreach_targets(&current_dir, include_parent, skip_hidden)?;
+        if targets.is_empty() {
+            println!("No repositories to run in");
+            return Ok(());
         }
         
-        let remote_url = match GitOperations::get_remote_origin(path) {
-            Ok(url) => url,
-            Err(_) => return Ok(false),
-        };
+        let total = targets.len();
+        let outcomes = foreach::run(targets, &Invocation::Shell(command.to_string()), options).await;
+        foreach::summarize(&outcomes, total)
+    }
+    
+    /// 在父仓库和每个隐藏仓库中运行 git（`dot git`），输出按仓库分组并加上 `[目录]` 前缀
+    ///
+    /// `only` 非空时只在列出的目录中运行（`.` 为父仓库）。所有仓库都会运行，
+    /// 有失败时返回汇总了退出状态的错误。
+    pub async fn git_passthrough(
+        &self,
+        args: Vec<String>,
+        only: &[String],
+        skip_hidden: bool
+    ) -> Result<(), RepositoryError> {
+        let current_dir = env::current_dir()?;
+        let targets = self.foreach_targets(&current_dir, true, skip_hidden)?;
+        let targets = foreach::select(targets, only)?;
         
-        let base_key = GitOperations::generate_base_key(&remote_url)?;
-        let projects = self.index_manager.find_projects_by_base_key(&base_key);
+        let total = targets.len();
+        let options = ForeachOptions { parallel: 1, continue_on_error: true, prefix_output: true };
+        let outcomes = foreach::run(targets, &Invocation::Git(args), options).await;
+        foreach::summarize(&outcomes, total)
+    }
+    
+    /// 导出当前项目的清单（没有本地清单时从全局索引生成）
+    pub async fn export_manifest(&self) -> Result<ProjectManifest, RepositoryError> {
+        let current_dir = env::current_dir()?;
         
-        Ok(!projects.is_empty())
+        if let Some(manifest) = ProjectManifest::load(&current_dir)? {
+            return Ok(manifest);
+        }
+        
+        let remote_url = GitOperations::get_remote_origin(&current_dir)?;
+        let base_key = GitOperations::generate_base_key(&remote_url)?;
+        let mut manifest = ProjectManifest::default();
+        for project in self.index_manager.find_projects_by_base_key(&base_key) {
+            manifest.register(project)?;
+        }
+        Ok(manifest)
     }
     
-    async fn get_hidden_repositories(&self, path: &Path) -> Result<Vec<(String, PathBuf)>, RepositoryError> {
-        let remote_url = GitOperations::get_remote_origin(path)?;
+    /// 导入清单到当前项目，并克隆本地缺失的隐藏仓库
+    pub async fn import_manifest(&self, manifest_path: &Path) -> Result<(), RepositoryError> {
+        let current_dir = env::current_dir()?;
+        let remote_url = GitOperations::get_remote_origin(&current_dir)?;
         let base_key = GitOperations::generate_base_key(&remote_url)?;
-        let projects = self.index_manager.find_projects_by_base_key(&base_key);
         
-        let mut hidden_repos = Vec::new();
-        for project in projects {
-            let repo_path = path.join(&project.hidden_directory);
-            hidden_repos.push((project.hidden_directory.clone(), repo_path));
+        let imported = ProjectManifest::read_from(manifest_path)?;
+        let mut manifest = ProjectManifest::load(&current_dir)?.unwrap_or_default();
+        
+        let mut imported_count = 0;
+        for (repository_key, registration) in imported.projects {
+            if registration.base_key() != base_key {
+                eprintln!("Skipping {}: it does not belong to this project", repository_key);
+                continue;
+            }
+            if manifest.contains(&repository_key) {
+                println!("Already registered: {}", registration.hidden_directory);
+                continue;
+            }
+            manifest.register(registration)?;
+            imported_count += 1;
         }
+        manifest.save(&current_dir)?;
+        println!("Imported {} hidden repositories into {}", imported_count,
+            ProjectManifest::path(&current_dir)?.display());
         
-        Ok(hidden_repos)
+        // 克隆本地缺失的隐藏仓库
+        for project in manifest.find_projects_by_base_key(&base_key) {
+            let hidden_dir = current_dir.join(&project.hidden_directory);
+            if hidden_dir.exists() {
+                continue;
+            }
+            let hidden_repo_url = self.generate_hidden_repo_url(project);
+            let credentials = self.hidden_repo_credentials(project);
+            match GitOperations::clone_repository_with_credentials(&hidden_repo_url, &hidden_dir, credentials.as_ref()) {
+                Ok(_) => println!("Cloned hidden repository: {}", project.hidden_directory),
+                Err(e) => eprintln!("Failed to clone hidden repository {}: {}", project.hidden_directory, e),
+            }
+        }
+        
+        self.materialize_hidden_files(&current_dir);
+        Ok(())
     }
-}
-
-// 实现 From trait 用于错误转换
-impl From<crate::error::OperationError> for RepositoryError {
-    fn from(err: crate::error::OperationError) -> Self {
-        RepositoryError::IoError(std::io::Error::new(std::io::ErrorKind::Other, err))
+    
+    // 私有辅助方法
+    
+    /// 将隐藏文件改写为 `.dot-files` 中的路径，复制模式下先把修改过的副本同步回去
+    ///
+    /// `.` 同步所有隐藏文件；跳过隐藏仓库时隐藏文件也被跳过。
+    fn route_hidden_files(&self, project_path: &Path, files: Vec<String>, skip_hidden: bool) -> Result<Vec<String>, RepositoryError> {
+        let hidden = hidden_files::tracked_files(project_path)?;
+        if hidden.is_empty() {
+            return Ok(files);
+        }
+        let copy = self.config.hidden_file_mode() == crate::config::HiddenFileMode::Copy;
+        
+        let mut routed = Vec::new();
+        for file in files {
+            if file == "." {
+                if copy && !skip_hidden {
+                    for hidden_file in &hidden {
+                        hidden_files::sync_copy(project_path, hidden_file)?;
+                    }
+                }
+                routed.push(file);
+                continue;
+            }
+            
+            match GitOperations::normalize_hidden_path(&file) {
+                Ok(path) if hidden.contains(&path) => {
+                    if skip_hidden {
+                        continue;
+                    }
+                    if copy {
+                        hidden_files::sync_copy(project_path, &path)?;
+                    }
+                    routed.push(format!("{}/{}", STORE_DIRECTORY, path));
+                }
+                _ => routed.push(file),
+            }
+        }
+        Ok(routed)
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::TempDir;
     
-    #[test]
-    fn test_repository_manager_creation() {
-        // 这个测试需要实际的配置和索引管理器
-        // 在实际测试中，我们会使用模拟对象
-        assert!(true); // 占位符测试
+    /// 把文件移入 `.dot-files` 隐藏，任何一个文件失败时撤销已经隐藏的文件
+    fn hide_files(&self, project_path: &Path, files: &[String]) -> Result<(), RepositoryError> {
+        if files.is_empty() {
+            return Ok(());
+        }
+        
+        let store_excluded = hidden_files::exclude(project_path, STORE_DIRECTORY)?;
+        let mode = self.config.hidden_file_mode();
+        let mut hidden = Vec::new();
+        for file in files {
+            match hidden_files::hide(project_path, file, mode) {
+                Ok(hidden_file) => {
+                    println!("✓ Hidden file: {} (stored in {})", file, STORE_DIRECTORY);
+                    hidden.push(hidden_file);
+                }
+                Err(e) => {
+                    for hidden_file in hidden.iter().rev() {
+                        if let Err(undo_error) = hidden_file.undo(project_path) {
+                            eprintln!("Warning: Failed to restore {}: {}", hidden_file.file, undo_error);
+                        }
+                    }
+                    if store_excluded {
+                        let _ = hidden_files::remove_exclude(project_path, STORE_DIRECTORY);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
     }
-}
\ No newline at end of file
+    
+    /// 在工作区中呈现 `.dot-files` 中的隐藏文件（失败只给出警告）
+    fn materialize_hidden_files(&self, project_path: &Path) {
+        let files = match hidden_files::tracked_files(project_path) {
+            Ok(files) => files,
+            Err(e) => {
+                eprintln!("Warning: Failed to read hidden files: {}", e);
+                return;
+            }
+        };
+        if files.is_empty() {
+            return;
+        }
+        
+        if let Err(e) = hidden_files::exclude(project_path, STORE_DIRECTORY) {
+            eprintln!("Warning: Failed to update .git/info/exclude: {}", e);
+        }
+        let mode = self.config.hidden_file_mode();
+        for file in files {
+            let result = hidden_files::exclude(project_path, &file)
+                .and_then(|_| hidden_files::materialize(project_path, &file, mode));
+            match result {
+                Ok(()) => println!("Restored hidden file: {}", file),
+                Err(e) => eprintln!("Failed to restore hidden file {}: {}", file, e),
+            }
+        }
+    }
+    
+    /// 在本地状态文件中记录项目的检出位置（失败不影响主操作）
+    fn record_checkout(&self, path: &Path) {
+        let base_key = match GitOperations::get_remote_origin(path)
+            .and_then(|url| GitOperations::generate_base_key(&url))
+        {
+            Ok(base_key) => base_key,
+            Err(_) => return,
+        };
+        
+        let result = StateManager::load()
+            .and_then(|mut state| state.record_checkout(&base_key, path));
+        if let Err(e) = result {
+            eprintln!("Warning: Failed to record checkout location: {}", e);
+        }
+    }
+    
+    async fn ensure_git_initialized(&self, path: &Path) -> Result<(), RepositoryError> {
+        if !GitOperations::is_git_initialized(path) {
+            GitOperations::init_repository(path)?;
+            println!("Initialized git repository in {}", path.display());
+        }
+        Ok(())
+    }
+    
+    fn get_remote_origin(&self, path: &Path) -> Result<String, RepositoryError> {
+        GitOperations::get_remote_origin(path)
+    }
+    
+    async fn deinit_hidden_repository(
+        &mut self,
+        project_path: &Path,
+        directory: &str,
+        no_atomic: bool,
+        options: DeinitOptions
+    ) -> Result<(), RepositoryError> {
+        let directory = &GitOperations::normalize_hidden_path(directory)?;
+        let (registration, in_manifest) = self.find_registration(project_path, directory)?;
+        let repository_key = registration.repository_key.clone();
+        
+        let hidden_dir = project_path.join(directory);
+        let hidden_git_dir = hidden_dir.join(".git");
+        let remove_local = !options.keep_local && !options.fold_into_parent && hidden_dir.exists();
+        
+        // 删除目录或其 .git 之前确认没有会丢失的内容
+        if (remove_local || options.fold_into_parent) && hidden_git_dir.exists() {
+            let dirty = remove_local && !GitOperations::is_clean(&hidden_dir)?;
+            if dirty || GitOperations::has_unpushed_commits(&hidden_dir)? {
+                return Err(RepositoryError::IoError(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("{} has uncommitted or unpushed changes; push them first or use --keep-local", directory),
+                )));
+            }
+        }
+        
+        let parent_git_dir = git2::Repository::open(project_path)?.path().to_path_buf();
+        let staging_path = parent_git_dir.join("dot").join("deinit").join(format!(
+            "{}-{}", directory.replace('/', "_"), chrono::Utc::now().format("%Y%m%d%H%M%S")
+        ));
+        let org = self.project_organization(&registration).to_string();
+        let owner = self.remote_owner(&registration).to_string();
+        // 接管的仓库不由 dot 管理，共享仓库还被其他项目使用，都保留远程仓库
+        let keep_remote = options.keep_remote || registration.is_adopted() || registration.is_shared();
+        let remote = (!keep_remote).then(|| (self.forge(&org), self.config.remote_cleanup()));
+        
+        let mut operations = AtomicOperations::new(no_atomic);
+        if options.fold_into_parent {
+            if hidden_git_dir.exists() {
+                operations.add_operation(Box::new(MovePathOperation::new(hidden_git_dir, staging_path.clone())));
+            }
+            for ignore_file in [project_path.join(".gitignore"), parent_git_dir.join("info").join("exclude")] {
+                operations.add_operation(Box::new(IgnoreRuleOperation::remove(ignore_file, directory.to_string())));
+            }
+        } else if remove_local {
+            operations.add_operation(Box::new(MovePathOperation::new(hidden_dir, staging_path.clone())));
+        }
+        
+        let store = if in_manifest {
+            RegistrationStore::Manifest(project_path.to_path_buf())
+        } else {
+            RegistrationStore::Index(&mut self.index_manager)
+        };
+        operations.add_operation(Box::new(UnregisterOperation::new(store, repository_key)));
+        
+        // 远程仓库的处理放在最后：删除无法回滚
+        if let Some((forge, mode)) = remote {
+            operations.add_operation(Box::new(DisposeRemoteOperation::new(forge, owner, registration.remote_name(), mode)));
+        }
+        
+        operations.execute().await?;
+        drop(operations);
+        
+        if staging_path.exists() {
+            if let Err(e) = std::fs::remove_dir_all(&staging_path) {
+                eprintln!("Warning: Failed to remove {}: {}", staging_path.display(), e);
+            }
+        }
+        
+        println!("✓ Detached hidden directory: {}", directory);
+        if options.fold_into_parent {
+            println!("  - Files are now tracked by the parent repository");
+        } else if options.keep_local {
+            println!("  - Local directory kept");
+        }
+        if keep_remote {
+            println!("  - Remote kept: {}", self.generate_hidden_repo_url(&registration));
+        }
+        Ok(())
+    }
+    
+    async fn rename_hidden_repository(
+        &mut self,
+        project_path: &Path,
+        from: &str,
+        to: &str,
+        no_atomic: bool
+    ) -> Result<(), RepositoryError> {
+        let from = &GitOperations::normalize_hidden_path(from)?;
+        let to = &GitOperations::normalize_hidden_path(to)?;
+        let (registration, in_manifest) = self.find_registration(project_path, from)?;
+        
+        let remote_url = self.get_remote_origin(project_path)?;
+        let new_key = GitOperations::generate_repository_key(&remote_url, Some(to))?;
+        let new_key_in_manifest = ProjectManifest::load(project_path)?.is_some_and(|m| m.contains(&new_key));
+        if new_key_in_manifest || self.index_manager.project_exists(&new_key) {
+            return Err(RepositoryError::ProjectAlreadyExists(new_key));
+        }
+        if directories_overlap(from, to) {
+            return Err(RepositoryError::InvalidHiddenDirectory {
+                path: to.to_string(),
+                reason: format!("cannot move {} inside itself", from),
+            });
+        }
+        let others: Vec<ProjectRegistration> = self.find_registrations(project_path, registration.base_key())?
+            .into_iter()
+            .filter(|p| p.repository_key != registration.repository_key)
+            .collect();
+        check_overlaps(&[to.to_string()], &others)?;
+        
+        let from_path = project_path.join(from);
+        let to_path = project_path.join(to);
+        if to_path.exists() {
+            return Err(RepositoryError::IoError(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} already exists", to),
+            )));
+        }
+        
+        let forge = self.forge(self.project_organization(&registration));
+        let owner = self.remote_owner(&registration).to_string();
+        let repo_name = registration.remote_name();
+        let new_name = if registration.is_adopted() || registration.is_shared() {
+            repo_name.clone()
+        } else {
+            crate::naming::repository_name(self.config.repository_naming(), &new_key, to)?
+        };
+        let new_url = match GitOperations::get_remote_origin(&from_path) {
+            Ok(url) => renamed_remote_url(&url, &repo_name, &new_name),
+            Err(_) => None,
+        }.unwrap_or_else(|| forge.clone_url(&owner, &new_name));
+        let new_registration = ProjectRegistration {
+            repository_key: new_key,
+            repository_name: new_name.clone(),
+            hidden_directory: to.to_string(),
+            ..registration.clone()
+        };
+        let parent_git_dir = git2::Repository::open(project_path)?.path().to_path_buf();
+        
+        let mut operations = AtomicOperations::new(no_atomic);
+        if from_path.exists() {
+            operations.add_operation(Box::new(MovePathOperation::new(from_path.clone(), to_path.clone())));
+        }
+        for ignore_file in [project_path.join(".gitignore"), parent_git_dir.join("info").join("exclude")] {
+            operations.add_operation(Box::new(IgnoreRuleOperation::rename(ignore_file, from.to_string(), to.to_string())));
+        }
+        
+        let store = if in_manifest {
+            RegistrationStore::Manifest(project_path.to_path_buf())
+        } else {
+            RegistrationStore::Index(&mut self.index_manager)
+        };
+        operations.add_operation(Box::new(ReplaceRegistrationOperation::new(
+            store,
+            vec![(registration.repository_key.clone(), new_registration)],
+        )));
+        
+        // 远程仓库最后改名，之前的步骤都可以回滚
+        let rename_remote = new_name != repo_name;
+        if rename_remote {
+            if from_path.join(".git").exists() {
+                operations.add_operation(Box::new(SetOriginOperation::new(to_path, new_url.clone())));
+            }
+            println!("Renaming remote repository on {}: {}/{} -> {}/{}", forge.name(), owner, repo_name, owner, new_name);
+            operations.add_operation(Box::new(RenameRemoteOperation::new(forge, owner, repo_name, new_name)));
+        }
+        
+        operations.execute().await?;
+        
+        println!("✓ Renamed hidden directory: {} -> {}", from, to);
+        if rename_remote {
+            println!("  - Remote: {}", new_url);
+        }
+        Ok(())
+    }
+    
+    async fn relink_hidden_repositories(
+        &mut self,
+        project_path: &Path,
+        from: &str,
+        rename_remotes: bool,
+        no_atomic: bool
+    ) -> Result<(), RepositoryError> {
+        let remote_url = self.get_remote_origin(project_path)?;
+        let base_key = GitOperations::generate_base_key(&remote_url)?;
+        let old_base_key = GitOperations::generate_base_key(from)?;
+        if old_base_key == base_key {
+            return Err(RepositoryError::IoError(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{} is the current origin of this project", from),
+            )));
+        }
+        
+        let registrations: Vec<ProjectRegistration> = self.find_registrations(project_path, &old_base_key)?
+            .into_iter()
+            .filter(|p| p.base_key() == old_base_key)
+            .collect();
+        if registrations.is_empty() {
+            return Err(RepositoryError::IoError(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No hidden repositories registered for {}", from),
+            )));
+        }
+        let manifest = ProjectManifest::load(project_path)?;
+        let in_manifest = manifest.as_ref()
+            .is_some_and(|m| registrations.iter().any(|p| m.contains(&p.repository_key)));
+        
+        let mut replacements = Vec::new();
+        let mut remote_operations: Vec<Box<dyn Operation>> = Vec::new();
+        for registration in &registrations {
+            let directory = &registration.hidden_directory;
+            let new_key = GitOperations::generate_repository_key(&remote_url, Some(directory))?;
+            let new_key_in_manifest = manifest.as_ref().is_some_and(|m| m.contains(&new_key));
+            if new_key_in_manifest || self.index_manager.project_exists(&new_key) {
+                return Err(RepositoryError::ProjectAlreadyExists(new_key));
+            }
+            
+            // 旧的注册信息可能没有记录仓库名，按旧 Key 推导出的名称必须保留下来
+            let repo_name = registration.remote_name();
+            let mut new_name = repo_name.clone();
+            if rename_remotes && !registration.is_adopted() && !registration.is_shared() {
+                new_name = crate::naming::repository_name(self.config.repository_naming(), &new_key, directory)?;
+            }
+            
+            if new_name != repo_name {
+                let forge = self.forge(self.project_organization(registration));
+                let owner = self.remote_owner(registration).to_string();
+                let hidden_dir = project_path.join(directory);
+                if hidden_dir.join(".git").exists() {
+                    let new_url = GitOperations::get_remote_origin(&hidden_dir).ok()
+                        .and_then(|url| renamed_remote_url(&url, &repo_name, &new_name))
+                        .unwrap_or_else(|| forge.clone_url(&owner, &new_name));
+                    remote_operations.push(Box::new(SetOriginOperation::new(hidden_dir, new_url)));
+                }
+                println!("Renaming remote repository on {}: {}/{} -> {}/{}", forge.name(), owner, repo_name, owner, new_name);
+                remote_operations.push(Box::new(RenameRemoteOperation::new(forge, owner, repo_name, new_name.clone())));
+            }
+            
+            replacements.push((registration.repository_key.clone(), ProjectRegistration {
+                repository_key: new_key,
+                repository_name: new_name,
+                project_git_path: remote_url.clone(),
+                ..registration.clone()
+            }));
+        }
+        
+        let store = if in_manifest {
+            RegistrationStore::Manifest(project_path.to_path_buf())
+        } else {
+            RegistrationStore::Index(&mut self.index_manager)
+        };
+        let mut operations = AtomicOperations::new(no_atomic);
+        operations.add_operation(Box::new(ReplaceRegistrationOperation::new(store, replacements)));
+        // 远程仓库最后改名，之前的步骤都可以回滚
+        for operation in remote_operations {
+            operations.add_operation(operation);
+        }
+        operations.execute().await?;
+        
+        println!("✓ Relinked {} hidden repositories from {} to {}", registrations.len(), old_base_key, base_key);
+        Ok(())
+    }
+    
+    /// 当前目录没有注册信息时，查找看起来像是项目改名前创建的隐藏目录
+    ///
+    /// 逐个检查清单和索引中登记的隐藏目录（包括 `tools/.cache` 这样的嵌套目录），
+    /// 目录的 origin 指向登记的隐藏仓库时，返回该仓库注册时的父仓库 URL。
+    fn detect_moved_project(&self, path: &Path) -> Option<String> {
+        let manifest = ProjectManifest::load(path).ok().flatten().unwrap_or_default();
+        let registrations = manifest.projects.into_values().chain(self.index_manager.projects());
+        for registration in registrations {
+            let hidden_dir = path.join(&registration.hidden_directory);
+            if !hidden_dir.join(".git").exists() {
+                continue;
+            }
+            let Ok(url) = GitOperations::get_remote_origin(&hidden_dir) else {
+                continue;
+            };
+            if remote_repository_name(&url) == registration.remote_name() {
+                return Some(registration.project_git_path);
+            }
+        }
+        None
+    }
+    
+    async fn create_hidden_repository(
+        &mut self,
+        project_path: &Path,
+        directory: &str,
+        repository_key: &str,
+        org: &str,
+        use_manifest: bool,
+        shared: Option<&str>
+    ) -> Result<String, RepositoryError> {
+        let hidden_dir = project_path.join(directory);
+        
+        // 检查目录是否已存在
+        let dir_existed = hidden_dir.exists();
+        
+        // 创建隐藏目录（如果不存在）
+        if !dir_existed {
+            std::fs::create_dir_all(&hidden_dir)?;
+        }
+        
+        // 按配置的命名策略生成仓库名，共享隐藏仓库直接使用共享名
+        let repo_name = match shared {
+            Some(name) => name.to_string(),
+            None => crate::naming::repository_name(self.config.repository_naming(), repository_key, directory)?,
+        };
+        
+        // 在组织所在平台上创建远程仓库（必须成功）
+        let forge = self.forge(org);
+        println!("Creating remote repository on {}: {}/{}", forge.name(), org, repo_name);
+        let description = match shared {
+            Some(name) => shared_description(name),
+            None => format!("Hidden repository for {}", repository_key),
+        };
+        
+        let template = self.config.get_organization_config(org).repository_template;
+        
+        let result = match crate::forge::ensure_repository(forge.as_ref(), org, &repo_name, &description).await {
+            Ok(ensured) if ensured.created => {
+                println!("  ✓ Remote repository created successfully");
+                let heads = GitOperations::remote_heads(&ensured.url, forge.credentials().as_ref()).ok();
+                self.created_remotes.insert(
+                    (org.to_string(), repo_name.clone()),
+                    CreatedRemote { owner: ensured.owner.clone(), heads },
+                );
+                // 应用组织的仓库模板，失败时按 remote_cleanup 移除刚创建的仓库
+                match &template {
+                    Some(template) => match crate::forge::apply_template(forge.as_ref(), &ensured.owner, &repo_name, template).await {
+                        Ok(()) => Ok(ensured),
+                        Err(e) => {
+                            println!("Rolling back: removing remote repository {}/{}", ensured.owner, repo_name);
+                            if let Err(dispose_error) = self.dispose_remote_repository(org, &repo_name).await {
+                                eprintln!("Warning: Failed to remove remote repository: {}", dispose_error);
+                            }
+                            Err(e)
+                        }
+                    },
+                    None => Ok(ensured),
+                }
+            }
+            Ok(ensured) => Ok(ensured),
+            Err(e) => Err(e),
+        };
+        
+        let (remote_url, owner) = match result {
+            Ok(ensured) => (ensured.url, ensured.owner),
+            Err(e) => {
+                // 远程仓库创建失败
+                // 只有当目录是我们新创建的才删除，已存在的目录不能删！
+                if !dir_existed && hidden_dir.exists() {
+                    let _ = std::fs::remove_dir_all(&hidden_dir);
+                }
+                return Err(e);
+            }
+        };
+        
+        // 检查是否已经是 git 仓库
+        let is_git_repo = hidden_dir.join(".git").exists();
+        
+        if !is_git_repo {
+            // 初始化本地 git 仓库
+            GitOperations::init_repository(&hidden_dir)?;
+            
+            // 设置远程 origin
+            let repo = git2::Repository::open(&hidden_dir)?;
+            repo.remote("origin", &remote_url)?;
+            
+            // 本地初始分支与模板中的默认分支一致
+            if let Some(branch) = template.as_ref().and_then(|t| t.default_branch.as_ref()) {
+                repo.set_head(&format!("refs/heads/{}", branch))?;
+            }
+        } else {
+            // 已经是 git 仓库，检查是否需要更新 remote
+            let repo = git2::Repository::open(&hidden_dir)?;
+            if repo.find_remote("origin").is_err() {
+                repo.remote("origin", &remote_url)?;
+            }
+        }
+        
+        // 注册到索引
+        let registration = ProjectRegistration {
+            repository_key: repository_key.to_string(),
+            repository_name: repo_name.clone(),
+            organization: org.to_string(),
+            // 平台把仓库建在了其他账户下（GitHub 的 `allow_user_fallback`），之后按实际账户访问
+            owner: (owner != org).then_some(owner),
+            git_user: GitOperations::get_git_user(project_path)?,
+            project_git_path: self.get_remote_origin(project_path)?,
+            project_disk_path: project_path.to_string_lossy().to_string(),
+            hidden_directory: directory.to_string(),
+            created_at: chrono::Utc::now(),
+            remote_url: None,
+            shared: shared.map(str::to_string),
+        };
+        self.register_hidden_repository(project_path, registration, use_manifest).await?;
+        
+        println!("✓ Created hidden repository: {}", directory);
+        println!("  - Remote: {}", remote_url);
+        Ok(repo_name)
+    }
+    
+    /// 接管目录中已有的 git 仓库：确认 origin 可以访问后原样登记，不在平台上创建仓库
+    ///
+    /// origin 可以在组织之外，甚至在其他平台上；访问使用用户自己的 git 凭据。
+    async fn adopt_hidden_repository(
+        &mut self,
+        project_path: &Path,
+        directory: &str,
+        repository_key: &str,
+        org: &str,
+        use_manifest: bool
+    ) -> Result<String, RepositoryError> {
+        let hidden_dir = project_path.join(directory);
+        if !hidden_dir.join(".git").exists() {
+            return Err(RepositoryError::IoError(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("{} is not a git repository; run 'dot init {}' without --adopt", directory, directory),
+            )));
+        }
+        
+        let remote_url = GitOperations::get_remote_origin(&hidden_dir)?;
+        println!("Checking remote repository: {}", remote_url);
+        GitOperations::remote_heads(&remote_url, None)?;
+        
+        let repo_name = remote_repository_name(&remote_url).to_string();
+        let registration = ProjectRegistration {
+            repository_key: repository_key.to_string(),
+            repository_name: repo_name.clone(),
+            organization: org.to_string(),
+            owner: None,
+            git_user: GitOperations::get_git_user(project_path)?,
+            project_git_path: self.get_remote_origin(project_path)?,
+            project_disk_path: project_path.to_string_lossy().to_string(),
+            hidden_directory: directory.to_string(),
+            created_at: chrono::Utc::now(),
+            remote_url: Some(remote_url.clone()),
+            shared: None,
+        };
+        self.register_hidden_repository(project_path, registration, use_manifest).await?;
+        
+        println!("✓ Adopted hidden repository: {}", directory);
+        println!("  - Remote: {}", remote_url);
+        Ok(repo_name)
+    }
+    
+    async fn link_hidden_repository(
+        &mut self,
+        project_path: &Path,
+        name: &str,
+        directory: &str,
+        organization: Option<String>
+    ) -> Result<(), RepositoryError> {
+        validate_shared_name(name)?;
+        let directory = &GitOperations::normalize_hidden_path(directory)?;
+        let remote_url = self.get_remote_origin(project_path)?;
+        let base_key = GitOperations::generate_base_key(&remote_url)?;
+        let repository_key = GitOperations::generate_repository_key(&remote_url, Some(directory))?;
+        
+        let in_manifest = ProjectManifest::load(project_path)?.is_some_and(|m| m.contains(&repository_key));
+        if in_manifest || self.index_manager.project_exists(&repository_key) {
+            return Err(RepositoryError::ProjectAlreadyExists(repository_key));
+        }
+        check_overlaps(&[directory.to_string()], &self.find_registrations(project_path, &base_key)?)?;
+        
+        // 索引中还没有时查找组织中已有的同名仓库
+        let repository = match self.index_manager.shared_repository(name) {
+            Some(repository) => repository.clone(),
+            None => {
+                let org = match organization {
+                    Some(org) if !self.config.is_organization_authorized(&org) => {
+                        return Err(ConfigError::OrganizationNotAuthorized.into());
+                    }
+                    Some(org) => org,
+                    None => self.index_manager.get_organization().to_string(),
+                };
+                if self.forge(&org).get_repository(&org, name).await?.is_none() {
+                    return Err(RepositoryError::IoError(std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        format!("Shared repository '{}' not found in {}; create it with 'dot init --shared {} <directory>'", name, org, name),
+                    )));
+                }
+                SharedRepository {
+                    repository_name: name.to_string(),
+                    organization: org,
+                    owner: None,
+                    created_at: chrono::Utc::now(),
+                    remote_url: None,
+                    links: HashMap::new(),
+                }
+            }
+        };
+        let link = SharedLink {
+            git_user: GitOperations::get_git_user(project_path)?,
+            project_git_path: remote_url,
+            hidden_directory: directory.to_string(),
+            linked_at: chrono::Utc::now(),
+        };
+        let registration = repository.registration(name, &repository_key, &link);
+        
+        // 已有的检出必须指向共享仓库，否则克隆到空目录
+        let hidden_dir = project_path.join(directory);
+        let cloned = if hidden_dir.join(".git").exists() {
+            let origin = GitOperations::get_remote_origin(&hidden_dir)?;
+            if remote_repository_name(&origin) != registration.remote_name() {
+                return Err(RepositoryError::InvalidHiddenDirectory {
+                    path: directory.to_string(),
+                    reason: format!("existing repository points to {}, not the shared repository '{}'", origin, name),
+                });
+            }
+            false
+        } else {
+            if std::fs::read_dir(&hidden_dir).is_ok_and(|mut entries| entries.next().is_some()) {
+                return Err(RepositoryError::InvalidHiddenDirectory {
+                    path: directory.to_string(),
+                    reason: "directory exists and is not empty".to_string(),
+                });
+            }
+            let url = self.generate_hidden_repo_url(&registration);
+            let credentials = self.hidden_repo_credentials(&registration);
+            GitOperations::clone_repository_with_credentials(&url, &hidden_dir, credentials.as_ref())?;
+            true
+        };
+        
+        if let Err(e) = self.index_manager.register_project(registration.clone()).await {
+            if cloned {
+                let _ = std::fs::remove_dir_all(&hidden_dir);
+            }
+            return Err(e.into());
+        }
+        
+        println!("✓ Linked shared repository '{}' into {}", name, directory);
+        println!("  - Remote: {}", self.generate_hidden_repo_url(&registration));
+        Ok(())
+    }
+    
+    /// 将隐藏仓库登记到项目本地清单或全局索引
+    async fn register_hidden_repository(
+        &mut self,
+        project_path: &Path,
+        registration: ProjectRegistration,
+        use_manifest: bool
+    ) -> Result<(), RepositoryError> {
+        if use_manifest {
+            // 记录到项目本地清单，不写入全局索引
+            let mut manifest = ProjectManifest::load(project_path)?.unwrap_or_default();
+            manifest.register(registration)?;
+            manifest.save(project_path)?;
+        } else {
+            self.index_manager.register_project(registration).await?;
+        }
+        Ok(())
+    }
+    
+    /// 移除本次写入的注册信息（项目清单或全局索引）
+    async fn unregister_hidden_repository(&mut self, project_path: &Path, repository_key: &str) -> Result<(), RepositoryError> {
+        if let Some(mut manifest) = ProjectManifest::load(project_path)? {
+            if manifest.unregister(repository_key).is_some() {
+                manifest.save(project_path)?;
+            }
+        }
+        self.index_manager.unregister_project(repository_key).await?;
+        Ok(())
+    }
+    
+    async fn rollback_hidden_repository(
+        &mut self,
+        project_path: &Path,
+        directory: &str,
+        repository_key: &str,
+        repo_name: &str,
+        org: &str,
+        dir_was_created: bool,  // 新增参数：目录是否是我们创建的
+    ) -> Result<(), RepositoryError> {
+        if let Err(e) = self.unregister_hidden_repository(project_path, repository_key).await {
+            eprintln!("Warning: Failed to unregister {}: {}", repository_key, e);
+        }
+        
+        // 只有当目录是我们新创建的才删除
+        if dir_was_created {
+            let hidden_dir = project_path.join(directory);
+            if hidden_dir.exists() {
+                std::fs::remove_dir_all(&hidden_dir)?;
+            }
+        }
+        
+        println!("Rolling back remote repository {}/{}", org, repo_name);
+        if let Err(e) = self.dispose_remote_repository(org, repo_name).await {
+            eprintln!("Warning: Failed to remove remote repository: {}", e);
+        }
+        
+        Ok(())
+    }
+    
+    /// 移除远程隐藏仓库
+    ///
+    /// 本次运行中新建且之后没有任何推送的仓库直接删除；其余仓库按 `remote_cleanup`
+    /// 改名为 `dot-trash-<时间戳>-<原名>`（并归档）保留，除非配置为直接删除。
+    async fn dispose_remote_repository(&self, org: &str, repo_name: &str) -> Result<(), RepositoryError> {
+        let forge = self.forge(org);
+        let created = self.created_remotes.get(&(org.to_string(), repo_name.to_string()));
+        let owner = created.map_or(org, |remote| remote.owner.as_str());
+        let mut mode = self.config.remote_cleanup();
+        if mode != RemoteCleanup::Delete && created.is_some_and(|remote| Self::is_unpushed(forge.as_ref(), remote, repo_name)) {
+            mode = RemoteCleanup::Delete;
+        }
+        forge::dispose_repository(forge.as_ref(), owner, repo_name, mode).await?;
+        Ok(())
+    }
+    
+    /// 本次运行中新建的远程仓库在创建后是否没有任何推送
+    fn is_unpushed(forge: &dyn Forge, remote: &CreatedRemote, repo_name: &str) -> bool {
+        let Some(snapshot) = &remote.heads else {
+            return false;
+        };
+        let url = forge.clone_url(&remote.owner, repo_name);
+        GitOperations::remote_heads(&url, forge.credentials().as_ref())
+            .is_ok_and(|heads| &heads == snapshot)
+    }
+    
+    #[allow(dead_code)]
+    async fn create_remote_hidden_repository(&self, _repository_key: &str) -> Result<String, RepositoryError> {
+        // 这个方法不再使用，保留以兼容
+        unreachable!("This method is deprecated")
+    }
+    
+    /// 隐藏仓库所在的组织（旧的注册信息没有记录组织时使用默认组织）
+    fn project_organization<'a>(&'a self, project: &'a ProjectRegistration) -> &'a str {
+        if project.organization.is_empty() {
+            self.index_manager.get_organization()
+        } else {
+            &project.organization
+        }
+    }
+    
+    /// 远程仓库实际所在的账户（没有单独记录时就是组织）
+    fn remote_owner<'a>(&'a self, project: &'a ProjectRegistration) -> &'a str {
+        project.owner.as_deref().unwrap_or_else(|| self.project_organization(project))
+    }
+    
+    fn generate_hidden_repo_url(&self, project: &ProjectRegistration) -> String {
+        if let Some(url) = &project.remote_url {
+            return url.clone();
+        }
+        self.forge(self.project_organization(project)).clone_url(self.remote_owner(project), &project.remote_name())
+    }
+    
+    /// 隐藏仓库通过 HTTPS 访问时使用的凭据（接管的仓库可能不在组织的平台上，使用用户自己的凭据）
+    fn hidden_repo_credentials(&self, project: &ProjectRegistration) -> Option<GitCredentials> {
+        if project.is_adopted() {
+            return None;
+        }
+        self.forge(self.project_organization(project)).credentials()
+    }
+    
+    async fn is_dot_initialized(&self, path: &Path) -> Result<bool, RepositoryError> {
+        if !GitOperations::is_git_initialized(path) {
+            return Ok(false);
+        }
+        
+        let remote_url = match GitOperations::get_remote_origin(path) {
+            Ok(url) => url,
+            Err(_) => return Ok(false),
+        };
+        
+        let base_key = GitOperations::generate_base_key(&remote_url)?;
+        let projects = self.find_registrations(path, &base_key)?;
+        
+        Ok(!projects.is_empty())
+    }
+    
+    async fn get_hidden_repositories(&self, path: &Path) -> Result<Vec<(String, PathBuf)>, RepositoryError> {
+        let remote_url = GitOperations::get_remote_origin(path)?;
+        let base_key = GitOperations::generate_base_key(&remote_url)?;
+        let projects = self.find_registrations(path, &base_key)?;
+        
+        let mut hidden_repos = Vec::new();
+        for project in projects {
+            let repo_path = path.join(&project.hidden_directory);
+            hidden_repos.push((project.hidden_directory, repo_path));
+        }
+        
+        Ok(hidden_repos)
+    }
+    
+    /// `dot foreach` 运行命令的仓库：父仓库在前，隐藏仓库按目录排序
+    fn foreach_targets(&self, path: &Path, include_parent: bool, skip_hidden: bool) -> Result<Vec<foreach::Target>, RepositoryError> {
+        let remote_url = GitOperations::get_remote_origin(path)?;
+        let base_key = GitOperations::generate_base_key(&remote_url)?;
+        
+        let mut targets = Vec::new();
+        if include_parent {
+            targets.push(foreach::Target {
+                directory: ".".to_string(),
+                path: path.to_path_buf(),
+                repository_key: base_key.clone(),
+                remote: remote_url,
+            });
+        }
+        if skip_hidden {
+            return Ok(targets);
+        }
+        
+        let mut projects = self.find_registrations(path, &base_key)?;
+        projects.sort_by(|a, b| a.hidden_directory.cmp(&b.hidden_directory));
+        for project in projects {
+            let repo_path = path.join(&project.hidden_directory);
+            if !repo_path.join(".git").exists() {
+                eprintln!("Skipping {}: not checked out", project.hidden_directory);
+                continue;
+            }
+            let remote = GitOperations::get_remote_origin(&repo_path)
+                .unwrap_or_else(|_| self.generate_hidden_repo_url(&project));
+            targets.push(foreach::Target {
+                directory: project.hidden_directory,
+                path: repo_path,
+                repository_key: project.repository_key,
+                remote,
+            });
+        }
+        Ok(targets)
+    }
+    
+    /// 查找项目的隐藏仓库注册信息：优先使用项目本地清单，其次使用全局索引
+    fn find_registrations(&self, path: &Path, base_key: &str) -> Result<Vec<ProjectRegistration>, RepositoryError> {
+        if let Some(manifest) = ProjectManifest::load(path)? {
+            let projects = manifest.find_projects_by_base_key(base_key);
+            if !projects.is_empty() {
+                return Ok(projects.into_iter().cloned().collect());
+            }
+        }
+        
+        Ok(self.index_manager.find_projects_by_base_key(base_key))
+    }
+    /// 查找项目中某个隐藏目录的注册信息，同时返回它是否记录在项目本地清单中
+    fn find_registration(&self, path: &Path, directory: &str) -> Result<(ProjectRegistration, bool), RepositoryError> {
+        let remote_url = self.get_remote_origin(path)?;
+        let base_key = GitOperations::generate_base_key(&remote_url)?;
+        let repository_key = GitOperations::generate_repository_key(&remote_url, Some(directory))?;
+        
+        let registration = self.find_registrations(path, &base_key)?
+            .into_iter()
+            .find(|p| p.repository_key == repository_key)
+            .ok_or_else(|| RepositoryError::IoError(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("{} is not managed by dot", directory),
+            )))?;
+        let in_manifest = ProjectManifest::load(path)?.is_some_and(|m| m.contains(&repository_key));
+        
+        Ok((registration, in_manifest))
+    }
+}
+
+/// 将远程 URL 结尾的仓库名替换为新名称，保留主机、协议和 SSH 别名
+fn renamed_remote_url(url: &str, repo_name: &str, new_name: &str) -> Option<String> {
+    [".git", ""].iter().find_map(|suffix| {
+        url.strip_suffix(&format!("{}{}", repo_name, suffix))
+            .filter(|prefix| prefix.ends_with('/') || prefix.ends_with(':'))
+            .map(|prefix| format!("{}{}{}", prefix, new_name, suffix))
+    })
+}
+
+/// 两个隐藏目录是否相同或互相包含（如 `.config` 和 `.config/app`）
+fn directories_overlap(a: &str, b: &str) -> bool {
+    a == b || a.starts_with(&format!("{}/", b)) || b.starts_with(&format!("{}/", a))
+}
+
+/// 检查新的隐藏目录之间以及与已注册的隐藏目录之间没有重叠
+fn check_overlaps(directories: &[String], registered: &[ProjectRegistration]) -> Result<(), RepositoryError> {
+    for (i, directory) in directories.iter().enumerate() {
+        let existing = registered.iter().map(|p| p.hidden_directory.as_str());
+        let others = directories[..i].iter().map(String::as_str).chain(existing);
+        for other in others {
+            if directories_overlap(directory, other) {
+                return Err(RepositoryError::InvalidHiddenDirectory {
+                    path: directory.clone(),
+                    reason: format!("overlaps with {}", other),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 将 `dot add` 的文件分配到所在的仓库
+///
+/// 隐藏目录中的文件交给最深的匹配隐藏目录（路径改为相对于该目录），其余文件交给父仓库；
+/// `.` 表示所有仓库的全部文件。
+fn route_files(files: &[String], hidden_directories: &[String]) -> (HashMap<String, Vec<String>>, Vec<String>) {
+    let mut hidden: HashMap<String, Vec<String>> = HashMap::new();
+    let mut parent = Vec::new();
+    
+    for file in files {
+        if file == "." {
+            for directory in hidden_directories {
+                hidden.entry(directory.clone()).or_default().push(".".to_string());
+            }
+            parent.push(".".to_string());
+            continue;
+        }
+        
+        let path = file.replace('\\', "/");
+        let path = path.trim_start_matches("./").trim_end_matches('/');
+        let owner = hidden_directories
+            .iter()
+            .filter(|d| path == d.as_str() || path.starts_with(&format!("{}/", d)))
+            .max_by_key(|d| d.len());
+        match owner {
+            Some(directory) => {
+                let relative = path[directory.len()..].trim_start_matches('/');
+                let relative = if relative.is_empty() { "." } else { relative };
+                hidden.entry(directory.clone()).or_default().push(relative.to_string());
+            }
+            None => parent.push(file.clone()),
+        }
+    }
+    
+    (hidden, parent)
+}
+
+/// 远程 URL 中的仓库名（最后一段路径，去掉 `.git`）
+fn remote_repository_name(url: &str) -> &str {
+    let name = url.rsplit(['/', ':']).next().unwrap_or(url);
+    name.strip_suffix(".git").unwrap_or(name)
+}
+
+/// 把模板复制到新建的隐藏目录并创建初始提交
+fn apply_template(template: &Template, project_path: &Path, directory: &str) -> Result<(), RepositoryError> {
+    let copied = template.apply(&project_path.join(directory))?;
+    println!("  - Copied {} file(s) from template into {}", copied.len(), directory);
+    Ok(())
+}
+
+/// 共享隐藏仓库名直接用作远程仓库名，只允许平台通用的字符
+fn validate_shared_name(name: &str) -> Result<(), RepositoryError> {
+    let valid = !name.is_empty()
+        && name.len() <= 100
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        return Ok(());
+    }
+    Err(RepositoryError::IoError(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("Invalid shared repository name '{}': use letters, digits, '-', '_' and '.'", name),
+    )))
+}
+
+/// 共享隐藏仓库在平台上的描述（同时作为 dot 的标记）
+fn shared_description(name: &str) -> String {
+    format!("Shared hidden repository {}", name)
+}
+
+// 实现 From trait 用于错误转换
+impl From<crate::error::OperationError> for RepositoryError {
+    fn from(err: crate::error::OperationError) -> Self {
+        RepositoryError::IoError(std::io::Error::other(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DotConfig;
+    use crate::index_store::LocalIndexStore;
+    use tempfile::TempDir;
+    
+    #[tokio::test]
+    async fn test_repository_manager_creation() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("project");
+        let repo = GitOperations::init_repository(&project_path).unwrap();
+        repo.remote("origin", "git@github.com:user/repo.git").unwrap();
+        
+        let store = Box::new(LocalIndexStore::new(temp_dir.path().join("index")));
+        let mut index_manager = IndexManager::with_store("test-org".to_string(), store).await.unwrap();
+        index_manager.register_project(ProjectRegistration {
+            repository_key: "github.com:user/repo/.kiro".to_string(),
+            repository_name: "abc123def456".to_string(),
+            organization: "test-org".to_string(),
+            owner: None,
+            git_user: "testuser".to_string(),
+            project_git_path: "git@github.com:user/repo.git".to_string(),
+            project_disk_path: String::new(),
+            hidden_directory: ".kiro".to_string(),
+            created_at: chrono::Utc::now(),
+            remote_url: None,
+            shared: None,
+        }).await.unwrap();
+        
+        let config = ConfigManager::new(temp_dir.path().join("dot.conf"), DotConfig::default());
+        let manager = RepositoryManager::new(config, index_manager);
+        
+        assert!(manager.is_dot_initialized(&project_path).await.unwrap());
+        let hidden_repos = manager.get_hidden_repositories(&project_path).await.unwrap();
+        assert_eq!(hidden_repos, vec![(".kiro".to_string(), project_path.join(".kiro"))]);
+    }
+    
+    #[tokio::test]
+    async fn test_manifest_is_consulted_before_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("project");
+        let repo = GitOperations::init_repository(&project_path).unwrap();
+        repo.remote("origin", "git@github.com:user/repo.git").unwrap();
+        
+        let store = Box::new(LocalIndexStore::new(temp_dir.path().join("index")));
+        let index_manager = IndexManager::with_store("test-org".to_string(), store).await.unwrap();
+        let config = ConfigManager::new(temp_dir.path().join("dot.conf"), DotConfig::default());
+        let manager = RepositoryManager::new(config, index_manager);
+        
+        assert!(!manager.is_dot_initialized(&project_path).await.unwrap());
+        
+        let mut manifest = ProjectManifest::default();
+        manifest.register(ProjectRegistration {
+            repository_key: "github.com:user/repo/.config".to_string(),
+            repository_name: "abc123def456".to_string(),
+            organization: "test-org".to_string(),
+            owner: None,
+            git_user: "testuser".to_string(),
+            project_git_path: "git@github.com:user/repo.git".to_string(),
+            project_disk_path: String::new(),
+            hidden_directory: ".config".to_string(),
+            created_at: chrono::Utc::now(),
+            remote_url: None,
+            shared: None,
+        }).unwrap();
+        manifest.save(&project_path).unwrap();
+        
+        assert!(manager.is_dot_initialized(&project_path).await.unwrap());
+        let hidden_repos = manager.get_hidden_repositories(&project_path).await.unwrap();
+        assert_eq!(hidden_repos, vec![(".config".to_string(), project_path.join(".config"))]);
+    }
+    
+    #[tokio::test]
+    async fn test_hidden_repository_on_local_forge() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("project");
+        let repo = GitOperations::init_repository(&project_path).unwrap();
+        repo.remote("origin", "git@github.com:user/repo.git").unwrap();
+        repo.config().unwrap().set_str("user.name", "testuser").unwrap();
+        
+        let root = temp_dir.path().join("remotes");
+        let config: DotConfig = serde_json::from_value(serde_json::json!({
+            "authorized_organizations": ["acme"],
+            "default_organization": "acme",
+            "organizations": {"acme": {"forge": "local", "root": root}}
+        })).unwrap();
+        let config = ConfigManager::new(temp_dir.path().join("dot.conf"), config);
+        let store = Box::new(LocalIndexStore::new(temp_dir.path().join("index")));
+        let index_manager = IndexManager::with_store("acme".to_string(), store).await.unwrap();
+        let mut manager = RepositoryManager::new(config, index_manager);
+        
+        let key = "github.com:user/repo/.kiro";
+        manager.create_hidden_repository(&project_path, ".kiro", key, "acme", false, None).await.unwrap();
+        
+        let repo_name = format!("{:x}", md5::compute(key.as_bytes()));
+        let bare_path = root.join("acme").join(format!("{}.git", repo_name));
+        let hidden_dir = project_path.join(".kiro");
+        assert_eq!(
+            GitOperations::get_remote_origin(&hidden_dir).unwrap(),
+            format!("file://{}", bare_path.display())
+        );
+        
+        // 无需网络即可提交并推送到裸仓库
+        std::fs::write(hidden_dir.join("notes.md"), "hello").unwrap();
+        GitOperations::add_all(&hidden_dir).unwrap();
+        GitOperations::commit(&hidden_dir, "Initial commit").unwrap();
+        GitOperations::push(&hidden_dir).unwrap();
+        let bare = git2::Repository::open_bare(&bare_path).unwrap();
+        assert!(bare.head().is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_repository_template_applied_and_rolled_back() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("project");
+        let repo = GitOperations::init_repository(&project_path).unwrap();
+        repo.remote("origin", "git@github.com:user/repo.git").unwrap();
+        repo.config().unwrap().set_str("user.name", "testuser").unwrap();
+        
+        let root = temp_dir.path().join("remotes");
+        let manager = |template: serde_json::Value| {
+            let config: DotConfig = serde_json::from_value(serde_json::json!({
+                "authorized_organizations": ["acme"],
+                "default_organization": "acme",
+                "organizations": {"acme": {"forge": "local", "root": root, "repository_template": template}}
+            })).unwrap();
+            ConfigManager::new(temp_dir.path().join("dot.conf"), config)
+        };
+        let store = || Box::new(LocalIndexStore::new(temp_dir.path().join("index")));
+        
+        // 默认分支同时应用到远程和本地仓库
+        let config = manager(serde_json::json!({"default_branch": "trunk"}));
+        let index_manager = IndexManager::with_store("acme".to_string(), store()).await.unwrap();
+        let mut manager_with_branch = RepositoryManager::new(config, index_manager);
+        let key = "github.com:user/repo/.kiro";
+        manager_with_branch.create_hidden_repository(&project_path, ".kiro", key, "acme", false, None).await.unwrap();
+        let bare_path = root.join("acme").join(format!("{:x}.git", md5::compute(key.as_bytes())));
+        let bare = git2::Repository::open_bare(&bare_path).unwrap();
+        assert_eq!(bare.find_reference("HEAD").unwrap().symbolic_target(), Some("refs/heads/trunk"));
+        let local = git2::Repository::open(project_path.join(".kiro")).unwrap();
+        assert_eq!(local.find_reference("HEAD").unwrap().symbolic_target(), Some("refs/heads/trunk"));
+        
+        // 本地平台不支持主题：失败时移除刚创建（尚未推送）的远程仓库和目录
+        let config = manager(serde_json::json!({"topics": ["dot-hidden"]}));
+        let index_manager = IndexManager::with_store("acme".to_string(), store()).await.unwrap();
+        let mut manager_with_topics = RepositoryManager::new(config, index_manager);
+        let key = "github.com:user/repo/.cursor";
+        assert!(manager_with_topics.create_hidden_repository(&project_path, ".cursor", key, "acme", false, None).await.is_err());
+        assert!(!root.join("acme").join(format!("{:x}.git", md5::compute(key.as_bytes()))).exists());
+        assert!(!project_path.join(".cursor").exists());
+    }
+    
+    #[tokio::test]
+    async fn test_hide_files_restores_all_files_on_failure() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("project");
+        let repo = GitOperations::init_repository(&project_path).unwrap();
+        repo.config().unwrap().set_str("user.name", "testuser").unwrap();
+        GitOperations::init_repository(project_path.join(STORE_DIRECTORY)).unwrap();
+        std::fs::write(project_path.join(".env"), "TOKEN=1").unwrap();
+        std::fs::write(project_path.join("tracked.txt"), "tracked").unwrap();
+        GitOperations::add_files(&project_path, &["tracked.txt".to_string()]).unwrap();
+        
+        let store = Box::new(LocalIndexStore::new(temp_dir.path().join("index")));
+        let index_manager = IndexManager::with_store("acme".to_string(), store).await.unwrap();
+        let config = ConfigManager::new(temp_dir.path().join("dot.conf"), DotConfig::default());
+        let manager = RepositoryManager::new(config, index_manager);
+        
+        // 第二个文件被父仓库跟踪，无法隐藏：第一个文件也移回原位置
+        let files = vec![".env".to_string(), "tracked.txt".to_string()];
+        assert!(manager.hide_files(&project_path, &files).is_err());
+        assert!(!project_path.join(".env").is_symlink());
+        assert_eq!(std::fs::read_to_string(project_path.join(".env")).unwrap(), "TOKEN=1");
+        assert!(hidden_files::tracked_files(&project_path).unwrap().is_empty());
+        let exclude = std::fs::read_to_string(project_path.join(".git").join("info").join("exclude")).unwrap_or_default();
+        assert!(!exclude.contains("/.env") && !exclude.contains(STORE_DIRECTORY), "{}", exclude);
+    }
+    
+    #[tokio::test]
+    async fn test_rollback_only_deletes_unpushed_new_remotes() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("project");
+        let repo = GitOperations::init_repository(&project_path).unwrap();
+        repo.remote("origin", "git@github.com:user/repo.git").unwrap();
+        repo.config().unwrap().set_str("user.name", "testuser").unwrap();
+        
+        let root = temp_dir.path().join("remotes");
+        let new_manager = || async {
+            let config: DotConfig = serde_json::from_value(serde_json::json!({
+                "authorized_organizations": ["acme"],
+                "default_organization": "acme",
+                "remote_cleanup": "archive",
+                "organizations": {"acme": {"forge": "local", "root": root}}
+            })).unwrap();
+            let config = ConfigManager::new(temp_dir.path().join("dot.conf"), config);
+            let store = Box::new(LocalIndexStore::new(temp_dir.path().join("index")));
+            let index_manager = IndexManager::with_store("acme".to_string(), store).await.unwrap();
+            RepositoryManager::new(config, index_manager)
+        };
+        let bare_path = |key: &str| root.join("acme").join(format!("{:x}.git", md5::compute(key.as_bytes())));
+        let trash = || -> Vec<String> {
+            std::fs::read_dir(root.join("acme")).unwrap()
+                .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+                .filter(|name| name.starts_with("dot-trash-"))
+                .collect()
+        };
+        let mut manager = new_manager().await;
+        
+        // 本次新建且没有推送：直接删除
+        let kiro = "github.com:user/repo/.kiro";
+        let name = manager.create_hidden_repository(&project_path, ".kiro", kiro, "acme", false, None).await.unwrap();
+        manager.rollback_hidden_repository(&project_path, ".kiro", kiro, &name, "acme", true).await.unwrap();
+        assert!(!bare_path(kiro).exists());
+        assert!(!manager.index_manager.project_exists(kiro));
+        assert!(trash().is_empty());
+        
+        // 本次新建但已经推送过提交：改名保留
+        let cursor = "github.com:user/repo/.cursor";
+        let name = manager.create_hidden_repository(&project_path, ".cursor", cursor, "acme", false, None).await.unwrap();
+        let hidden_dir = project_path.join(".cursor");
+        std::fs::write(hidden_dir.join("notes.md"), "hello").unwrap();
+        GitOperations::add_all(&hidden_dir).unwrap();
+        GitOperations::commit(&hidden_dir, "Initial commit").unwrap();
+        GitOperations::push(&hidden_dir).unwrap();
+        manager.rollback_hidden_repository(&project_path, ".cursor", cursor, &name, "acme", true).await.unwrap();
+        assert!(!bare_path(cursor).exists());
+        let trashed = trash();
+        assert_eq!(trashed.len(), 1);
+        assert!(trashed[0].ends_with(&format!("-{:x}.git", md5::compute(cursor.as_bytes()))));
+        
+        // 不是本次运行创建的仓库（即使为空）也不删除
+        let vscode = "github.com:user/repo/.vscode";
+        let name = manager.create_hidden_repository(&project_path, ".vscode", vscode, "acme", false, None).await.unwrap();
+        let mut manager = new_manager().await;
+        manager.rollback_hidden_repository(&project_path, ".vscode", vscode, &name, "acme", true).await.unwrap();
+        assert!(!bare_path(vscode).exists());
+        assert_eq!(trash().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_user_fallback_records_actual_owner() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("project");
+        let repo = GitOperations::init_repository(&project_path).unwrap();
+        repo.remote("origin", "git@github.com:user/repo.git").unwrap();
+        repo.config().unwrap().set_str("user.name", "testuser").unwrap();
+
+        let key = "github.com:user/repo/.kiro";
+        let repo_name = format!("{:x}", md5::compute(key.as_bytes()));
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/user"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"login": "alice"})))
+            .mount(&server).await;
+        Mock::given(method("POST")).and(path("/orgs/acme/repos"))
+            .respond_with(ResponseTemplate::new(403).set_body_json(serde_json::json!({
+                "message": "Members of this organization cannot create private repositories"
+            })))
+            .mount(&server).await;
+        Mock::given(method("POST")).and(path("/user/repos"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({"name": repo_name})))
+            .expect(1)
+            .mount(&server).await;
+        Mock::given(method("DELETE")).and(path(format!("/repos/alice/{}", repo_name)))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server).await;
+
+        let config: DotConfig = serde_json::from_value(serde_json::json!({
+            "authorized_organizations": ["acme"],
+            "default_organization": "acme",
+            "remote_cleanup": "delete",
+            "organizations": {"acme": {
+                "api_base_url": server.uri(),
+                "token": "test-token",
+                "protocol": "https",
+                "allow_user_fallback": true
+            }}
+        })).unwrap();
+        let config = ConfigManager::new(temp_dir.path().join("dot.conf"), config);
+        let store = Box::new(LocalIndexStore::new(temp_dir.path().join("index")));
+        let index_manager = IndexManager::with_store("acme".to_string(), store).await.unwrap();
+        let mut manager = RepositoryManager::new(config, index_manager);
+
+        manager.create_hidden_repository(&project_path, ".kiro", key, "acme", false, None).await.unwrap();
+
+        // 组织仍然是 acme（决定平台和令牌），远程仓库按实际所在的个人账户访问
+        let registration = manager.index_manager.projects().find(|p| p.repository_key == key).unwrap().clone();
+        assert_eq!(registration.organization, "acme");
+        assert_eq!(registration.owner.as_deref(), Some("alice"));
+        let expected_url = format!("https://127.0.0.1:{}/alice/{}.git", server.address().port(), repo_name);
+        assert_eq!(manager.generate_hidden_repo_url(&registration), expected_url);
+        assert_eq!(GitOperations::get_remote_origin(project_path.join(".kiro")).unwrap(), expected_url);
+
+        // 回滚时删除的是个人账户下的仓库
+        manager.rollback_hidden_repository(&project_path, ".kiro", key, &repo_name, "acme", true).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_resolve_project() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = ConfigManager::new(temp_dir.path().join("dot.conf"), DotConfig::default());
+        let store = Box::new(LocalIndexStore::new(temp_dir.path().join("index")));
+        let mut index_manager = IndexManager::with_store("acme".to_string(), store).await.unwrap();
+        for (base_key, directory) in [
+            ("github.com:user/repo", ".kiro"),
+            ("github.com:user/repo", ".cursor"),
+            ("github.com:user/repo2", ".kiro"),
+            ("github.com:other/repo", ".kiro"),
+        ] {
+            index_manager.register_project(ProjectRegistration {
+                repository_key: format!("{}/{}", base_key, directory),
+                repository_name: String::new(),
+                organization: "acme".to_string(),
+                owner: None,
+                git_user: "testuser".to_string(),
+                project_git_path: format!("git@{}.git", base_key),
+                project_disk_path: String::new(),
+                hidden_directory: directory.to_string(),
+                created_at: chrono::Utc::now(),
+                remote_url: None,
+                shared: None,
+            }).await.unwrap();
+        }
+        let manager = RepositoryManager::new(config, index_manager);
+        
+        let (base_key, registrations) = manager.resolve_project("user/repo").unwrap();
+        assert_eq!(base_key, "github.com:user/repo");
+        assert_eq!(registrations.len(), 2);
+        
+        let (base_key, _) = manager.resolve_project("git@github.com:user/repo2.git").unwrap();
+        assert_eq!(base_key, "github.com:user/repo2");
+        
+        // 有歧义或不存在的项目
+        assert!(manager.resolve_project("repo").is_err());
+        assert!(manager.resolve_project("user/missing").is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_deinit_hidden_repository() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("project");
+        let repo = GitOperations::init_repository(&project_path).unwrap();
+        repo.remote("origin", "git@github.com:user/repo.git").unwrap();
+        repo.config().unwrap().set_str("user.name", "testuser").unwrap();
+        
+        let root = temp_dir.path().join("remotes");
+        let config: DotConfig = serde_json::from_value(serde_json::json!({
+            "authorized_organizations": ["acme"],
+            "default_organization": "acme",
+            "remote_cleanup": "archive",
+            "organizations": {"acme": {"forge": "local", "root": root}}
+        })).unwrap();
+        let config = ConfigManager::new(temp_dir.path().join("dot.conf"), config);
+        let store = Box::new(LocalIndexStore::new(temp_dir.path().join("index")));
+        let index_manager = IndexManager::with_store("acme".to_string(), store).await.unwrap();
+        let mut manager = RepositoryManager::new(config, index_manager);
+        let bare_path = |key: &str| root.join("acme").join(format!("{:x}.git", md5::compute(key.as_bytes())));
+        
+        let mut keys = Vec::new();
+        for directory in [".kiro", ".cursor"] {
+            let key = format!("github.com:user/repo/{}", directory);
+            manager.create_hidden_repository(&project_path, directory, &key, "acme", false, None).await.unwrap();
+            let hidden_dir = project_path.join(directory);
+            std::fs::write(hidden_dir.join("notes.md"), "hello").unwrap();
+            GitOperations::add_all(&hidden_dir).unwrap();
+            GitOperations::commit(&hidden_dir, "Initial commit").unwrap();
+            GitOperations::push(&hidden_dir).unwrap();
+            keys.push(key);
+        }
+        let (kiro, cursor) = (&keys[0], &keys[1]);
+        
+        // 有未提交的更改时拒绝删除本地目录
+        std::fs::write(project_path.join(".kiro").join("draft.md"), "wip").unwrap();
+        let result = manager.deinit_hidden_repository(&project_path, ".kiro", false, DeinitOptions::default()).await;
+        assert!(result.is_err());
+        assert!(manager.index_manager.project_exists(kiro));
+        
+        // 并入父仓库：删除 .git 和忽略规则，保留文件，远程仓库改名保留
+        std::fs::write(project_path.join(".gitignore"), "target/\n/.kiro/\n").unwrap();
+        let options = DeinitOptions { fold_into_parent: true, ..Default::default() };
+        manager.deinit_hidden_repository(&project_path, ".kiro", false, options).await.unwrap();
+        assert!(!project_path.join(".kiro").join(".git").exists());
+        assert!(project_path.join(".kiro").join("draft.md").exists());
+        assert_eq!(std::fs::read_to_string(project_path.join(".gitignore")).unwrap(), "target/\n");
+        assert!(!manager.index_manager.project_exists(kiro));
+        assert!(!bare_path(kiro).exists());
+        
+        // 远程仓库处理失败时，本地目录和注册信息一起回滚
+        std::fs::remove_dir_all(bare_path(cursor)).unwrap();
+        let result = manager.deinit_hidden_repository(&project_path, ".cursor", false, DeinitOptions::default()).await;
+        assert!(result.is_err());
+        assert!(project_path.join(".cursor").join("notes.md").exists());
+        assert!(manager.index_manager.project_exists(cursor));
+        
+        // 保留远程仓库：只删除本地目录和注册信息
+        let options = DeinitOptions { keep_remote: true, ..Default::default() };
+        manager.deinit_hidden_repository(&project_path, ".cursor", false, options).await.unwrap();
+        assert!(!project_path.join(".cursor").exists());
+        assert!(!manager.index_manager.project_exists(cursor));
+        assert!(!project_path.join(".git").join("dot").join("deinit").read_dir().unwrap().any(|_| true));
+    }
+    
+    #[tokio::test]
+    async fn test_rename_hidden_repository() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("project");
+        let repo = GitOperations::init_repository(&project_path).unwrap();
+        repo.remote("origin", "git@github.com:user/repo.git").unwrap();
+        repo.config().unwrap().set_str("user.name", "testuser").unwrap();
+        
+        let root = temp_dir.path().join("remotes");
+        let config: DotConfig = serde_json::from_value(serde_json::json!({
+            "authorized_organizations": ["acme"],
+            "default_organization": "acme",
+            "organizations": {"acme": {"forge": "local", "root": root}}
+        })).unwrap();
+        let config = ConfigManager::new(temp_dir.path().join("dot.conf"), config);
+        let store = Box::new(LocalIndexStore::new(temp_dir.path().join("index")));
+        let index_manager = IndexManager::with_store("acme".to_string(), store).await.unwrap();
+        let mut manager = RepositoryManager::new(config, index_manager);
+        let bare_path = |key: &str| root.join("acme").join(format!("{:x}.git", md5::compute(key.as_bytes())));
+        
+        let kiro = "github.com:user/repo/.kiro";
+        manager.create_hidden_repository(&project_path, ".kiro", kiro, "acme", false, None).await.unwrap();
+        let hidden_dir = project_path.join(".kiro");
+        std::fs::write(hidden_dir.join("notes.md"), "hello").unwrap();
+        GitOperations::add_all(&hidden_dir).unwrap();
+        GitOperations::commit(&hidden_dir, "Initial commit").unwrap();
+        GitOperations::push(&hidden_dir).unwrap();
+        std::fs::write(project_path.join(".gitignore"), "/.kiro/\n").unwrap();
+        
+        manager.rename_hidden_repository(&project_path, ".kiro", ".kiro-ai", false).await.unwrap();
+        let renamed = "github.com:user/repo/.kiro-ai";
+        let renamed_dir = project_path.join(".kiro-ai");
+        assert!(!hidden_dir.exists());
+        assert!(renamed_dir.join("notes.md").exists());
+        assert!(!manager.index_manager.project_exists(kiro));
+        assert!(manager.index_manager.project_exists(renamed));
+        assert!(!bare_path(kiro).exists());
+        assert!(git2::Repository::open_bare(bare_path(renamed)).unwrap().head().is_ok());
+        assert_eq!(
+            GitOperations::get_remote_origin(&renamed_dir).unwrap(),
+            format!("file://{}", bare_path(renamed).display())
+        );
+        assert_eq!(std::fs::read_to_string(project_path.join(".gitignore")).unwrap(), "/.kiro-ai/\n");
+        
+        // 远程改名失败时全部回滚
+        let cursor = "github.com:user/repo/.cursor";
+        manager.create_hidden_repository(&project_path, ".cursor", cursor, "acme", false, None).await.unwrap();
+        std::fs::create_dir_all(bare_path("github.com:user/repo/.cursor-ai")).unwrap();
+        let result = manager.rename_hidden_repository(&project_path, ".cursor", ".cursor-ai", false).await;
+        assert!(result.is_err());
+        assert!(project_path.join(".cursor").exists());
+        assert!(!project_path.join(".cursor-ai").exists());
+        assert!(manager.index_manager.project_exists(cursor));
+        assert!(!manager.index_manager.project_exists("github.com:user/repo/.cursor-ai"));
+        
+        // 目标已注册或已存在
+        assert!(manager.rename_hidden_repository(&project_path, ".cursor", ".kiro-ai", false).await.is_err());
+    }
+    
+    #[test]
+    fn test_renamed_remote_url() {
+        assert_eq!(renamed_remote_url("git@github.com:acme/abc.git", "abc", "xyz").unwrap(), "git@github.com:acme/xyz.git");
+        assert_eq!(renamed_remote_url("git@dot-abc:acme/abc.git", "abc", "xyz").unwrap(), "git@dot-abc:acme/xyz.git");
+        assert_eq!(renamed_remote_url("https://example.com/acme/abc", "abc", "xyz").unwrap(), "https://example.com/acme/xyz");
+        assert!(renamed_remote_url("git@github.com:acme/xabc.git", "abc", "xyz").is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_relink_moved_project() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("project");
+        let repo = GitOperations::init_repository(&project_path).unwrap();
+        repo.remote("origin", "git@github.com:user/repo.git").unwrap();
+        repo.config().unwrap().set_str("user.name", "testuser").unwrap();
+        
+        let root = temp_dir.path().join("remotes");
+        let config: DotConfig = serde_json::from_value(serde_json::json!({
+            "authorized_organizations": ["acme"],
+            "default_organization": "acme",
+            "organizations": {"acme": {"forge": "local", "root": root}}
+        })).unwrap();
+        let config = ConfigManager::new(temp_dir.path().join("dot.conf"), config);
+        let store = Box::new(LocalIndexStore::new(temp_dir.path().join("index")));
+        let index_manager = IndexManager::with_store("acme".to_string(), store).await.unwrap();
+        let mut manager = RepositoryManager::new(config, index_manager);
+        let bare_path = |key: &str| root.join("acme").join(format!("{:x}.git", md5::compute(key.as_bytes())));
+        
+        let kiro = "github.com:user/repo/.kiro";
+        manager.create_hidden_repository(&project_path, ".kiro", kiro, "acme", false, None).await.unwrap();
+        
+        // 父仓库改名后能检测到旧的注册信息
+        repo.remote_set_url("origin", "git@github.com:user/renamed.git").unwrap();
+        assert!(!manager.is_dot_initialized(&project_path).await.unwrap());
+        assert_eq!(manager.detect_moved_project(&project_path).unwrap(), "git@github.com:user/repo.git");
+        
+        // 改名远程仓库
+        let renamed = "github.com:user/renamed/.kiro";
+        manager.relink_hidden_repositories(&project_path, "git@github.com:user/repo.git", true, false).await.unwrap();
+        assert!(!manager.index_manager.project_exists(kiro));
+        let registration = manager.index_manager.projects().find(|p| p.repository_key == renamed).unwrap();
+        assert_eq!(registration.project_git_path, "git@github.com:user/renamed.git");
+        assert!(!bare_path(kiro).exists());
+        assert!(bare_path(renamed).exists());
+        assert_eq!(
+            GitOperations::get_remote_origin(project_path.join(".kiro")).unwrap(),
+            format!("file://{}", bare_path(renamed).display())
+        );
+        assert!(manager.is_dot_initialized(&project_path).await.unwrap());
+        
+        // 不改名远程仓库时保留原来的仓库名
+        repo.remote_set_url("origin", "git@github.com:team/renamed.git").unwrap();
+        manager.relink_hidden_repositories(&project_path, "github.com:user/renamed", false, false).await.unwrap();
+        let registration = manager.index_manager.projects().find(|p| p.repository_key == "github.com:team/renamed/.kiro").unwrap();
+        assert_eq!(registration.remote_name(), format!("{:x}", md5::compute(renamed)));
+        assert!(bare_path(renamed).exists());
+        
+        // 旧 URL 下已经没有注册信息
+        assert!(manager.relink_hidden_repositories(&project_path, "git@github.com:user/repo.git", false, false).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_moved_project_with_nested_hidden_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("project");
+        let repo = GitOperations::init_repository(&project_path).unwrap();
+        repo.remote("origin", "git@github.com:user/repo.git").unwrap();
+        repo.config().unwrap().set_str("user.name", "testuser").unwrap();
+
+        let root = temp_dir.path().join("remotes");
+        let config: DotConfig = serde_json::from_value(serde_json::json!({
+            "authorized_organizations": ["acme"],
+            "default_organization": "acme",
+            "organizations": {"acme": {"forge": "local", "root": root}}
+        })).unwrap();
+        let config = ConfigManager::new(temp_dir.path().join("dot.conf"), config);
+        let store = Box::new(LocalIndexStore::new(temp_dir.path().join("index")));
+        let index_manager = IndexManager::with_store("acme".to_string(), store).await.unwrap();
+        let mut manager = RepositoryManager::new(config, index_manager);
+
+        let cache = "github.com:user/repo/tools/.cache";
+        manager.create_hidden_repository(&project_path, "tools/.cache", cache, "acme", false, None).await.unwrap();
+
+        // 嵌套的隐藏目录不在项目根目录下，也能检测到旧的注册信息
+        repo.remote_set_url("origin", "git@github.com:user/renamed.git").unwrap();
+        assert_eq!(manager.detect_moved_project(&project_path).unwrap(), "git@github.com:user/repo.git");
+
+        // 新 Key 已经记录在项目清单中时不能覆盖
+        let registration = manager.index_manager.projects().find(|p| p.repository_key == cache).unwrap();
+        let renamed = "github.com:user/renamed/tools/.cache";
+        let mut manifest = ProjectManifest::default();
+        manifest.register(ProjectRegistration {
+            repository_key: renamed.to_string(),
+            project_git_path: "git@github.com:user/renamed.git".to_string(),
+            ..registration
+        }).unwrap();
+        manifest.save(&project_path).unwrap();
+        match manager.relink_hidden_repositories(&project_path, "git@github.com:user/repo.git", false, false).await {
+            Err(RepositoryError::ProjectAlreadyExists(key)) => assert_eq!(key, renamed),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(manager.index_manager.project_exists(cache));
+    }
+
+    #[tokio::test]
+    async fn test_adopt_existing_repository() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("project");
+        let repo = GitOperations::init_repository(&project_path).unwrap();
+        repo.remote("origin", "git@github.com:user/repo.git").unwrap();
+        repo.config().unwrap().set_str("user.name", "testuser").unwrap();
+        
+        let root = temp_dir.path().join("remotes");
+        let config: DotConfig = serde_json::from_value(serde_json::json!({
+            "authorized_organizations": ["acme"],
+            "default_organization": "acme",
+            "organizations": {"acme": {"forge": "local", "root": root}}
+        })).unwrap();
+        let config = ConfigManager::new(temp_dir.path().join("dot.conf"), config);
+        let store = Box::new(LocalIndexStore::new(temp_dir.path().join("index")));
+        let index_manager = IndexManager::with_store("acme".to_string(), store).await.unwrap();
+        let mut manager = RepositoryManager::new(config, index_manager);
+        
+        // 组织之外的已有仓库
+        let elsewhere = temp_dir.path().join("elsewhere").join("kiro-notes.git");
+        git2::Repository::init_bare(&elsewhere).unwrap();
+        let url = format!("file://{}", elsewhere.display());
+        let hidden_dir = project_path.join(".kiro");
+        GitOperations::init_repository(&hidden_dir).unwrap().remote("origin", &url).unwrap();
+        
+        let kiro = "github.com:user/repo/.kiro";
+        let name = manager.adopt_hidden_repository(&project_path, ".kiro", kiro, "acme", false).await.unwrap();
+        assert_eq!(name, "kiro-notes");
+        let registration = manager.index_manager.projects().find(|p| p.repository_key == kiro).unwrap().clone();
+        assert!(registration.is_adopted());
+        assert_eq!(manager.generate_hidden_repo_url(&registration), url);
+        assert!(manager.hidden_repo_credentials(&registration).is_none());
+        assert!(!root.join("acme").exists());
+        
+        // 无法访问的 origin 和不是 git 仓库的目录都不登记
+        let cursor_dir = project_path.join(".cursor");
+        GitOperations::init_repository(&cursor_dir).unwrap()
+            .remote("origin", &format!("file://{}", temp_dir.path().join("missing.git").display())).unwrap();
+        let cursor = "github.com:user/repo/.cursor";
+        assert!(manager.adopt_hidden_repository(&project_path, ".cursor", cursor, "acme", false).await.is_err());
+        assert!(!manager.index_manager.project_exists(cursor));
+        std::fs::create_dir_all(project_path.join(".vscode")).unwrap();
+        let vscode = "github.com:user/repo/.vscode";
+        assert!(manager.adopt_hidden_repository(&project_path, ".vscode", vscode, "acme", false).await.is_err());
+        
+        // 解除管理时不处理接管的远程仓库
+        let options = DeinitOptions { keep_local: true, ..Default::default() };
+        manager.deinit_hidden_repository(&project_path, ".kiro", false, options).await.unwrap();
+        assert!(!manager.index_manager.project_exists(kiro));
+        assert!(elsewhere.exists());
+    }
+    
+    #[tokio::test]
+    async fn test_shared_hidden_repository() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = |name: &str| {
+            let path = temp_dir.path().join(name);
+            let repo = GitOperations::init_repository(&path).unwrap();
+            repo.remote("origin", &format!("git@github.com:user/{}.git", name)).unwrap();
+            repo.config().unwrap().set_str("user.name", "testuser").unwrap();
+            path
+        };
+        let api = project("api");
+        let web = project("web");
+        
+        let root = temp_dir.path().join("remotes");
+        let config: DotConfig = serde_json::from_value(serde_json::json!({
+            "authorized_organizations": ["acme"],
+            "default_organization": "acme",
+            "organizations": {"acme": {"forge": "local", "root": root}}
+        })).unwrap();
+        let config = ConfigManager::new(temp_dir.path().join("dot.conf"), config);
+        let store = Box::new(LocalIndexStore::new(temp_dir.path().join("index")));
+        let index_manager = IndexManager::with_store("acme".to_string(), store).await.unwrap();
+        let mut manager = RepositoryManager::new(config, index_manager);
+        
+        // 共享仓库使用共享名作为远程仓库名
+        let name = manager.create_hidden_repository(&api, ".kiro", "github.com:user/api/.kiro", "acme", false, Some("kiro-steering")).await.unwrap();
+        assert_eq!(name, "kiro-steering");
+        assert!(root.join("acme").join("kiro-steering.git").exists());
+        
+        // 链接到另一个项目时克隆共享仓库
+        manager.link_hidden_repository(&web, "kiro-steering", "docs/.kiro", None).await.unwrap();
+        assert!(web.join("docs").join(".kiro").join(".git").exists());
+        let linked = manager.index_manager.find_projects_by_base_key("github.com:user/web");
+        assert_eq!(linked.len(), 1);
+        assert_eq!(linked[0].shared.as_deref(), Some("kiro-steering"));
+        assert_eq!(manager.index_manager.shared_repository("kiro-steering").unwrap().links.len(), 2);
+        assert!(manager.link_hidden_repository(&web, "kiro-steering", "docs/.kiro", None).await.is_err());
+        assert!(manager.link_hidden_repository(&web, "missing", ".kiro", None).await.is_err());
+        assert!(manager.link_hidden_repository(&web, "../x", ".kiro", None).await.is_err());
+        
+        // 已有的其他仓库不能被当作共享仓库的检出
+        GitOperations::init_repository(web.join(".cursor")).unwrap().remote("origin", "git@github.com:acme/other.git").unwrap();
+        assert!(manager.link_hidden_repository(&web, "kiro-steering", ".cursor", None).await.is_err());
+        
+        // 解除一个项目的链接不影响远程仓库和其他项目
+        let options = DeinitOptions { keep_local: true, ..Default::default() };
+        manager.deinit_hidden_repository(&web, "docs/.kiro", false, options).await.unwrap();
+        assert!(root.join("acme").join("kiro-steering.git").exists());
+        assert!(manager.index_manager.project_exists("github.com:user/api/.kiro"));
+        assert!(!manager.index_manager.project_exists("github.com:user/web/docs/.kiro"));
+    }
+    
+    #[test]
+    fn test_nested_hidden_directories() {
+        let registered = |dir: &str| ProjectRegistration {
+            repository_key: format!("github.com:user/repo/{}", dir),
+            repository_name: String::new(),
+            organization: "acme".to_string(),
+            owner: None,
+            git_user: "testuser".to_string(),
+            project_git_path: "git@github.com:user/repo.git".to_string(),
+            project_disk_path: String::new(),
+            hidden_directory: dir.to_string(),
+            created_at: chrono::Utc::now(),
+            remote_url: None,
+            shared: None,
+        };
+        let dirs = |dirs: &[&str]| dirs.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+        
+        // 重叠检测
+        assert!(check_overlaps(&dirs(&[".config/app"]), &[registered(".config")]).is_err());
+        assert!(check_overlaps(&dirs(&[".config"]), &[registered(".config/app")]).is_err());
+        assert!(check_overlaps(&dirs(&[".kiro", ".kiro/specs"]), &[]).is_err());
+        assert!(check_overlaps(&dirs(&[".config-app", "config/.app"]), &[registered(".config")]).is_ok());
+        
+        // 文件分配到最深的隐藏目录
+        let hidden = dirs(&[".config", "tools/.config", "tools/.config/app"]);
+        let files = dirs(&["README.md", "./.config/a.json", "tools/.config/app/b.json", "tools/.config", "tools/x.rs"]);
+        let (routed, parent) = route_files(&files, &hidden);
+        assert_eq!(routed[".config"], vec!["a.json"]);
+        assert_eq!(routed["tools/.config"], vec!["."]);
+        assert_eq!(routed["tools/.config/app"], vec!["b.json"]);
+        assert_eq!(parent, vec!["README.md", "tools/x.rs"]);
+        
+        let (routed, parent) = route_files(&dirs(&["."]), &hidden);
+        assert_eq!(routed.len(), 3);
+        assert_eq!(parent, vec!["."]);
+    }
+}
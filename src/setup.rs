@@ -1,5 +1,6 @@
-use crate::config::DotConfig;
+use crate::config::{DotConfig, OrganizationConfig, RemoteCleanup};
 use crate::error::ConfigError;
+use crate::forge::{Forge, GitHubForge};
 use std::io::{self, Write};
 use std::path::PathBuf;
 use std::process::Command;
@@ -20,17 +21,19 @@ impl SetupWizard {
         // 步骤 2: 获取用户的 GitHub 用户名
         let github_username = Self::get_github_username()?;
         
-        // 步骤 3: 询问要使用的组织
+        // 步骤 3: 询问要使用的组织和 GitHub 主机
         let organization = Self::prompt_organization(&github_username)?;
+        let org_config = Self::prompt_github_host()?;
+        let forge = Self::github_forge(&org_config);
         
         // 步骤 4: 询问 GitHub Token（可选）
-        let github_token = Self::prompt_github_token()?;
+        let github_token = Self::prompt_github_token(&forge)?;
         
         // 步骤 5: 创建配置文件
-        Self::create_config(&organization, github_token.as_deref()).await?;
+        Self::create_config(&organization, github_token.as_deref(), org_config).await?;
         
         // 步骤 6: 检查并创建 .index 仓库
-        Self::setup_index_repository(&organization).await?;
+        Self::setup_index_repository(&organization, &forge).await?;
         
         println!();
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
@@ -192,14 +195,48 @@ impl SetupWizard {
         Ok(organization)
     }
     
+    /// 询问 GitHub 主机（GitHub Enterprise Server 使用自己的主机名）
+    fn prompt_github_host() -> Result<OrganizationConfig, ConfigError> {
+        println!("   如果使用 GitHub Enterprise Server，请输入它的主机名。");
+        print!("   GitHub 主机 [github.com]: ");
+        io::stdout().flush()?;
+        
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        
+        let org_config = Self::organization_config_for_host(input.trim());
+        if let Some(api_base_url) = &org_config.api_base_url {
+            println!("   ✓ 将使用 GitHub Enterprise API: {}", api_base_url);
+        }
+        println!();
+        
+        Ok(org_config)
+    }
+    
+    /// GitHub Enterprise Server 的 API 地址为 `https://<host>/api/v3`
+    fn organization_config_for_host(host: &str) -> OrganizationConfig {
+        let host = host.trim_start_matches("https://").trim_end_matches('/');
+        if host.is_empty() || host == "github.com" {
+            return OrganizationConfig::default();
+        }
+        OrganizationConfig {
+            api_base_url: Some(format!("https://{}/api/v3", host)),
+            ..Default::default()
+        }
+    }
+    
+    fn github_forge(org_config: &OrganizationConfig) -> GitHubForge {
+        GitHubForge::new(None, org_config.api_base_url.clone())
+    }
+    
     /// 询问 GitHub Token（可选）
-    fn prompt_github_token() -> Result<Option<String>, ConfigError> {
+    fn prompt_github_token(forge: &GitHubForge) -> Result<Option<String>, ConfigError> {
         println!("🔑 步骤 4/6: 配置 GitHub Token（可选）");
         println!();
         println!("   GitHub Token 用于通过 API 创建远程仓库。");
         println!("   如果不配置，将使用 GitHub CLI (gh) 作为备选方案。");
         println!();
-        println!("   获取 Token: https://github.com/settings/tokens");
+        println!("   获取 Token: {}/settings/tokens", forge.hosts().web_url());
         println!("   需要的权限: repo (Full control of private repositories)");
         println!();
         
@@ -230,7 +267,11 @@ impl SetupWizard {
     }
     
     /// 创建配置文件
-    async fn create_config(organization: &str, github_token: Option<&str>) -> Result<(), ConfigError> {
+    async fn create_config(
+        organization: &str,
+        github_token: Option<&str>,
+        org_config: OrganizationConfig
+    ) -> Result<(), ConfigError> {
         println!("📝 步骤 5/6: 创建配置文件");
         println!();
         
@@ -253,20 +294,27 @@ impl SetupWizard {
             }
         }
         
+        let remote_cleanup = Self::prompt_remote_cleanup()?;
+        
         // 创建配置目录
         if let Some(parent) = config_path.parent() {
             tokio::fs::create_dir_all(parent).await?;
         }
         
         // 创建配置
-        let config = DotConfig {
+        let mut config = DotConfig {
             authorized_organizations: vec![organization.to_string()],
             default_organization: Some(organization.to_string()),
             github_token: github_token.map(|s| s.to_string()),
+            remote_cleanup,
+            ..Default::default()
         };
+        if org_config != OrganizationConfig::default() {
+            config.organizations.insert(organization.to_string(), org_config);
+        }
         
         let content = serde_json::to_string_pretty(&config)
-            .map_err(|e| ConfigError::JsonError(e))?;
+            .map_err(ConfigError::JsonError)?;
         tokio::fs::write(&config_path, content).await?;
         
         println!("   ✓ 配置文件已创建: {}", config_path.display());
@@ -275,13 +323,36 @@ impl SetupWizard {
         Ok(())
     }
     
+    /// 询问回滚和 `dot deinit` 时如何处理远程隐藏仓库
+    fn prompt_remote_cleanup() -> Result<RemoteCleanup, ConfigError> {
+        println!("   回滚或 'dot deinit' 时如何处理远程隐藏仓库？");
+        println!("     delete  - 直接删除（默认）");
+        println!("     archive - 改名为 dot-trash-<时间戳>-<原名> 并归档，保留已推送的内容");
+        println!("     rename  - 只改名，不归档");
+        print!("   请选择 [delete]: ");
+        io::stdout().flush()?;
+        
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        let (remote_cleanup, name) = match input.trim().to_lowercase().as_str() {
+            "archive" => (RemoteCleanup::Archive, "archive"),
+            "rename" => (RemoteCleanup::Rename, "rename"),
+            _ => (RemoteCleanup::Delete, "delete"),
+        };
+        
+        println!("   ✓ 远程仓库处理方式: {}", name);
+        println!();
+        
+        Ok(remote_cleanup)
+    }
+    
     /// 设置 .index 仓库
-    async fn setup_index_repository(organization: &str) -> Result<(), ConfigError> {
+    async fn setup_index_repository(organization: &str, forge: &GitHubForge) -> Result<(), ConfigError> {
         println!("📦 步骤 6/6: 设置索引仓库");
         println!();
         
         let dot_dir = Self::dot_dir()?;
-        let index_path = dot_dir.join(".index");
+        let index_path = dot_dir.join("indexes").join(organization);
         
         // 检查本地 .index 目录是否存在
         if index_path.exists() {
@@ -291,8 +362,12 @@ impl SetupWizard {
             return Ok(());
         }
         
+        if let Some(parent) = index_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        
         // 尝试克隆远程 .index 仓库
-        let remote_url = format!("git@github.com:{}/{}.git", organization, ".index");
+        let remote_url = forge.clone_url(organization, ".index");
         println!("   尝试克隆索引仓库: {}", remote_url);
         
         let clone_result = Command::new("git")
@@ -308,7 +383,7 @@ impl SetupWizard {
                 println!("   索引仓库不存在，正在创建...");
                 println!();
                 println!("   ⚠️  请在 GitHub 上手动创建仓库:");
-                println!("      1. 访问 https://github.com/new");
+                println!("      1. 访问 {}/new", forge.hosts().web_url());
                 println!("      2. Repository name: .index");
                 println!("      3. Owner: {}", organization);
                 println!("      4. 选择 Private");
@@ -371,3 +446,21 @@ impl SetupWizard {
         Ok(home.join(".dot"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_enterprise_host_configuration() {
+        assert_eq!(SetupWizard::organization_config_for_host(""), OrganizationConfig::default());
+        assert_eq!(SetupWizard::organization_config_for_host("github.com"), OrganizationConfig::default());
+        
+        let org_config = SetupWizard::organization_config_for_host("https://ghe.example.com/");
+        assert_eq!(org_config.api_base_url.as_deref(), Some("https://ghe.example.com/api/v3"));
+        
+        let forge = SetupWizard::github_forge(&org_config);
+        assert_eq!(forge.clone_url("acme", ".index"), "git@ghe.example.com:acme/.index.git");
+        assert_eq!(forge.hosts().web_url(), "https://ghe.example.com");
+    }
+}
@@ -0,0 +1,200 @@
+use crate::error::RepositoryError;
+use crate::index::ProjectRegistration;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// 项目在某台机器上的一个本地检出
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkout {
+    pub hostname: String,
+    pub path: PathBuf,
+    pub last_seen: chrono::DateTime<chrono::Utc>,
+}
+
+/// 本地状态（不共享，不进入索引）
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LocalState {
+    /// base key -> 已知的本地检出
+    pub checkouts: HashMap<String, Vec<Checkout>>,
+}
+
+/// 本地状态管理器，数据保存在 ~/.dot/state.json
+pub struct StateManager {
+    state_path: PathBuf,
+    state: LocalState,
+}
+
+impl StateManager {
+    pub fn load() -> Result<Self, RepositoryError> {
+        let home = dirs::home_dir().ok_or(RepositoryError::IoError(
+            std::io::Error::new(std::io::ErrorKind::NotFound, "Home directory not found")
+        ))?;
+        Self::open(home.join(".dot").join("state.json"))
+    }
+
+    pub fn open(state_path: PathBuf) -> Result<Self, RepositoryError> {
+        let state = if state_path.exists() {
+            let content = std::fs::read_to_string(&state_path)?;
+            serde_json::from_str(&content)?
+        } else {
+            LocalState::default()
+        };
+        Ok(Self { state_path, state })
+    }
+
+    /// 当前机器的主机名
+    pub fn hostname() -> String {
+        gethostname::gethostname().to_string_lossy().to_string()
+    }
+
+    /// 记录（或刷新）当前机器上项目的一个检出
+    pub fn record_checkout(&mut self, base_key: &str, path: &Path) -> Result<(), RepositoryError> {
+        let hostname = Self::hostname();
+        let now = chrono::Utc::now();
+        let checkouts = self.state.checkouts.entry(base_key.to_string()).or_default();
+
+        match checkouts.iter_mut().find(|c| c.hostname == hostname && c.path == path) {
+            Some(checkout) => checkout.last_seen = now,
+            None => checkouts.push(Checkout {
+                hostname,
+                path: path.to_path_buf(),
+                last_seen: now,
+            }),
+        }
+
+        self.save()
+    }
+
+    /// 用旧索引中的 `project_disk_path` 补充检出记录
+    ///
+    /// 旧版本把检出位置写在共享索引中，无法区分是哪台机器，因此只在项目还没有任何
+    /// 已知检出、且该路径在本机存在时记录为本机的检出。
+    pub fn seed_legacy_checkouts(
+        &mut self,
+        registrations: impl IntoIterator<Item = ProjectRegistration>,
+    ) -> Result<(), RepositoryError> {
+        let hostname = Self::hostname();
+        let mut changed = false;
+
+        for registration in registrations {
+            let path = PathBuf::from(&registration.project_disk_path);
+            if registration.project_disk_path.is_empty() || !path.is_dir() {
+                continue;
+            }
+            let checkouts = self.state.checkouts.entry(registration.base_key().to_string()).or_default();
+            if checkouts.is_empty() {
+                checkouts.push(Checkout {
+                    hostname: hostname.clone(),
+                    path,
+                    last_seen: registration.created_at,
+                });
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    pub fn checkouts(&self, base_key: &str) -> &[Checkout] {
+        self.state.checkouts.get(base_key).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// 按 base key 查找项目的检出，`query` 可以是完整的 base key、
+    /// 结尾的路径片段（如 `user/repo` 或 `repo`）
+    pub fn find(&self, query: &str) -> Vec<(&str, &Checkout)> {
+        let query = query.trim_end_matches('/');
+        let mut result: Vec<(&str, &Checkout)> = self.state.checkouts
+            .iter()
+            .filter(|(base_key, _)| {
+                base_key.as_str() == query
+                    || base_key.ends_with(&format!("/{}", query))
+                    || base_key.ends_with(&format!(":{}", query))
+            })
+            .flat_map(|(base_key, checkouts)| checkouts.iter().map(move |c| (base_key.as_str(), c)))
+            .collect();
+        result.sort_by(|a, b| a.0.cmp(b.0).then(b.1.last_seen.cmp(&a.1.last_seen)));
+        result
+    }
+
+    fn save(&self) -> Result<(), RepositoryError> {
+        if let Some(parent) = self.state_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(&self.state)?;
+        std::fs::write(&self.state_path, content)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_and_find_checkouts() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_path = temp_dir.path().join("state.json");
+
+        let mut state = StateManager::open(state_path.clone()).unwrap();
+        state.record_checkout("github.com:user/repo", Path::new("/work/repo")).unwrap();
+        state.record_checkout("github.com:user/repo", Path::new("/tmp/repo-copy")).unwrap();
+        // 重复记录只刷新 last_seen
+        state.record_checkout("github.com:user/repo", Path::new("/work/repo")).unwrap();
+        state.record_checkout("github.com:user/other", Path::new("/work/other")).unwrap();
+
+        let reloaded = StateManager::open(state_path).unwrap();
+        assert_eq!(reloaded.checkouts("github.com:user/repo").len(), 2);
+        assert_eq!(reloaded.find("github.com:user/repo").len(), 2);
+        assert_eq!(reloaded.find("user/repo").len(), 2);
+        assert_eq!(reloaded.find("repo").len(), 2);
+        assert_eq!(reloaded.find("other").len(), 1);
+        assert!(reloaded.find("epo").is_empty());
+
+        let (_, checkout) = reloaded.find("other")[0];
+        assert_eq!(checkout.hostname, StateManager::hostname());
+        assert_eq!(checkout.path, PathBuf::from("/work/other"));
+    }
+
+    #[test]
+    fn test_seed_legacy_checkouts() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_path = temp_dir.path().join("state.json");
+        let project_path = temp_dir.path().join("repo");
+        std::fs::create_dir_all(&project_path).unwrap();
+
+        let legacy = |key: &str, disk_path: &Path| ProjectRegistration {
+            repository_key: format!("{}/.kiro", key),
+            repository_name: String::new(),
+            organization: String::new(),
+            owner: None,
+            git_user: "testuser".to_string(),
+            project_git_path: String::new(),
+            project_disk_path: disk_path.to_string_lossy().to_string(),
+            hidden_directory: ".kiro".to_string(),
+            created_at: chrono::Utc::now(),
+            remote_url: None,
+            shared: None,
+        };
+
+        let mut state = StateManager::open(state_path.clone()).unwrap();
+        state.record_checkout("github.com:user/known", Path::new("/work/known")).unwrap();
+        state.seed_legacy_checkouts(vec![
+            legacy("github.com:user/repo", &project_path),
+            // 已有检出记录的项目不变
+            legacy("github.com:user/known", &project_path),
+            // 本机不存在的路径属于其它机器
+            legacy("github.com:user/elsewhere", Path::new("/nonexistent/elsewhere")),
+        ]).unwrap();
+
+        let reloaded = StateManager::open(state_path).unwrap();
+        assert_eq!(reloaded.checkouts("github.com:user/repo")[0].path, project_path);
+        assert_eq!(reloaded.checkouts("github.com:user/known")[0].path, PathBuf::from("/work/known"));
+        assert_eq!(reloaded.checkouts("github.com:user/known").len(), 1);
+        assert!(reloaded.checkouts("github.com:user/elsewhere").is_empty());
+    }
+}
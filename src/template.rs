@@ -0,0 +1,247 @@
+//! 新建隐藏目录的内容模板
+//!
+//! 模板在配置的 `templates` 中按名称定义，来源可以是本地目录或 git URL。
+//! `dot init .kiro --template <name>` 把模板内容（不含 `.git`）复制到新的隐藏仓库，
+//! 并在第一次推送之前创建初始提交。
+
+use crate::error::{ConfigError, RepositoryError};
+use crate::git_operations::GitOperations;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 准备好的模板内容
+pub struct Template {
+    name: String,
+    root: PathBuf,
+    /// git 模板的临时检出，使用完后删除
+    checkout: Option<PathBuf>,
+}
+
+impl Template {
+    /// 准备模板内容：本地目录直接使用，git URL 浅克隆到临时目录
+    pub fn fetch(name: &str, source: &str) -> Result<Self, RepositoryError> {
+        if !is_git_url(source) {
+            let root = expand_home(source)?;
+            if !root.is_dir() {
+                return Err(RepositoryError::IoError(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("Template '{}' directory not found: {}", name, root.display()),
+                )));
+            }
+            return Ok(Self { name: name.to_string(), root, checkout: None });
+        }
+
+        let checkout = std::env::temp_dir().join(format!("dot-template-{}-{}", std::process::id(), name));
+        if checkout.exists() {
+            fs::remove_dir_all(&checkout)?;
+        }
+        println!("Fetching template '{}' from {}", name, source);
+        let output = GitOperations::git_command(None)
+            .args(["clone", "--quiet", "--depth", "1", source])
+            .arg(&checkout)
+            .output()?;
+        if !output.status.success() {
+            let _ = fs::remove_dir_all(&checkout);
+            return Err(RepositoryError::IoError(std::io::Error::other(format!(
+                "Failed to fetch template '{}': {}",
+                name,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))));
+        }
+
+        Ok(Self { name: name.to_string(), root: checkout.clone(), checkout: Some(checkout) })
+    }
+
+    /// 把模板内容复制到隐藏仓库并创建初始提交，返回复制的文件
+    ///
+    /// 目录中已有的文件不会被覆盖。
+    pub fn apply(&self, hidden_dir: &Path) -> Result<Vec<String>, RepositoryError> {
+        let mut copied = Vec::new();
+        copy_tree(&self.root, hidden_dir, "", &mut copied)?;
+        if copied.is_empty() {
+            return Ok(copied);
+        }
+
+        GitOperations::add_files(hidden_dir, &copied)?;
+        GitOperations::commit(hidden_dir, &format!("Initial commit from template {}", self.name))?;
+        Ok(copied)
+    }
+}
+
+impl Drop for Template {
+    fn drop(&mut self) {
+        if let Some(checkout) = &self.checkout {
+            let _ = fs::remove_dir_all(checkout);
+        }
+    }
+}
+
+/// 递归复制目录（跳过 `.git`），`prefix` 为相对于模板根目录的路径
+///
+/// 符号链接按链接本身复制，不跟随到链接目标。
+fn copy_tree(from: &Path, to: &Path, prefix: &str, copied: &mut Vec<String>) -> Result<(), RepositoryError> {
+    let mut entries = fs::read_dir(from)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == ".git" {
+            continue;
+        }
+        let relative = if prefix.is_empty() { name.clone() } else { format!("{}/{}", prefix, name) };
+        let target = to.join(&name);
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            fs::create_dir_all(&target)?;
+            copy_tree(&entry.path(), &target, &relative, copied)?;
+        } else if fs::symlink_metadata(&target).is_ok() {
+            println!("  - Kept existing {}", relative);
+        } else if file_type.is_symlink() {
+            copy_symlink(&entry.path(), &target, &relative)?;
+            copied.push(relative);
+        } else {
+            fs::copy(entry.path(), &target)?;
+            copied.push(relative);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(link: &Path, target: &Path, _relative: &str) -> Result<(), RepositoryError> {
+    std::os::unix::fs::symlink(fs::read_link(link)?, target)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(_link: &Path, _target: &Path, relative: &str) -> Result<(), RepositoryError> {
+    Err(RepositoryError::IoError(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!("Template contains a symbolic link ({}), which is not supported on this platform", relative),
+    )))
+}
+
+/// 模板来源是否为 git URL（其余按本地目录处理）
+fn is_git_url(source: &str) -> bool {
+    if source.contains("://") {
+        return true;
+    }
+    // scp 形式：user@host:path 或 host.example.com:path
+    match source.split_once(':') {
+        Some((host, _)) => (host.contains('@') || host.contains('.')) && !host.contains('/'),
+        None => false,
+    }
+}
+
+/// 展开本地目录开头的 `~`
+fn expand_home(source: &str) -> Result<PathBuf, RepositoryError> {
+    match source.strip_prefix("~/") {
+        Some(rest) => {
+            let home = dirs::home_dir().ok_or(ConfigError::HomeDirectoryNotFound)?;
+            Ok(home.join(rest))
+        }
+        None => Ok(PathBuf::from(source)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn hidden_repository(temp_dir: &TempDir) -> PathBuf {
+        let hidden_dir = temp_dir.path().join("project").join(".kiro");
+        let repo = GitOperations::init_repository(&hidden_dir).unwrap();
+        repo.config().unwrap().set_str("user.name", "testuser").unwrap();
+        repo.config().unwrap().set_str("user.email", "test@example.com").unwrap();
+        hidden_dir
+    }
+
+    #[test]
+    fn test_git_url_detection() {
+        assert!(is_git_url("git@github.com:acme/kiro-template.git"));
+        assert!(is_git_url("https://github.com/acme/kiro-template.git"));
+        assert!(is_git_url("file:///srv/templates/kiro.git"));
+        assert!(is_git_url("github.com:acme/kiro-template.git"));
+        assert!(!is_git_url("./templates/kiro:v2"));
+        assert!(!is_git_url("~/dot-templates/kiro"));
+        assert!(!is_git_url("/srv/templates/kiro"));
+        assert!(!is_git_url("C:\\templates\\kiro"));
+    }
+
+    #[test]
+    fn test_apply_local_template() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("template");
+        fs::create_dir_all(source.join("steering")).unwrap();
+        fs::create_dir_all(source.join(".git")).unwrap();
+        fs::write(source.join("README.md"), "template").unwrap();
+        fs::write(source.join("steering").join("product.md"), "# Product").unwrap();
+        fs::write(source.join(".git").join("HEAD"), "ignored").unwrap();
+
+        let hidden_dir = hidden_repository(&temp_dir);
+        fs::write(hidden_dir.join("README.md"), "mine").unwrap();
+
+        let template = Template::fetch("kiro-default", source.to_str().unwrap()).unwrap();
+        let copied = template.apply(&hidden_dir).unwrap();
+        assert_eq!(copied, vec!["steering/product.md"]);
+        assert_eq!(fs::read_to_string(hidden_dir.join("README.md")).unwrap(), "mine");
+        assert_eq!(fs::read_to_string(hidden_dir.join("steering").join("product.md")).unwrap(), "# Product");
+
+        // 初始提交只包含模板中的文件
+        let repo = git2::Repository::open(&hidden_dir).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.message(), Some("Initial commit from template kiro-default"));
+        assert!(head.tree().unwrap().get_path(Path::new("steering/product.md")).is_ok());
+        assert!(head.tree().unwrap().get_path(Path::new("README.md")).is_err());
+
+        assert!(Template::fetch("missing", temp_dir.path().join("missing").to_str().unwrap()).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_apply_template_copies_symlinks_as_links() {
+        let temp_dir = TempDir::new().unwrap();
+        let outside = temp_dir.path().join("secret.txt");
+        fs::write(&outside, "secret").unwrap();
+        let source = temp_dir.path().join("template");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("AGENTS.md"), "# Agents").unwrap();
+        std::os::unix::fs::symlink("AGENTS.md", source.join("CLAUDE.md")).unwrap();
+        std::os::unix::fs::symlink(&outside, source.join("secret.txt")).unwrap();
+
+        let hidden_dir = hidden_repository(&temp_dir);
+        let template = Template::fetch("links", source.to_str().unwrap()).unwrap();
+        assert_eq!(template.apply(&hidden_dir).unwrap(), vec!["AGENTS.md", "CLAUDE.md", "secret.txt"]);
+
+        // 链接本身被复制，链接目标的内容不会进入隐藏仓库
+        assert_eq!(fs::read_link(hidden_dir.join("CLAUDE.md")).unwrap(), PathBuf::from("AGENTS.md"));
+        assert_eq!(fs::read_link(hidden_dir.join("secret.txt")).unwrap(), outside);
+        let repo = git2::Repository::open(&hidden_dir).unwrap();
+        let tree = repo.head().unwrap().peel_to_commit().unwrap().tree().unwrap();
+        let entry = tree.get_path(Path::new("secret.txt")).unwrap();
+        assert_eq!(entry.filemode(), i32::from(git2::FileMode::Link));
+    }
+
+    #[test]
+    fn test_apply_git_template() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("template");
+        let repo = GitOperations::init_repository(&source).unwrap();
+        repo.config().unwrap().set_str("user.name", "testuser").unwrap();
+        fs::write(source.join(".gitignore"), "*.log\n").unwrap();
+        GitOperations::add_files(&source, &[".gitignore".to_string()]).unwrap();
+        GitOperations::commit(&source, "template").unwrap();
+
+        let url = format!("file://{}", source.display());
+        let template = Template::fetch("kiro-team", &url).unwrap();
+        let checkout = template.checkout.clone().unwrap();
+        let hidden_dir = hidden_repository(&temp_dir);
+        assert_eq!(template.apply(&hidden_dir).unwrap(), vec![".gitignore"]);
+
+        // 临时检出在使用完后删除
+        drop(template);
+        assert!(!checkout.exists());
+    }
+}